@@ -6,10 +6,8 @@ use std::{
     time::Instant,
 };
 
-use crate::{
-    nulibc::{NULIBC, NULIBCH},
-    Config,
-};
+use crate::{status, Config};
+use neit::nulibc::{NULIBC, NULIBCH};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
@@ -55,40 +53,65 @@ fn needs_update(file_path: &str, new_content: &str, hash_map: &HashMap<String, S
     }
 }
 
-fn ensure_source_file(code: &str, hash_map: &mut HashMap<String, String>) -> Result<(), Error> {
+fn ensure_source_file(
+    code: &str,
+    hash_map: &mut HashMap<String, String>,
+    config: &Config,
+) -> Result<(), Error> {
     if needs_update(SRC_FILE, code, hash_map) {
-        println!("[*] Source file '{}' has changed. Updating file.", SRC_FILE);
+        status(
+            config,
+            format!("[*] Source file '{}' has changed. Updating file.", SRC_FILE),
+        );
         File::create(SRC_FILE)?.write_all(code.as_bytes())?;
         let new_hash = compute_hash(code);
         hash_map.insert(SRC_FILE.to_string(), new_hash);
     } else {
-        println!("[*] Source file '{}' is up-to-date.", SRC_FILE);
+        status(
+            config,
+            format!("[*] Source file '{}' is up-to-date.", SRC_FILE),
+        );
     }
     Ok(())
 }
 
-fn create_nulibc_files(hash_map: &mut HashMap<String, String>) -> Result<(), Error> {
+fn create_nulibc_files(
+    hash_map: &mut HashMap<String, String>,
+    config: &Config,
+) -> Result<(), Error> {
     if needs_update(NULIBC_C, NULIBC, hash_map) {
-        println!(
-            "[*] Nulibc source '{}' has changed. Updating file.",
-            NULIBC_C
+        status(
+            config,
+            format!(
+                "[*] Nulibc source '{}' has changed. Updating file.",
+                NULIBC_C
+            ),
         );
         File::create(NULIBC_C)?.write_all(NULIBC.as_bytes())?;
         let new_hash = compute_hash(NULIBC);
         hash_map.insert(NULIBC_C.to_string(), new_hash);
     } else {
-        println!("[*] Nulibc source '{}' is up-to-date.", NULIBC_C);
+        status(
+            config,
+            format!("[*] Nulibc source '{}' is up-to-date.", NULIBC_C),
+        );
     }
     if needs_update(NULIBC_H, NULIBCH, hash_map) {
-        println!(
-            "[*] Nulibc header '{}' has changed. Updating file.",
-            NULIBC_H
+        status(
+            config,
+            format!(
+                "[*] Nulibc header '{}' has changed. Updating file.",
+                NULIBC_H
+            ),
         );
         File::create(NULIBC_H)?.write_all(NULIBCH.as_bytes())?;
         let new_hash = compute_hash(NULIBCH);
         hash_map.insert(NULIBC_H.to_string(), new_hash);
     } else {
-        println!("[*] Nulibc header '{}' is up-to-date.", NULIBC_H);
+        status(
+            config,
+            format!("[*] Nulibc header '{}' is up-to-date.", NULIBC_H),
+        );
     }
     Ok(())
 }
@@ -155,8 +178,8 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
     let overall_start = Instant::now();
     let mut hash_map = read_hashes();
 
-    ensure_source_file(code, &mut hash_map)?;
-    create_nulibc_files(&mut hash_map)?;
+    ensure_source_file(code, &mut hash_map, config)?;
+    create_nulibc_files(&mut hash_map, config)?;
 
     // Compiler caching: use key "compiler" in hash_map to store the selected compiler.
     let comp = if let Some(comp_cached) = hash_map.get(COMPILER_KEY) {
@@ -164,11 +187,11 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
     } else {
         let comp_found = find_compiler("zig")
             .or_else(|| {
-                println!("[!] 'zig' not found, trying 'clang'...");
+                status(config, "[!] 'zig' not found, trying 'clang'...");
                 find_compiler("clang")
             })
             .or_else(|| {
-                println!("[!] 'clang' not found, trying 'gcc'...");
+                status(config, "[!] 'clang' not found, trying 'gcc'...");
                 find_compiler("gcc")
             })
             .unwrap_or_else(|| {
@@ -179,7 +202,7 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
         comp_found
     };
 
-    println!("[*] Compiler selected: {}", comp);
+    status(config, format!("[*] Compiler selected: {}", comp));
 
     // Always use the output filename exactly as specified in config.out.
     let mut out_file = config.out.to_string();
@@ -191,9 +214,12 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
     }
 
     if !needs_recompile(&out_file) {
-        println!(
-            "[*] No changes detected. Reusing existing output file: '{}'",
-            out_file
+        status(
+            config,
+            format!(
+                "[*] No changes detected. Reusing existing output file: '{}'",
+                out_file
+            ),
         );
         return Ok(());
     }
@@ -204,7 +230,7 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
     // For each target, compile using the selected compiler.
     // However, the final output file is always named as config.out.
     for target in targets {
-        println!("[*] Compiling for target: {}", target);
+        status(config, format!("[*] Compiling for target: {}", target));
         let comp_clone = comp.clone();
         let target_arg = translate_target(&comp_clone, &target);
         let static_flag = static_flag;
@@ -248,9 +274,12 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
             );
             exit(1);
         } else {
-            println!(
-                "[*] Success {}: {} ({} ms)",
-                target, current_out, compile_time
+            status(
+                config,
+                format!(
+                    "[*] Success {}: {} ({} ms)",
+                    target, current_out, compile_time
+                ),
             );
         }
     }
@@ -260,6 +289,9 @@ pub fn linux_b_64(code: &String, config: &Config) -> Result<(), Error> {
     }
 
     let overall_time = overall_start.elapsed().as_millis();
-    println!("[*] Total compilation time: {} ms", overall_time);
+    status(
+        config,
+        format!("[*] Total compilation time: {} ms", overall_time),
+    );
     Ok(())
 }
@@ -1,101 +1,197 @@
 use crate::{
-    err_system::err_types::ErrTypes,
-    helpers::c_condmk::mk_c_cond,
-    parse_systems::{PrintTokTypes, Variables, AST},
+    helpers::{
+        c_condmk::mk_c_cond,
+        c_exprmk::{c_escape, c_fn_name, c_type, mk_c_expr, mk_c_str, mk_c_value},
+        fn_calls::{fn_signature, fn_tag, lookup_fn},
+        symbol_table::{ScopeKind, StructDef, SymbolTable},
+        Expr,
+    },
+    parse_systems::{parse3::counts_down, FuncDef, PrintTokTypes, Variables, AST},
+    type_system::{elem_type, infer},
 };
 use std::collections::HashMap;
 use std::fmt::Write;
 
-fn contains_letters(s: &str) -> bool {
-    s.chars().any(|c| c.is_alphabetic())
+fn write_decl(code: &mut String, declared_type: &str, name: &str, value: &str) {
+    if let Some(real_type) = declared_type.strip_prefix("const;") {
+        writeln!(code, "const {} {} = {};", c_type(real_type), name, value).unwrap();
+    } else {
+        writeln!(code, "{} {} = {};", c_type(declared_type), name, value).unwrap();
+    }
+}
+
+fn fn_header(def: &FuncDef) -> String {
+    let params = if def.params.is_empty() {
+        "void".to_string()
+    } else {
+        def.params
+            .iter()
+            .map(|(name, typ)| format!("{} {}", c_type(typ), name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{} {}({})",
+        c_type(def.ret.unwrap_or("void")),
+        c_fn_name(&def.name),
+        params
+    )
 }
 
-fn evaluate_numeric_expr(expr: &str) -> Option<f64> {
-    let expr = expr.replace(" ", "");
-    let expr = expr.replace("-", "+-");
-    let tokens: Vec<&str> = expr.split('+').filter(|s| !s.is_empty()).collect();
-    let mut result = 0.0;
-    for token in tokens {
-        if let Ok(val) = token.parse::<f64>() {
-            result += val;
-        } else {
-            return None;
+/// Collects every function definition, including ones declared inside blocks, so they can be
+/// emitted before `main`.
+fn collect_funcs<'a>(ast: &'a [AST], funcs: &mut Vec<&'a FuncDef>) {
+    for node in ast {
+        match node {
+            AST::Func(def) => funcs.push(def),
+            AST::While(body, _)
+            | AST::IF(body, _)
+            | AST::ElseIf(body, _)
+            | AST::Else(body)
+            | AST::For { body, .. } => collect_funcs(body, funcs),
+            _ => {}
         }
     }
-    Some(result)
 }
 
-fn eval_math_expr(expr: &str, math_values: &HashMap<String, f64>) -> Option<f64> {
-    if !contains_letters(expr) {
-        return evaluate_numeric_expr(expr);
+fn make_fn(def: &FuncDef, collected_vars: &SymbolTable) -> String {
+    let mut code = format!("{}{{\n", fn_header(def));
+    // As in the parser, the body only sees functions and its own parameters.
+    let mut fn_vars = collected_vars.functions();
+    fn_vars.push_scope(ScopeKind::Function(def.ret.unwrap_or("void")));
+    for (name, typ) in &def.params {
+        fn_vars.declare(name.clone(), typ);
     }
-    let mut substituted = expr.to_string();
-    for token in expr.split(|c: char| !c.is_alphanumeric() && c != '_') {
-        if !token.is_empty() && token.chars().any(|c| c.is_alphabetic()) {
-            if let Some(value) = math_values.get(token) {
-                substituted = substituted.replace(token, &value.to_string());
-            } else {
-                return None;
-            }
-        }
-    }
-    if !contains_letters(&substituted) {
-        evaluate_numeric_expr(&substituted)
-    } else {
-        None
+    code.push_str(&make_c(&def.body, false, &mut fn_vars));
+    code.push_str("}\n");
+    code
+}
+
+/// Emits a block body inside its own scope; the caller writes the surrounding braces.
+fn make_block(body: &[AST], collected_vars: &mut SymbolTable, kind: ScopeKind) -> String {
+    collected_vars.push_scope(kind);
+    let code = make_c(body, false, collected_vars);
+    collected_vars.pop_scope();
+    code
+}
+
+fn struct_typedef(def: &StructDef) -> String {
+    let mut code = String::from("typedef struct {\n");
+    for (field, typ) in &def.fields {
+        writeln!(code, "{} {};", c_type(typ), field).unwrap();
     }
+    writeln!(code, "}} {};", def.name).unwrap();
+    code
 }
 
-fn write_decl(code: &mut String, declared_type: &str, name: &str, value: &str) {
-    if declared_type.starts_with("const;") {
-        let real_type = &declared_type["const;".len()..];
-        write!(code, "const {} {} = {};\n", real_type, name, value).unwrap();
+/// Writes the `nprintf` for the text collected so far and starts a new one.
+fn flush_print(code: &mut String, fd: i32, fmt: &mut String, args: &mut Vec<String>) {
+    if args.is_empty() {
+        if !fmt.is_empty() {
+            writeln!(code, "nprintf({},\"{}\");", fd, fmt).unwrap();
+        }
     } else {
-        write!(code, "{} {} = {};\n", declared_type, name, value).unwrap();
+        writeln!(code, "nprintf({},\"{}\",{});", fd, fmt, args.join(",")).unwrap();
     }
+    fmt.clear();
+    args.clear();
 }
 
-#[allow(non_snake_case)]
-pub fn make_c(
-    ast: &[AST],
-    gen_main_function: bool,
-    collected_vars: &mut Vec<(String, &'static str)>,
-    collected_errors: &mut Vec<ErrTypes>,
-    math_values: &mut HashMap<String, f64>,
-) -> String {
-    let mut code = String::with_capacity(4096);
-
-    let binding = collected_vars.clone();
-    let var_types: HashMap<&str, &'static str> = binding
+fn call_to_c(name: &str, args: &[Expr], collected_vars: &SymbolTable) -> String {
+    let args: Vec<String> = args
         .iter()
-        .map(|(name, typ)| (name.as_str(), *typ))
+        .map(|arg| mk_c_expr(arg, collected_vars))
         .collect();
+    format!("{}({})", c_fn_name(name), args.join(", "))
+}
 
-    const HEADER: &str =
-        "#include \"nulibc.h\"\n#include <stdio.h>\n#include <stdlib.h>\nint main(){\n";
+#[allow(non_snake_case)]
+pub fn make_c(ast: &[AST], gen_main_function: bool, collected_vars: &mut SymbolTable) -> String {
+    let mut code = String::with_capacity(4096);
+
+    const HEADER: &str = "#include \"nulibc.h\"\n#include <stdio.h>\n#include <stdlib.h>\n";
     if gen_main_function {
         code.push_str(HEADER);
+        // Structs are only declared at the top level, and functions may use them.
+        for node in ast {
+            if let AST::Struct(def) = node {
+                code.push_str(&struct_typedef(def));
+                collected_vars.declare_struct(def.clone());
+            }
+        }
+        let mut funcs = Vec::new();
+        collect_funcs(ast, &mut funcs);
+        for def in &funcs {
+            writeln!(&mut code, "{};", fn_header(def)).unwrap();
+            let tag = fn_tag(def.ret.unwrap_or("void"), &def.params);
+            collected_vars.declare(def.name.clone(), tag);
+        }
+        for def in &funcs {
+            code.push_str(&make_fn(def, collected_vars));
+        }
+        code.push_str("int main(){\n");
     }
 
-    static FORMAT_SPECIFIERS: [(&str, &str); 6] = [
+    static FORMAT_SPECIFIERS: [(&str, &str); 9] = [
         ("ch", "%c"),
         ("i8", "%d"),
         ("i16", "%d"),
         ("i32", "%d"),
-        ("i64", "%d"),
+        ("i64", "%ld"),
         ("f32", "%f"),
+        ("f64", "%f"),
+        ("str", "%s"),
+        ("bool", "%b"),
     ];
     let format_map: HashMap<&str, &str> = FORMAT_SPECIFIERS.iter().copied().collect();
 
     for node in ast {
         match node {
-            AST::Input(_var) => {
-                //
+            AST::Input {
+                var,
+                typ,
+                declare,
+                line,
+            } => {
+                let decl = if *declare {
+                    collected_vars.declare(var.clone(), typ);
+                    format!("{} ", c_type(typ))
+                } else {
+                    String::new()
+                };
+                match *typ {
+                    "str" if *declare => writeln!(&mut code, "nstring {var};\nninput(&{var});"),
+                    "str" => writeln!(&mut code, "ninput(&{var});"),
+                    "ch" => writeln!(&mut code, "{decl}{var} = ninput_ch({line});"),
+                    "bool" => writeln!(&mut code, "{decl}{var} = ninput_bool({line});"),
+                    "f32" | "f64" => writeln!(
+                        &mut code,
+                        "{decl}{var} = ({typ})ninput_float(\"{typ}\", {line});"
+                    ),
+                    _ => {
+                        let bits = &typ[1..];
+                        writeln!(
+                            &mut code,
+                            "{decl}{var} = ({typ})ninput_int(\"{typ}\", INT{bits}_MIN, INT{bits}_MAX, {line});"
+                        )
+                    }
+                }
+                .unwrap();
+            }
+            // Functions are emitted before `main`.
+            AST::Func(_) => {}
+            AST::Return(Some(expr), _) => {
+                writeln!(&mut code, "return {};", mk_c_expr(expr, collected_vars)).unwrap()
+            }
+            AST::Return(None, _) => code.push_str("return;\n"),
+            AST::Call(name, args, _) => {
+                writeln!(&mut code, "{};", call_to_c(name, args, collected_vars)).unwrap()
             }
             AST::Print {
                 descriptor: fd,
                 text,
             } => {
+                let fd = fd.display();
                 let mut fmt = String::with_capacity(text.len() * 2);
                 let mut args = Vec::new();
                 for ptok in text {
@@ -103,172 +199,267 @@ pub fn make_c(
                         PrintTokTypes::Newline => fmt.push_str("\\n"),
                         PrintTokTypes::Space => fmt.push(' '),
                         PrintTokTypes::Word(w) => fmt.push_str(w),
+                        // `%` would start a conversion in the format.
+                        PrintTokTypes::Text(text) => {
+                            fmt.push_str(&c_escape(text).replace('%', "%%"))
+                        }
                         PrintTokTypes::Var(v) => {
-                            if let Some(&typ) = var_types.get(v.as_str()) {
-                                if let Some(fmt_spec) = format_map.get(typ) {
-                                    fmt.push_str(fmt_spec);
+                            let typ = collected_vars
+                                .lookup_path(v)
+                                .map(|typ| typ.trim_start_matches("const;"));
+                            // Structs are printed field by field, as `Point { x: 1.0, y: 2.0 }`.
+                            if let Some(def) = typ.and_then(|typ| collected_vars.struct_def(typ)) {
+                                write!(fmt, "{} {{ ", def.name).unwrap();
+                                for (i, (field, typ)) in def.fields.iter().enumerate() {
+                                    if i > 0 {
+                                        fmt.push_str(", ");
+                                    }
+                                    write!(fmt, "{}: {}", field, format_map[typ]).unwrap();
+                                    if *typ == "str" {
+                                        args.push(format!("{}.{}.str", v, field));
+                                    } else {
+                                        args.push(format!("{}.{}", v, field));
+                                    }
                                 }
+                                fmt.push_str(" }");
+                                continue;
+                            }
+                            // Arrays are printed element by element, as `[1, 2, 3]`.
+                            if let Some(elem) = typ.and_then(elem_type) {
+                                flush_print(&mut code, fd, &mut fmt, &mut args);
+                                let item = format!("(({} *){}.data)[__i]", c_type(elem), v);
+                                let item = if elem == "str" {
+                                    format!("{}.str", item)
+                                } else {
+                                    item
+                                };
+                                writeln!(
+                                    &mut code,
+                                    "nprintf({fd},\"[\");for(custom_size_t __i = 0; __i < {v}.len; __i++){{if(__i) nprintf({fd},\", \");nprintf({fd},\"{}\",{item});}}nprintf({fd},\"]\");",
+                                    format_map.get(elem).copied().unwrap_or("%d"),
+                                )
+                                .unwrap();
+                                continue;
+                            }
+                            if let Some(fmt_spec) = typ.and_then(|typ| format_map.get(typ)) {
+                                fmt.push_str(fmt_spec);
+                            }
+                            if typ == Some("str") {
+                                args.push(format!("{}.str", v));
+                            } else {
+                                args.push(v.clone());
                             }
-                            args.push(v.as_str());
                         }
-                    }
-                }
-                if !args.is_empty() {
-                    write!(
-                        &mut code,
-                        "nprintf({},\"{}\",{});\n",
-                        fd.display(),
-                        fmt,
-                        args.join(",")
-                    )
-                    .unwrap();
-                } else {
-                    write!(&mut code, "nprintf({},\"{}\");\n", fd.display(), fmt).unwrap();
-                }
-            }
-            AST::Var(var) => {
-                use Variables::*;
-                match var {
-                    MATH(n, v) => {
-                        let computed_value = eval_math_expr(v, math_values);
-                        let base_type = "f32";
-                        let declared_type = var_types.get(n.as_str()).unwrap_or(&base_type);
-                        collected_vars.push((n.clone(), declared_type));
-                        if let Some(result) = computed_value {
-                            math_values.insert(n.clone(), result);
+                        PrintTokTypes::Call(name, call_args, _) => {
+                            let ret = lookup_fn(name, collected_vars)
+                                .and_then(fn_signature)
+                                .map(|(ret, _)| ret);
+                            // C may evaluate the arguments of one `nprintf` in any order, so a
+                            // call gets its own, after the pieces before it.
+                            flush_print(&mut code, fd, &mut fmt, &mut args);
+                            if let Some(fmt_spec) = ret.and_then(|ret| format_map.get(ret)) {
+                                fmt.push_str(fmt_spec);
+                            }
+                            let call = Expr::Call(name.clone(), call_args.clone());
+                            if ret == Some("str") {
+                                args.push(mk_c_str(&call, collected_vars));
+                            } else {
+                                args.push(mk_c_expr(&call, collected_vars));
+                            }
+                            flush_print(&mut code, fd, &mut fmt, &mut args);
                         }
-                        if declared_type.contains("i32") || declared_type.contains("i64") {
-                            if let Some(result) = computed_value {
-                                write_decl(
-                                    &mut code,
-                                    declared_type,
-                                    n,
-                                    &format!("{}", result as i64),
-                                );
+                        PrintTokTypes::Expr(expr, _) => {
+                            let typ = infer(expr, collected_vars);
+                            // As for a call, `pop` and indexing run in the order written.
+                            if expr.has_effects() {
+                                flush_print(&mut code, fd, &mut fmt, &mut args);
+                            }
+                            if let Some(fmt_spec) = format_map.get(typ) {
+                                fmt.push_str(fmt_spec);
+                            }
+                            if typ == "str" {
+                                args.push(mk_c_str(expr, collected_vars));
                             } else {
-                                let v_clean = if v.ends_with(".0") {
-                                    &v[..v.len() - 2]
-                                } else {
-                                    v
-                                };
-                                write_decl(&mut code, declared_type, n, v_clean);
+                                args.push(mk_c_expr(expr, collected_vars));
+                            }
+                            if expr.has_effects() {
+                                flush_print(&mut code, fd, &mut fmt, &mut args);
                             }
-                        } else {
-                            write_decl(&mut code, declared_type, n, v);
                         }
                     }
-                    Char(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"char"),
-                        n,
-                        &format!("'{}'", v),
-                    ),
-                    I8(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"i8"),
-                        n,
-                        &format!("{}", v),
-                    ),
-                    I16(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"i16"),
-                        n,
-                        &format!("{}", v),
-                    ),
-                    I32(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"i32"),
-                        n,
-                        &format!("{}", v),
-                    ),
-                    I64(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"i64"),
-                        n,
-                        &format!("{}", v),
-                    ),
-                    F32(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"f32"),
-                        n,
-                        &format!("{}", v),
-                    ),
-                    F64(n, v) => write_decl(
-                        &mut code,
-                        var_types.get(n).unwrap_or(&"double"),
-                        n,
-                        &format!("{}", v),
-                    ),
-                    Str(n, v) => {
-                        let typ = var_types.get(n).unwrap_or(&"nstring");
-                        // If the type is "str", override it with "nstring"
-                        let typ = if *typ == "str" { "nstring" } else { "nstring" };
-                        write_decl(&mut code, typ, n, &format!("nstr_new(\"{}\")", v))
-                    }
-                    REF(n, v) => {
-                        let (typ, actual) = resolve_ref(ast, v);
-                        write!(&mut code, "{} {} = {};\n", typ, n, actual).unwrap();
-                    }
                 }
+                flush_print(&mut code, fd, &mut fmt, &mut args);
+            }
+            AST::Var(var, declared_type, _) => {
+                use Variables::*;
+                let (name, mut value) = match var {
+                    MATH(n, v) => (n.as_str(), mk_c_value(v, declared_type, collected_vars)),
+                    Char(n, v) => (*n, format!("'{}'", c_escape(&v.to_string()))),
+                    I8(n, v) => (*n, v.to_string()),
+                    I16(n, v) => (*n, v.to_string()),
+                    I32(n, v) => (*n, v.to_string()),
+                    I64(n, v) => (*n, v.to_string()),
+                    F32(n, v) => (*n, v.to_string()),
+                    F64(n, v) => (*n, v.to_string()),
+                    Bool(n, v) => (*n, v.to_string()),
+                    Str(n, v) => (*n, format!("nstr_new(\"{}\")", c_escape(v))),
+                };
+                // C puts a new variable in scope inside its own initialiser, so a shadowing
+                // `may shadow x = x + 1` computes its value while the outer `x` is still visible.
+                if matches!(var, MATH(..))
+                    && collected_vars.lookup(name).is_some()
+                    && !collected_vars.declared_here(name)
+                {
+                    let tmp = format!("__shadow_{}", name);
+                    write_decl(&mut code, declared_type, &tmp, &value);
+                    value = tmp;
+                }
+                write_decl(&mut code, declared_type, name, &value);
+                collected_vars.declare(name.to_string(), declared_type);
             }
             AST::While(body, cond) => {
-                let cond_str = mk_c_cond(cond, collected_errors, collected_vars, 0);
-                write!(&mut code, "while({}) {{\n", cond_str).unwrap();
-                code.push_str(&make_c(
-                    body,
-                    false,
-                    collected_vars,
-                    collected_errors,
-                    math_values,
-                ));
+                let cond_str = mk_c_cond(cond, collected_vars);
+                writeln!(&mut code, "while({}) {{", cond_str).unwrap();
+                code.push_str(&make_block(body, collected_vars, ScopeKind::Loop));
                 code.push_str("}\n");
             }
             AST::IF(body, cond) => {
-                let cond_str = mk_c_cond(cond, collected_errors, collected_vars, 0);
-                write!(&mut code, "if({}) {{\n", cond_str).unwrap();
-                code.push_str(&make_c(
-                    body,
-                    false,
-                    collected_vars,
-                    collected_errors,
-                    math_values,
-                ));
+                let cond_str = mk_c_cond(cond, collected_vars);
+                writeln!(&mut code, "if({}) {{", cond_str).unwrap();
+                code.push_str(&make_block(body, collected_vars, ScopeKind::Block));
                 code.push_str("}\n");
             }
-            AST::VarAssign(var) => {
+            AST::ElseIf(body, cond) => {
+                let cond_str = mk_c_cond(cond, collected_vars);
+                writeln!(&mut code, "else if({}) {{", cond_str).unwrap();
+                code.push_str(&make_block(body, collected_vars, ScopeKind::Block));
+                code.push_str("}\n");
+            }
+            AST::For {
+                var,
+                typ,
+                start,
+                end,
+                step,
+                inclusive,
+                body,
+                ..
+            } => {
+                // The end bound and the step are evaluated once, like the range they come from.
+                let down = counts_down(step);
+                let cmp = match (down, inclusive) {
+                    (false, false) => "<",
+                    (false, true) => "<=",
+                    (true, false) => ">",
+                    (true, true) => ">=",
+                };
+                // The loop stops before a step that would pass the end, since adding it could
+                // overflow the variable; so does a body that moved it past the end. The distances are taken as unsigned so they cannot
+                // overflow either.
+                let gap = if down {
+                    format!("(uint64_t){var} - (uint64_t)__{var}_end")
+                } else {
+                    format!("(uint64_t)__{var}_end - (uint64_t){var}")
+                };
+                let stride = if down {
+                    format!("(uint64_t)0 - (uint64_t)__{var}_step")
+                } else {
+                    format!("(uint64_t)__{var}_step")
+                };
+                let last = if *inclusive { "<" } else { "<=" };
+                writeln!(
+                    &mut code,
+                    "for({typ} {var} = {}, __{var}_end = {}, __{var}_step = {}, __{var}_last = 0; !__{var}_last && {var} {cmp} __{var}_end; __{var}_last = !({var} {cmp} __{var}_end) || {gap} {last} {stride}, {var} += __{var}_last ? 0 : __{var}_step) {{",
+                    mk_c_expr(start, collected_vars),
+                    mk_c_expr(end, collected_vars),
+                    mk_c_expr(step, collected_vars),
+                )
+                .unwrap();
+                collected_vars.push_scope(ScopeKind::Loop);
+                collected_vars.declare(var.clone(), typ);
+                code.push_str(&make_c(body, false, collected_vars));
+                collected_vars.pop_scope();
+                code.push_str("}\n");
+            }
+            AST::SetIndex {
+                array,
+                index,
+                value,
+                line,
+                ..
+            } => {
+                let elem = collected_vars
+                    .lookup(array)
+                    .and_then(elem_type)
+                    .unwrap_or("i32");
+                writeln!(
+                    &mut code,
+                    "*({} *)narray_at(&{}, {}, {}) = {};",
+                    c_type(elem),
+                    array,
+                    mk_c_expr(index, collected_vars),
+                    line,
+                    mk_c_value(value, elem, collected_vars)
+                )
+                .unwrap();
+            }
+            AST::Push(array, value, _) => {
+                let elem = collected_vars
+                    .lookup(array)
+                    .and_then(elem_type)
+                    .unwrap_or("i32");
+                writeln!(
+                    &mut code,
+                    "narray_push(&{}, ({}[]){{{}}});",
+                    array,
+                    c_type(elem),
+                    mk_c_value(value, elem, collected_vars)
+                )
+                .unwrap();
+            }
+            AST::Pop(array, line, _) => {
+                writeln!(&mut code, "narray_pop(&{}, {});", array, line).unwrap()
+            }
+            // Struct typedefs are emitted before `main`.
+            AST::Struct(def) => collected_vars.declare_struct(def.clone()),
+            AST::SetField {
+                var, field, value, ..
+            } => writeln!(
+                &mut code,
+                "{}.{} = {};",
+                var,
+                field,
+                mk_c_expr(value, collected_vars)
+            )
+            .unwrap(),
+            AST::Break => code.push_str("break;\n"),
+            AST::Continue => code.push_str("continue;\n"),
+            AST::Else(body) => {
+                code.push_str("else {\n");
+                code.push_str(&make_block(body, collected_vars, ScopeKind::Block));
+                code.push_str("}\n");
+            }
+            AST::VarAssign(var, _) => {
                 use Variables::*;
                 match var {
                     MATH(n, v) => {
-                        let computed_value = eval_math_expr(v, math_values);
-                        if let Some(result) = computed_value {
-                            math_values.insert(n.clone(), result);
-                        }
-                        if let Some(declared_type) = var_types.get(n.as_str()) {
-                            if declared_type.contains("i32") || declared_type.contains("i64") {
-                                if let Some(result) = computed_value {
-                                    write!(&mut code, "{} = {};\n", n, result as i64).unwrap();
-                                } else {
-                                    let v_clean = if v.ends_with(".0") {
-                                        &v[..v.len() - 2]
-                                    } else {
-                                        v
-                                    };
-                                    write!(&mut code, "{} = {};\n", n, v_clean).unwrap();
-                                }
-                            } else {
-                                write!(&mut code, "{} = {};\n", n, v).unwrap();
-                            }
-                        }
+                        let typ = collected_vars.lookup(n).unwrap_or("?");
+                        writeln!(&mut code, "{} = {};", n, mk_c_value(v, typ, collected_vars))
+                            .unwrap()
+                    }
+                    Char(n, v) => {
+                        writeln!(&mut code, "{} = '{}';", n, c_escape(&v.to_string())).unwrap()
+                    }
+                    I8(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    I16(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    I32(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    I64(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    F32(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    F64(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    Bool(n, v) => writeln!(&mut code, "{} = {};", n, v).unwrap(),
+                    Str(n, v) => {
+                        writeln!(&mut code, "{} = nstr_new(\"{}\");", n, c_escape(v)).unwrap()
                     }
-                    Char(n, v) => write!(&mut code, "{} = '{}';\n", n, v).unwrap(),
-                    I8(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
-                    I16(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
-                    I32(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
-                    I64(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
-                    F32(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
-                    F64(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
-                    Str(n, v) => write!(&mut code, "{} = nstr_new(\"{}\");\n", n, v).unwrap(),
-                    REF(n, v) => write!(&mut code, "{} = {};\n", n, v).unwrap(),
                 }
             }
         }
@@ -278,42 +469,3 @@ pub fn make_c(
     }
     code
 }
-
-#[inline]
-fn resolve_ref<'a>(ast: &'a [AST], mut current: &'a str) -> (&'static str, &'a str) {
-    while let Some(next) = ast.iter().find_map(|node| {
-        if let AST::Var(Variables::REF(name, next_val)) = node {
-            if name == &current {
-                Some(next_val.as_str())
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }) {
-        current = next;
-    }
-    let typ = ast
-        .iter()
-        .find_map(|node| {
-            if let AST::Var(var) = node {
-                match var {
-                    Variables::Char(name, _) if *name == current => Some("char"),
-                    Variables::I8(name, _) if *name == current => Some("i8"),
-                    Variables::I16(name, _) if *name == current => Some("i16"),
-                    Variables::I32(name, _) if *name == current => Some("i32"),
-                    Variables::I64(name, _) if *name == current => Some("i64"),
-                    Variables::F32(name, _) if *name == current => Some("f32"),
-                    Variables::F64(name, _) if *name == current => Some("double"),
-                    Variables::MATH(name, _) if *name == current => Some("f32"),
-                    Variables::Str(name, _) if *name == current => Some("nstring"),
-                    _ => None,
-                }
-            } else {
-                None
-            }
-        })
-        .unwrap_or("auto");
-    (typ, current)
-}
@@ -0,0 +1,151 @@
+use super::{
+    err_types::ErrTypes,
+    error_msg_gen::{describe, describe_warning, label},
+    warn_types::WarnTypes,
+};
+use crate::tok_system::tokens::Span;
+use std::fmt;
+
+/// How diagnostics are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// Boxed, coloured messages for people.
+    #[default]
+    Human,
+    /// One JSON object per line, for editors and CI.
+    Json,
+}
+
+/// How diagnostics are reported by `parse`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReportOptions {
+    pub format: MessageFormat,
+    /// Fail when there are warnings, as if they were errors.
+    pub deny_warnings: bool,
+}
+
+/// Whether a diagnostic stops the build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A compile error or warning as plain data, for callers that format or inspect them themselves.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable code such as `N0006` or `W0001`; see `neit explain`.
+    pub code: &'static str,
+    /// The code the error points at.
+    pub span: Span,
+    pub title: &'static str,
+    /// Short text shown under `span`.
+    pub label: String,
+    pub hint: &'static str,
+    /// Other code that explains the error, such as an earlier declaration.
+    pub notes: Vec<Note>,
+    /// Edits that would fix the error.
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// A secondary span with a message saying why it matters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub span: Span,
+    pub message: &'static str,
+}
+
+/// A machine-applicable fix: replacing the code at `span` with `replacement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl From<ErrTypes> for Diagnostic {
+    fn from(error: ErrTypes) -> Self {
+        let (title, span, hint) = describe(error);
+        let mut notes = Vec::new();
+        let mut suggestions = Vec::new();
+        match error {
+            ErrTypes::VarAlreadyExists(_, Some(prev)) => notes.push(Note {
+                span: prev,
+                message: "previous declaration here",
+            }),
+            ErrTypes::ImplicitShadow(_, Some(outer)) => notes.push(Note {
+                span: outer,
+                message: "outer declaration here",
+            }),
+            ErrTypes::VarISConst(_, Some(decl)) => notes.push(Note {
+                span: decl,
+                message: "declared as a constant here",
+            }),
+            ErrTypes::VarNotFound(span, Some(name))
+            | ErrTypes::UnknownFunction(span, Some(name))
+            | ErrTypes::UnknownField(span, Some(name)) => suggestions.push(Suggestion {
+                message: format!("did you mean `{}`?", name),
+                span,
+                replacement: name.to_string(),
+            }),
+            _ => {}
+        }
+        Diagnostic {
+            severity: Severity::Error,
+            code: error.code(),
+            span,
+            title,
+            label: label(error),
+            hint,
+            notes,
+            suggestions,
+        }
+    }
+}
+
+impl From<WarnTypes> for Diagnostic {
+    fn from(warning: WarnTypes) -> Self {
+        let (title, label, hint) = describe_warning(warning);
+        Diagnostic {
+            severity: Severity::Warning,
+            code: warning.code(),
+            span: warning.span(),
+            title,
+            label: label.to_string(),
+            hint,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} at line {}, column {}: {}",
+            self.code, self.title, self.span.line, self.span.col, self.label
+        )?;
+        for note in &self.notes {
+            write!(
+                f,
+                "; {} at line {}, column {}",
+                note.message, note.span.line, note.span.col
+            )?;
+        }
+        for suggestion in &self.suggestions {
+            write!(f, "; {}", suggestion.message)?;
+        }
+        Ok(())
+    }
+}
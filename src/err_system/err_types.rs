@@ -1,39 +1,141 @@
+use crate::tok_system::tokens::Span;
+
 #[derive(Debug, Clone, Copy)]
 pub enum ErrTypes {
     /// Unknown command encountered.
-    UnknownCMD(i32),
+    UnknownCMD(Span),
     /// Unsupported variable type.
-    UnsupportedVarType(i32),
-    /// Variable already exists.
-    VarAlreadyExists(i32),
+    UnsupportedVarType(Span),
+    /// Variable already exists; with the earlier declaration when it is known.
+    VarAlreadyExists(Span, Option<Span>),
     /// Char type variable shall have a length of 1.
-    CharVarLen(i32),
-    /// Invalid math usage (e.g. multiple operators in sequence).
-    InvalidMathUsage(i32),
-    /// Referenced variable not found.
-    VarNotFound(i32),
+    CharVarLen(Span),
+    /// Referenced variable not found; with a similarly spelled name that does exist.
+    VarNotFound(Span, Option<&'static str>),
     /// Generic syntax error.
-    SyntaxError(i32),
+    SyntaxError(Span),
     /// Expected operator is missing.
-    MissingOperator(i32),
+    MissingOperator(Span),
     /// An unexpected token was encountered.
-    UnexpectedToken(i32),
-    /// A required value is missing.
-    MissingValue(i32),
+    UnexpectedToken(Span),
+    /// A required value is missing; with the struct field it is for, in a struct literal.
+    MissingValue(Span, Option<&'static str>),
     /// Parentheses or similar grouping symbols are unbalanced.
-    UnbalancedParentheses(i32),
+    UnbalancedParentheses(Span),
     /// Attempted division by zero.
-    DivisionByZero(i32),
-    /// Mismatched types in an operation or assignment.
-    TypeMismatch(i32),
+    DivisionByZero(Span),
+    /// A value of the wrong type in an operation or assignment: the type wanted, or a kind of
+    /// type such as `number`, and the type found.
+    TypeMismatch(Span, &'static str, &'static str),
     /// Reserved keyword used as identifier.
-    ReservedKeyword(i32),
-    UnexpectedEndOfInput(i32),
-    InvalidNumberFormat(i32),
-    DuplicateOperator(i32),
-    MissingLeftOperand(i32),
-    MissingRightOperand(i32),
-    UnsupportedOperator(i32),
-    InvalidConditionSyntax(i32),
-    VarISConst(i32),
+    ReservedKeyword(Span),
+    UnexpectedEndOfInput(Span),
+    InvalidNumberFormat(Span),
+    DuplicateOperator(Span),
+    MissingLeftOperand(Span),
+    MissingRightOperand(Span),
+    InvalidConditionSyntax(Span),
+    /// Assignment to a constant; with the constant's declaration when it is known.
+    VarISConst(Span, Option<Span>),
+    /// Called function has not been declared; with a similarly spelled function that has.
+    UnknownFunction(Span, Option<&'static str>),
+    /// Function called with the wrong number of arguments.
+    ArgCountMismatch(Span),
+    /// `return` used outside of a function body.
+    ReturnOutsideFn(Span),
+    /// `else` without a preceding `if` block.
+    ElseWithoutIf(Span),
+    /// `break` or `continue` used outside of a loop.
+    BreakOutsideLoop(Span),
+    /// Declaration hides a name from an outer block without `may shadow`; with the outer
+    /// declaration when it is known.
+    ImplicitShadow(Span, Option<Span>),
+    /// A block header such as `if` or `while` without the `{` that opens its body.
+    MissingBlock(Span),
+    /// An escape in a string literal that Neit does not know, such as `\q`.
+    InvalidEscape(Span),
+    /// A field that the struct does not have; with a similarly spelled field that it has.
+    UnknownField(Span, Option<&'static str>),
+    /// A function with a return type whose body can end without a `return`.
+    MissingReturn(Span),
+    /// An integer constant that does not fit the integer type it is used as.
+    OutOfRange(Span, &'static str),
+}
+
+impl ErrTypes {
+    /// Stable code for the error, e.g. `N0006`, used by `neit explain` and in tooling.
+    /// Codes follow declaration order; new variants take the next free code and
+    /// existing codes are never reused. N0005 and N0020 are retired.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrTypes::UnknownCMD(..) => "N0001",
+            ErrTypes::UnsupportedVarType(..) => "N0002",
+            ErrTypes::VarAlreadyExists(..) => "N0003",
+            ErrTypes::CharVarLen(..) => "N0004",
+            ErrTypes::VarNotFound(..) => "N0006",
+            ErrTypes::SyntaxError(..) => "N0007",
+            ErrTypes::MissingOperator(..) => "N0008",
+            ErrTypes::UnexpectedToken(..) => "N0009",
+            ErrTypes::MissingValue(..) => "N0010",
+            ErrTypes::UnbalancedParentheses(..) => "N0011",
+            ErrTypes::DivisionByZero(..) => "N0012",
+            ErrTypes::TypeMismatch(..) => "N0013",
+            ErrTypes::ReservedKeyword(..) => "N0014",
+            ErrTypes::UnexpectedEndOfInput(..) => "N0015",
+            ErrTypes::InvalidNumberFormat(..) => "N0016",
+            ErrTypes::DuplicateOperator(..) => "N0017",
+            ErrTypes::MissingLeftOperand(..) => "N0018",
+            ErrTypes::MissingRightOperand(..) => "N0019",
+            ErrTypes::InvalidConditionSyntax(..) => "N0021",
+            ErrTypes::VarISConst(..) => "N0022",
+            ErrTypes::UnknownFunction(..) => "N0023",
+            ErrTypes::ArgCountMismatch(..) => "N0024",
+            ErrTypes::ReturnOutsideFn(..) => "N0025",
+            ErrTypes::ElseWithoutIf(..) => "N0026",
+            ErrTypes::BreakOutsideLoop(..) => "N0027",
+            ErrTypes::ImplicitShadow(..) => "N0028",
+            ErrTypes::MissingBlock(..) => "N0029",
+            ErrTypes::InvalidEscape(..) => "N0030",
+            ErrTypes::UnknownField(..) => "N0031",
+            ErrTypes::MissingReturn(..) => "N0032",
+            ErrTypes::OutOfRange(..) => "N0033",
+        }
+    }
+
+    /// The code the error points at.
+    pub fn span(&self) -> Span {
+        match self {
+            ErrTypes::UnknownCMD(span)
+            | ErrTypes::UnsupportedVarType(span)
+            | ErrTypes::CharVarLen(span)
+            | ErrTypes::SyntaxError(span)
+            | ErrTypes::MissingOperator(span)
+            | ErrTypes::UnexpectedToken(span)
+            | ErrTypes::MissingValue(span, _)
+            | ErrTypes::UnbalancedParentheses(span)
+            | ErrTypes::DivisionByZero(span)
+            | ErrTypes::TypeMismatch(span, ..)
+            | ErrTypes::ReservedKeyword(span)
+            | ErrTypes::UnexpectedEndOfInput(span)
+            | ErrTypes::InvalidNumberFormat(span)
+            | ErrTypes::DuplicateOperator(span)
+            | ErrTypes::MissingLeftOperand(span)
+            | ErrTypes::MissingRightOperand(span)
+            | ErrTypes::InvalidConditionSyntax(span)
+            | ErrTypes::ArgCountMismatch(span)
+            | ErrTypes::ReturnOutsideFn(span)
+            | ErrTypes::ElseWithoutIf(span)
+            | ErrTypes::BreakOutsideLoop(span)
+            | ErrTypes::MissingBlock(span)
+            | ErrTypes::InvalidEscape(span)
+            | ErrTypes::MissingReturn(span) => *span,
+            ErrTypes::VarAlreadyExists(span, _)
+            | ErrTypes::VarNotFound(span, _)
+            | ErrTypes::VarISConst(span, _)
+            | ErrTypes::UnknownFunction(span, _)
+            | ErrTypes::ImplicitShadow(span, _)
+            | ErrTypes::UnknownField(span, _)
+            | ErrTypes::OutOfRange(span, _) => *span,
+        }
+    }
 }
@@ -1,8 +1,15 @@
-use super::err_types::ErrTypes;
+use super::{
+    diagnostic::{Diagnostic, Severity},
+    err_types::ErrTypes,
+    warn_types::WarnTypes,
+};
+use crate::{
+    tok_system::tokens::Span,
+    type_system::{int_bounds, ARRAY, GROWABLE, INTEGER, NUMBER, VALUE},
+};
 use colored::Colorize;
-use rand::seq::{IndexedRandom, SliceRandom};
-use rand::{rng, thread_rng};
-use std::convert::TryInto;
+use rand::rng;
+use rand::seq::IndexedRandom;
 
 // A large collection of taunting messages to incite frustration.
 static TAUNTS: &[&str] = &[
@@ -64,162 +71,293 @@ fn get_random_taunt() -> &'static str {
     TAUNTS.choose(&mut rng).unwrap_or(&"")
 }
 
-/// Formats an error message in a compact style using vertical bars.
-/// The message includes a header, the adjusted error line number,
-/// the corresponding code piece, a hint, and a randomly selected taunt.
-fn format_error_msg(header: &str, line: u32, hint: &str, code: &String) -> String {
-    // Subtract one from the line number, avoiding underflow if line is 0.
-    let adjusted_line = line.saturating_sub(1);
-    let code_piece = code
-        .lines()
-        .nth(adjusted_line as usize)
-        .unwrap_or("Code snippet unavailable");
-    format!(
-        "┌[{}] at line {}\n├ Code Piece: {}\n├ Hint: {}\n└ {}",
-        header.red().bold(),
-        adjusted_line,
+/// Formats an error or warning in a compact style using vertical bars.
+/// The message includes a header with the code, the position, the offending line with the span
+/// underlined and labelled, any notes and suggestions, a hint and a pointer to `neit explain`.
+/// Errors are red and end with a randomly selected taunt; warnings are yellow.
+pub fn format_error_msg(diag: &Diagnostic, code: &str) -> String {
+    let paint = |text: &str| match diag.severity {
+        Severity::Error => text.red(),
+        Severity::Warning => text.yellow(),
+    };
+    let (code_piece, marks) =
+        snippet(code, diag.span).unwrap_or(("Code snippet unavailable", String::new()));
+    let mut msg = format!(
+        "┌[{}][{}] at line {}, column {}\n├ Code Piece: {}\n│             {} {}\n",
+        paint(diag.title).bold(),
+        paint(diag.code),
+        diag.span.line,
+        diag.span.col,
         code_piece,
-        hint.cyan(),
-        get_random_taunt().yellow().bold()
-    )
+        paint(&marks).bold(),
+        paint(&diag.label)
+    );
+    for note in &diag.notes {
+        msg.push_str(&format!(
+            "├ Note: {} at line {}, column {}\n",
+            note.message, note.span.line, note.span.col
+        ));
+        if let Some((code_piece, marks)) = snippet(code, note.span) {
+            msg.push_str(&format!(
+                "│             {}\n│             {}\n",
+                code_piece,
+                marks.blue().bold()
+            ));
+        }
+    }
+    for suggestion in &diag.suggestions {
+        msg.push_str(&format!("├ Help: {}\n", suggestion.message.green()));
+    }
+    msg.push_str(&format!("├ Hint: {}\n", diag.hint.cyan()));
+    match diag.severity {
+        Severity::Error => msg.push_str(&format!(
+            "├ Explain: neit explain {}\n└ {}",
+            diag.code,
+            get_random_taunt().yellow().bold()
+        )),
+        Severity::Warning => msg.push_str(&format!("└ Explain: neit explain {}", diag.code)),
+    }
+    msg
 }
 
-/// Generates an error message based on the error type and source code.
-/// The returned message includes the adjusted line number, the code piece, and error details.
-pub fn gen_error_msg(err_type: ErrTypes, _code: &String) -> String {
-    println!("code:\n{}", _code);
+/// The source line a span starts on, with the span marked underneath it.
+fn snippet(code: &str, span: Span) -> Option<(&str, String)> {
+    let code_piece = code.lines().nth(usize::try_from(span.line - 1).ok()?)?;
+    Some((code_piece, underline(code_piece, span)))
+}
+
+/// Marks the part of `code_piece` covered by `span` with carets, keeping tabs so the marks line up.
+/// A span running past the end of the line is cut at the line end.
+fn underline(code_piece: &str, span: Span) -> String {
+    let start = usize::try_from(span.col - 1).unwrap_or(0);
+    let mut marks: String = code_piece
+        .chars()
+        .take(start)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let rest = code_piece
+        .char_indices()
+        .nth(start)
+        .map_or("", |(at, _)| &code_piece[at..]);
+    let len = span.end.saturating_sub(span.start);
+    let width = rest.char_indices().take_while(|(at, _)| *at < len).count();
+    marks.push_str(&"^".repeat(width.max(1)));
+    marks
+}
+
+/// Title and hint for an error, with the code it points at.
+pub fn describe(err_type: ErrTypes) -> (&'static str, Span, &'static str) {
     match err_type {
-        ErrTypes::SyntaxError(line) => format_error_msg(
-            "Syntax Error",
-            line.try_into().unwrap(),
-            "Check your syntax and try again",
-            _code,
-        ),
-        ErrTypes::DivisionByZero(line) => format_error_msg(
+        ErrTypes::SyntaxError(span) => ("Syntax Error", span, "Check your syntax and try again"),
+        ErrTypes::DivisionByZero(span) => (
             "Division By Zero",
-            line.try_into().unwrap(),
+            span,
             "Ensure the denominator is not zero",
-            _code,
-        ),
-        ErrTypes::MissingOperator(line) => format_error_msg(
-            "Missing Operator",
-            line.try_into().unwrap(),
-            "Insert the appropriate operator",
-            _code,
         ),
-        ErrTypes::UnexpectedToken(line) => format_error_msg(
-            "Unexpected Token",
-            line.try_into().unwrap(),
-            "Review your tokens",
-            _code,
-        ),
-        ErrTypes::TypeMismatch(line) => format_error_msg(
+        ErrTypes::MissingOperator(span) => {
+            ("Missing Operator", span, "Insert the appropriate operator")
+        }
+        ErrTypes::UnexpectedToken(span) => ("Unexpected Token", span, "Review your tokens"),
+        ErrTypes::TypeMismatch(span, ..) => (
             "Type Mismatch",
-            line.try_into().unwrap(),
-            "Ensure types match as expected",
-            _code,
-        ),
-        ErrTypes::MissingValue(line) => format_error_msg(
-            "Missing Value",
-            line.try_into().unwrap(),
-            "Provide the missing value",
-            _code,
-        ),
-        ErrTypes::ReservedKeyword(line) => format_error_msg(
-            "Reserved Keyword",
-            line.try_into().unwrap(),
-            "Avoid using reserved keywords",
-            _code,
-        ),
-        ErrTypes::UnbalancedParentheses(line) => format_error_msg(
-            "Unbalanced Parentheses",
-            line.try_into().unwrap(),
-            "Balance your parentheses",
-            _code,
-        ),
-        ErrTypes::VarNotFound(line) => format_error_msg(
-            "Variable Not Found",
-            line.try_into().unwrap(),
-            "Declare or check the variable",
-            _code,
-        ),
-        ErrTypes::UnknownCMD(line) => format_error_msg(
-            "Unknown Command",
-            line.try_into().unwrap(),
-            "Check the command and try again",
-            _code,
-        ),
-        ErrTypes::UnsupportedVarType(line) => format_error_msg(
+            span,
+            "Convert the value with `value(type)`, or change the type it must have",
+        ),
+        ErrTypes::MissingValue(span, _) => ("Missing Value", span, "Provide the missing value"),
+        ErrTypes::ReservedKeyword(span) => {
+            ("Reserved Keyword", span, "Avoid using reserved keywords")
+        }
+        ErrTypes::UnbalancedParentheses(span) => {
+            ("Unbalanced Parentheses", span, "Balance your parentheses")
+        }
+        ErrTypes::VarNotFound(span, _) => {
+            ("Variable Not Found", span, "Declare or check the variable")
+        }
+        ErrTypes::UnknownCMD(span) => ("Unknown Command", span, "Check the command and try again"),
+        ErrTypes::UnsupportedVarType(span) => (
             "Unsupported Variable Type",
-            line.try_into().unwrap(),
+            span,
             "Use a supported variable type",
-            _code,
         ),
-        ErrTypes::VarAlreadyExists(line) => format_error_msg(
+        ErrTypes::VarAlreadyExists(span, _) => (
             "Variable Already Exists",
-            line.try_into().unwrap(),
+            span,
             "Rename or remove the duplicate",
-            _code,
         ),
-        ErrTypes::CharVarLen(line) => format_error_msg(
+        ErrTypes::CharVarLen(span) => (
             "Char Variable Length Error",
-            line.try_into().unwrap(),
+            span,
             "Check the character length",
-            _code,
-        ),
-        ErrTypes::InvalidMathUsage(line) => format_error_msg(
-            "Invalid Math Usage",
-            line.try_into().unwrap(),
-            "Review your math operations",
-            _code,
         ),
-        ErrTypes::DuplicateOperator(line) => format_error_msg(
-            "Duplicate Operator",
-            line.try_into().unwrap(),
-            "Remove the extra operator",
-            _code,
-        ),
-        ErrTypes::InvalidConditionSyntax(line) => format_error_msg(
+        ErrTypes::DuplicateOperator(span) => {
+            ("Duplicate Operator", span, "Remove the extra operator")
+        }
+        ErrTypes::InvalidConditionSyntax(span) => (
             "Invalid Condition Syntax",
-            line.try_into().unwrap(),
+            span,
             "Correct the condition syntax",
-            _code,
         ),
-        ErrTypes::InvalidNumberFormat(line) => format_error_msg(
+        ErrTypes::InvalidNumberFormat(span) => (
             "Invalid Number Format",
-            line.try_into().unwrap(),
+            span,
             "Ensure the number is correctly formatted",
-            _code,
-        ),
-        ErrTypes::MissingLeftOperand(line) => format_error_msg(
-            "Missing Left Operand",
-            line.try_into().unwrap(),
-            "Provide the left operand",
-            _code,
-        ),
-        ErrTypes::MissingRightOperand(line) => format_error_msg(
-            "Missing Right Operand",
-            line.try_into().unwrap(),
-            "Provide the right operand",
-            _code,
-        ),
-        ErrTypes::UnexpectedEndOfInput(line) => format_error_msg(
-            "Unexpected End Of Input",
-            line.try_into().unwrap(),
-            "Complete the input",
-            _code,
-        ),
-        ErrTypes::UnsupportedOperator(line) => format_error_msg(
-            "Unsupported Operator",
-            line.try_into().unwrap(),
-            "Use a supported operator",
-            _code,
-        ),
-        ErrTypes::VarISConst(line) => format_error_msg(
+        ),
+        ErrTypes::MissingLeftOperand(span) => {
+            ("Missing Left Operand", span, "Provide the left operand")
+        }
+        ErrTypes::MissingRightOperand(span) => {
+            ("Missing Right Operand", span, "Provide the right operand")
+        }
+        ErrTypes::UnexpectedEndOfInput(span) => {
+            ("Unexpected End Of Input", span, "Complete the input")
+        }
+        ErrTypes::VarISConst(span, _) => (
             "Constant Variable Error",
-            line.try_into().unwrap(),
+            span,
             "Constants cannot be modified",
-            _code,
+        ),
+        ErrTypes::UnknownFunction(span, _) => (
+            "Unknown Function",
+            span,
+            "Declare the function with `fn` before calling it",
+        ),
+        ErrTypes::ArgCountMismatch(span) => (
+            "Argument Count Mismatch",
+            span,
+            "Pass exactly the parameters the function declares",
+        ),
+        ErrTypes::ReturnOutsideFn(span) => (
+            "Return Outside Function",
+            span,
+            "Only use `return` inside a function body",
+        ),
+        ErrTypes::ElseWithoutIf(span) => (
+            "Else Without If",
+            span,
+            "Place `else` right after the closing `}` of an `if` block",
+        ),
+        ErrTypes::BreakOutsideLoop(span) => (
+            "Break Outside Loop",
+            span,
+            "Only use `break` and `continue` inside `while` or `for` loops",
+        ),
+        ErrTypes::ImplicitShadow(span, _) => (
+            "Name Already Declared In An Outer Block",
+            span,
+            "Rename it, or use `may shadow <name> = ...` to hide the outer one",
+        ),
+        ErrTypes::MissingBlock(span) => (
+            "Missing Block",
+            span,
+            "End the line with `{` and close the block with `}`",
+        ),
+        ErrTypes::InvalidEscape(span) => (
+            "Invalid Escape",
+            span,
+            "Use `\\n`, `\\t`, `\\\"`, `\\\\` or `\\u{...}`",
+        ),
+        ErrTypes::UnknownField(span, _) => (
+            "Unknown Field",
+            span,
+            "Check the field names in the struct's declaration",
+        ),
+        ErrTypes::MissingReturn(span) => (
+            "Missing Return",
+            span,
+            "Return a value on every path through the function",
+        ),
+        ErrTypes::OutOfRange(span, _) => (
+            "Number Out Of Range",
+            span,
+            "Use a wider type, or a number that fits",
+        ),
+    }
+}
+
+/// How a type is named in a message: `i32` in backquotes, or a kind of type in words.
+fn type_name(typ: &str) -> String {
+    match typ {
+        NUMBER => "a number".to_string(),
+        INTEGER => "an integer".to_string(),
+        VALUE => "a value".to_string(),
+        ARRAY => "an array".to_string(),
+        GROWABLE => "a growable array".to_string(),
+        "void" => "nothing".to_string(),
+        typ => format!("`{}`", typ),
+    }
+}
+
+/// Short text shown under the code an error points at.
+pub fn label(err_type: ErrTypes) -> String {
+    let text = match err_type {
+        ErrTypes::UnknownCMD(_) => "not a known command",
+        ErrTypes::UnsupportedVarType(_) => "unsupported type",
+        ErrTypes::VarAlreadyExists(..) => "already declared in this block",
+        ErrTypes::CharVarLen(_) => "must be exactly one ASCII character",
+        ErrTypes::VarNotFound(..) => "not found in this scope",
+        ErrTypes::SyntaxError(_) => "invalid syntax",
+        ErrTypes::MissingOperator(_) => "expected an operator here",
+        ErrTypes::UnexpectedToken(_) => "not expected here",
+        ErrTypes::MissingValue(_, Some(field)) => return format!("no value for field `{}`", field),
+        ErrTypes::MissingValue(_, None) => "expected a value here",
+        ErrTypes::UnbalancedParentheses(_) => "parentheses do not match",
+        ErrTypes::DivisionByZero(_) => "divides by zero",
+        ErrTypes::TypeMismatch(_, expected, found) => {
+            return format!(
+                "expected {}, found {}",
+                type_name(expected),
+                type_name(found)
+            )
+        }
+        ErrTypes::ReservedKeyword(_) => "reserved keyword",
+        ErrTypes::UnexpectedEndOfInput(_) => "never closed",
+        ErrTypes::InvalidNumberFormat(_) => "not a valid number here",
+        ErrTypes::DuplicateOperator(_) => "follows another operator",
+        ErrTypes::MissingLeftOperand(_) => "nothing on its left",
+        ErrTypes::MissingRightOperand(_) => "nothing on its right",
+        ErrTypes::InvalidConditionSyntax(_) => "invalid condition",
+        ErrTypes::VarISConst(..) => "cannot assign to a constant",
+        ErrTypes::UnknownFunction(..) => "no function with this name",
+        ErrTypes::ArgCountMismatch(_) => "wrong number of arguments",
+        ErrTypes::ReturnOutsideFn(_) => "not inside a function",
+        ErrTypes::ElseWithoutIf(_) => "no `if` before this `else`",
+        ErrTypes::BreakOutsideLoop(_) => "not inside a loop",
+        ErrTypes::ImplicitShadow(..) => "hides a name from an outer block",
+        ErrTypes::MissingBlock(_) => "expected `{` after this",
+        ErrTypes::InvalidEscape(_) => "not a valid escape",
+        ErrTypes::UnknownField(..) => "no field with this name",
+        ErrTypes::MissingReturn(_) => "can end without returning a value",
+        ErrTypes::OutOfRange(_, typ) => {
+            let (min, max) = int_bounds(typ).unwrap_or_default();
+            return format!("does not fit in `{}`, which holds {} to {}", typ, min, max);
+        }
+    };
+    text.to_string()
+}
+
+/// Title, label and hint for a warning.
+pub fn describe_warning(warning: WarnTypes) -> (&'static str, &'static str, &'static str) {
+    match warning {
+        WarnTypes::UnusedVariable(_) => (
+            "Unused Variable",
+            "never read",
+            "Use it, remove it, or start its name with `_`",
+        ),
+        WarnTypes::UnreachableCode(_) => (
+            "Unreachable Code",
+            "this condition is always false",
+            "Remove the block or fix the condition",
+        ),
+        WarnTypes::UnusedAssignment(_) => (
+            "Value Never Read",
+            "this value is never read",
+            "Remove the assignment or read the value before it changes",
         ),
     }
 }
+
+/// Generates an error message based on the error type and source code.
+/// The returned message includes the error position, the code piece, and error details.
+pub fn gen_error_msg(err_type: ErrTypes, code: &str) -> String {
+    format_error_msg(&Diagnostic::from(err_type), code)
+}
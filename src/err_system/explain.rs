@@ -0,0 +1,573 @@
+// Long-form explanations for `neit explain <code>`, one per `ErrTypes` and `WarnTypes` code.
+
+/// Code and explanation pairs, in code order.
+const EXPLANATIONS: [(&str, &str); 36] = [
+    (
+        "N0001",
+        "N0001: Unknown Command
+
+A statement did not start with a known command, or a command that needs a name
+(`may`, `const`, `fn`, `for`, `input`) was not followed by one.
+
+Wrong:
+    may = 5
+
+Corrected:
+    may count = 5",
+    ),
+    (
+        "N0002",
+        "N0002: Unsupported Variable Type
+
+The type given for a variable, parameter or input is not one Neit knows.
+Variables and parameters take `i8`, `i16`, `i32`, `i64`, `f32`, `f64`, `str`,
+`ch` or `bool`, and so does `input`.
+
+Wrong:
+    fn show(count: u32) {
+        println %count
+    }
+
+Corrected:
+    fn show(count: i32) {
+        println %count
+    }",
+    ),
+    (
+        "N0003",
+        "N0003: Variable Already Exists
+
+A name was declared twice in the same block, or a function has two parameters
+with the same name. Each block may declare a name only once, even with `shadow`.
+Assign to the existing variable instead, or pick a new name.
+
+Wrong:
+    may count = 1
+    may count = 2
+
+Corrected:
+    may count = 1
+    count = 2",
+    ),
+    (
+        "N0004",
+        "N0004: Char Variable Length Error
+
+A character literal in single quotes must hold exactly one ASCII character, as
+a `ch` is one byte. Use double quotes for text of any length, and for characters
+such as `é` that take more than one byte.
+
+Wrong:
+    may letter = 'ab'
+
+Corrected:
+    may letter = 'a'
+    may word = \"ab\"",
+    ),
+    (
+        "N0005",
+        "N0005: Invalid Math Usage (retired)
+
+This code is no longer reported. Misused arithmetic is reported with the more
+specific codes N0017, N0018 and N0019. The code is not reused.",
+    ),
+    (
+        "N0006",
+        "N0006: Variable Not Found
+
+A name was used that no visible block declares. Variables only exist from their
+declaration to the end of the block that declares them, and a function body only
+sees its own parameters and variables. When a similar name exists the message
+suggests it.
+
+Wrong:
+    may total = count + 1
+
+Corrected:
+    may count = 0
+    may total = count + 1",
+    ),
+    (
+        "N0007",
+        "N0007: Syntax Error
+
+The code does not form a valid statement. Common causes are a string missing its
+closing quote and a function declared inside another function.
+
+Wrong:
+    may greeting = \"hello
+
+Corrected:
+    may greeting = \"hello\"",
+    ),
+    (
+        "N0008",
+        "N0008: Missing Operator
+
+Two values follow each other with nothing to combine them. Put an operator
+between the values.
+
+Wrong:
+    may total = 1 2
+
+Corrected:
+    may total = 1 + 2",
+    ),
+    (
+        "N0009",
+        "N0009: Unexpected Token
+
+Something appeared where the statement expects something else, such as a
+declaration without `=` or a `for` loop without `in`.
+
+Wrong:
+    may count 5
+
+Corrected:
+    may count = 5",
+    ),
+    (
+        "N0010",
+        "N0010: Missing Value
+
+A statement ended before the value it needs, for example a declaration with
+nothing after `=`. An empty array `[]` also needs its type written, as in
+`may xs: [i32] = []`, and a struct literal needs a value for every field; the
+message names the first field that has none.
+
+Wrong:
+    may count =
+
+Corrected:
+    may count = 0",
+    ),
+    (
+        "N0011",
+        "N0011: Unbalanced Parentheses
+
+An opening `(` or `[` has no matching `)` or `]`, or a closing `)`, `]` or `}`
+has no matching opening one. A `{` that is never closed is N0015.
+
+Wrong:
+    may total = (1 + 2
+
+Corrected:
+    may total = (1 + 2)",
+    ),
+    (
+        "N0012",
+        "N0012: Division By Zero
+
+A value is divided by the literal `0`. This would fail when the program runs, so
+it is rejected at compile time.
+
+Wrong:
+    may half = 4 / 0
+
+Corrected:
+    may half = 4 / 2",
+    ),
+    (
+        "N0013",
+        "N0013: Type Mismatch
+
+A value has a type that does not fit where it is used; the message names the
+type wanted and the type found. Arithmetic takes numbers only, comparisons take
+two numbers or two values of the same type, and a condition that is not a
+comparison must be a `bool`. A number can be stored where a
+wider number is wanted (`i8` < `i16` < `i32` < `i64` < `f32` < `f64`), and an
+integer literal anywhere it fits, but going narrower, such as a float into an
+integer, needs a conversion with `value(type)`. Arrays are used through their
+elements, an index must be an integer, `push` and `pop` need a growable array,
+and an array literal must have as many elements as a fixed-length type. Structs
+are used through their fields, and each field takes values of its own type.
+
+Wrong:
+    may ratio = 2.5
+    may count = 1
+    count = ratio
+
+Corrected:
+    may ratio = 2.5
+    may count = 1
+    count = ratio(i32)",
+    ),
+    (
+        "N0014",
+        "N0014: Reserved Keyword
+
+A name is reserved by Neit. Functions cannot be called `len`, `push` or `pop`,
+which always mean the array builtins, structs cannot take the name of a type
+such as `i32`, and keywords such as `while` should not be used as names either.
+
+Wrong:
+    fn len() -> i32 {
+        return 3
+    }
+
+Corrected:
+    fn size() -> i32 {
+        return 3
+    }",
+    ),
+    (
+        "N0015",
+        "N0015: Unexpected End Of Input
+
+The file ended inside a block: a `{` was never closed by a `}`, or a `##` block
+comment was never closed by another `##`.
+
+Wrong:
+    fn greet() {
+        println hi
+
+Corrected:
+    fn greet() {
+        println hi
+    }",
+    ),
+    (
+        "N0016",
+        "N0016: Invalid Number Format
+
+Text that should be a number could not be read as one.
+
+Wrong:
+    may ratio = 1.2.3
+
+Corrected:
+    may ratio = 1.23",
+    ),
+    (
+        "N0017",
+        "N0017: Duplicate Operator
+
+An operator directly follows another one, leaving the first without a right-hand
+value.
+
+Wrong:
+    may total = 1 + * 2
+
+Corrected:
+    may total = 1 * 2",
+    ),
+    (
+        "N0018",
+        "N0018: Missing Left Operand
+
+An operator has no value on its left. Only `-` may start a value, to negate it.
+
+Wrong:
+    may total = * 2
+
+Corrected:
+    may total = 3 * 2",
+    ),
+    (
+        "N0019",
+        "N0019: Missing Right Operand
+
+An operator has no value on its right.
+
+Wrong:
+    may total = 1 +
+
+Corrected:
+    may total = 1 + 2",
+    ),
+    (
+        "N0020",
+        "N0020: Unsupported Operator (retired)
+
+This code is no longer reported. An operator Neit does not support, such as
+`%`, is reported as N0009. The code is not reused.",
+    ),
+    (
+        "N0021",
+        "N0021: Invalid Condition Syntax
+
+A condition is empty or incomplete, for example an `if` with nothing before `{`
+or an `&&` or `||` with nothing after it.
+
+Wrong:
+    may age = 20
+    if age > 12 && {
+        println welcome
+    }
+
+Corrected:
+    may age = 20
+    if age > 12 && age < 65 {
+        println welcome
+    }",
+    ),
+    (
+        "N0022",
+        "N0022: Constant Variable Error
+
+A variable declared with `const` was assigned to. Declare it with `may` if it
+needs to change.
+
+Wrong:
+    const limit = 10
+    limit = 20
+
+Corrected:
+    may limit = 10
+    limit = 20",
+    ),
+    (
+        "N0023",
+        "N0023: Unknown Function
+
+A call names a function that has not been declared. Functions must be declared
+with `fn` before they are called. When a similar name exists the message
+suggests it.
+
+Wrong:
+    may total = add(1, 2)
+
+Corrected:
+    fn add(a: i32, b: i32) -> i32 {
+        return a + b
+    }
+    may total = add(1, 2)",
+    ),
+    (
+        "N0024",
+        "N0024: Argument Count Mismatch
+
+A function was called with more or fewer arguments than it has parameters.
+
+Wrong:
+    fn double(n: i32) -> i32 {
+        return n * 2
+    }
+    may total = double(1, 2)
+
+Corrected:
+    fn double(n: i32) -> i32 {
+        return n * 2
+    }
+    may total = double(1)",
+    ),
+    (
+        "N0025",
+        "N0025: Return Outside Function
+
+`return` can only be used inside a function body.
+
+Wrong:
+    return 1
+
+Corrected:
+    fn one() -> i32 {
+        return 1
+    }",
+    ),
+    (
+        "N0026",
+        "N0026: Else Without If
+
+An `else` must directly follow the closing `}` of an `if` or `else if` block.
+
+Wrong:
+    else {
+        println no
+    }
+
+Corrected:
+    may answer = 0
+    if answer == 1 {
+        println yes
+    } else {
+        println no
+    }",
+    ),
+    (
+        "N0027",
+        "N0027: Break Outside Loop
+
+`break` and `continue` can only be used inside a `while` or `for` loop.
+
+Wrong:
+    break
+
+Corrected:
+    for i in 0..10 {
+        break
+    }",
+    ),
+    (
+        "N0028",
+        "N0028: Name Already Declared In An Outer Block
+
+A block declared a name that is already visible from an outer block. This is
+usually a mistake, so it must be spelled out with `may shadow`, or the inner
+variable renamed.
+
+Wrong:
+    may count = 1
+    if count > 0 {
+        may count = 2
+    }
+
+Corrected:
+    may count = 1
+    if count > 0 {
+        may shadow count = 2
+    }",
+    ),
+    (
+        "N0029",
+        "N0029: Missing Block
+
+An `if`, `else if`, `while` or `for` header did not end with the `{` that opens
+its body. The header must fit on one line, unless it continues inside
+parentheses or after `&&` or `||`.
+
+Wrong:
+    may count = 0
+    while count < 3
+        count += 1
+    }
+
+Corrected:
+    may count = 0
+    while count < 3 {
+        count += 1
+    }",
+    ),
+    (
+        "N0030",
+        "N0030: Invalid Escape
+
+A backslash in a string starts an escape, and only `\\n` (new line), `\\t` (tab),
+`\\\"` (quote), `\\\\` (backslash) and `\\u{...}` (the character with that hex
+code, e.g. `\\u{e9}`) are known. Write `\\\\` for a backslash of its own.
+
+Wrong:
+    may path = \"C:\\data\"
+
+Corrected:
+    may path = \"C:\\\\data\"",
+    ),
+    (
+        "N0031",
+        "N0031: Unknown Field
+
+A struct value is used with a field its struct does not declare, or something
+that is not a struct is used with `.field`.
+
+Wrong:
+    struct Point { x: f64, y: f64 }
+    may p = Point { x: 1.0, y: 2.0 }
+    p.z = 3.0
+
+Corrected:
+    struct Point { x: f64, y: f64 }
+    may p = Point { x: 1.0, y: 2.0 }
+    p.y = 3.0",
+    ),
+    (
+        "N0032",
+        "N0032: Missing Return
+
+A function declared with `-> type` can reach the end of its body without a
+`return`, so some calls would have no value to give back. Every path through the
+body must end in a `return`: an `if` needs an `else` that returns too, and a loop
+only counts when it is `while true` without a `break`.
+
+Wrong:
+    fn sign(x: i32) -> str {
+        if x > 0 {
+            return \"positive\"
+        }
+    }
+
+Corrected:
+    fn sign(x: i32) -> str {
+        if x > 0 {
+            return \"positive\"
+        }
+        return \"not positive\"
+    }",
+    ),
+    (
+        "N0033",
+        "N0033: Number Out Of Range
+
+An integer constant does not fit the integer type it is stored in, passed as or
+converted to. The message gives the range the type holds: `i8` holds -128 to
+127, `i16` -32768 to 32767, `i32` about two billion either way, and every
+integer literal has to fit an `i64`.
+
+Wrong:
+    may level: i8 = 300
+
+Corrected:
+    may level: i16 = 300",
+    ),
+    (
+        "W0001",
+        "W0001: Unused Variable
+
+A variable or parameter is never read. This is often a typo in a later use of
+the name. Unused declarations are dropped from the build. Start the name with `_`
+to keep it quiet, or add `# neit: allow(unused)` at the end of its line or on the
+line above.
+
+Wrong:
+    may count = 1
+    may cuont = count + 1
+
+Corrected:
+    may count = 1
+    may next = count + 1
+    println %next",
+    ),
+    (
+        "W0002",
+        "W0002: Unreachable Code
+
+An `if`, `else if` or `while` condition compares only literals and is always
+false, so its block never runs. Silence it with `# neit: allow(unreachable)`.
+
+Wrong:
+    if 1 > 2 {
+        println never
+    }
+
+Corrected:
+    may limit = 2
+    if limit > 1 {
+        println sometimes
+    }",
+    ),
+    (
+        "W0003",
+        "W0003: Value Never Read
+
+A value is assigned and then overwritten, or its variable goes out of scope, before
+anything reads it. Either the assignment is not needed or a read is missing. This
+is an `unused` warning, so `# neit: allow(unused)` silences it.
+
+Wrong:
+    may total = 0
+    total = 5
+    println %total
+
+Corrected:
+    may total = 5
+    println %total",
+    ),
+];
+
+/// The long explanation for an error code such as `N0006` or a warning code such as `W0001`,
+/// ignoring case.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code.trim()))
+        .map(|(_, text)| *text)
+}
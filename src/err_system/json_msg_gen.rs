@@ -0,0 +1,70 @@
+use super::diagnostic::Diagnostic;
+use crate::tok_system::tokens::Span;
+
+/// Quotes a string for JSON.
+pub fn json_str(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A span as a JSON object: byte offsets plus 1-based start and end positions.
+fn json_span(span: Span, code: &str) -> String {
+    let (end_line, end_column) = span.end_pos(code);
+    format!(
+        "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{}}}",
+        span.start, span.end, span.line, span.col, end_line, end_column
+    )
+}
+
+/// Renders a diagnostic as a single-line JSON object with its code, severity, file, span,
+/// message, notes and suggestions.
+pub fn gen_json_msg(diag: &Diagnostic, file: &str, code: &str) -> String {
+    let notes: Vec<String> = diag
+        .notes
+        .iter()
+        .map(|note| {
+            format!(
+                "{{\"message\":{},\"span\":{}}}",
+                json_str(note.message),
+                json_span(note.span, code)
+            )
+        })
+        .collect();
+    let suggestions: Vec<String> = diag
+        .suggestions
+        .iter()
+        .map(|suggestion| {
+            format!(
+                "{{\"message\":{},\"span\":{},\"replacement\":{}}}",
+                json_str(&suggestion.message),
+                json_span(suggestion.span, code),
+                json_str(&suggestion.replacement)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"code\":{},\"severity\":{},\"file\":{},\"message\":{},\"label\":{},\"hint\":{},\"span\":{},\"notes\":[{}],\"suggestions\":[{}]}}",
+        json_str(diag.code),
+        json_str(diag.severity.as_str()),
+        json_str(file),
+        json_str(diag.title),
+        json_str(&diag.label),
+        json_str(diag.hint),
+        json_span(diag.span, code),
+        notes.join(","),
+        suggestions.join(",")
+    )
+}
@@ -1,2 +1,6 @@
+pub mod diagnostic;
 pub mod err_types;
 pub mod error_msg_gen;
+pub mod explain;
+pub mod json_msg_gen;
+pub mod warn_types;
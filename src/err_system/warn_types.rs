@@ -0,0 +1,64 @@
+use crate::tok_system::tokens::Span;
+
+/// Problems that do not stop a build. They are reported apart from `ErrTypes` and only fail the
+/// build with `--deny-warnings`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarnTypes {
+    /// A variable or parameter that is never read.
+    UnusedVariable(Span),
+    /// A block behind a condition that is always false.
+    UnreachableCode(Span),
+    /// A value that is overwritten or goes out of scope before it is read.
+    UnusedAssignment(Span),
+}
+
+impl WarnTypes {
+    /// Stable code for the warning, e.g. `W0001`, used by `neit explain` and in tooling.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarnTypes::UnusedVariable(..) => "W0001",
+            WarnTypes::UnreachableCode(..) => "W0002",
+            WarnTypes::UnusedAssignment(..) => "W0003",
+        }
+    }
+
+    /// The name used in `# neit: allow(...)` comments.
+    pub fn lint(&self) -> &'static str {
+        match self {
+            WarnTypes::UnusedVariable(..) | WarnTypes::UnusedAssignment(..) => "unused",
+            WarnTypes::UnreachableCode(..) => "unreachable",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            WarnTypes::UnusedVariable(span)
+            | WarnTypes::UnreachableCode(span)
+            | WarnTypes::UnusedAssignment(span) => *span,
+        }
+    }
+
+    /// Whether a `# neit: allow(...)` comment naming this lint sits at the end of the warning's
+    /// line, or alone on the line above it.
+    pub fn allowed(&self, code: &str) -> bool {
+        let Ok(line) = usize::try_from(self.span().line - 1) else {
+            return false;
+        };
+        let lines: Vec<&str> = code.lines().collect();
+        let allows = |text: &str| {
+            allow_list(text).is_some_and(|lints| lints.split(',').any(|l| l.trim() == self.lint()))
+        };
+        lines.get(line).is_some_and(|text| allows(text))
+            || line
+                .checked_sub(1)
+                .and_then(|above| lines.get(above))
+                .is_some_and(|text| text.trim_start().starts_with('#') && allows(text))
+    }
+}
+
+/// The lint names inside a `# neit: allow(...)` comment on this line.
+fn allow_list(line: &str) -> Option<&str> {
+    let (_, directive) = line.split_once("# neit:")?;
+    let lints = directive.trim_start().strip_prefix("allow(")?;
+    Some(&lints[..lints.find(')')?])
+}
@@ -0,0 +1,425 @@
+// `neit fmt`: rewrites a source file in the canonical layout. Blocks are indented by four spaces,
+// operators get one space on each side, and text that is printed, quoted or commented is kept
+// as it was written.
+use crate::{
+    tok_system::tokens::{Token, TokenKind},
+    tokenize,
+};
+
+const INDENT: &str = "    ";
+
+/// Words after which `(` and a leading `-` are spaced like an operand, not a call.
+const CONTROL_WORDS: [&str; 7] = ["if", "while", "for", "in", "step", "return", "else"];
+
+/// Words that start a statement with a block.
+const BLOCK_WORDS: [&str; 5] = ["if", "else", "while", "for", "fn"];
+
+/// Commands whose argument is the rest of the line, printed as written.
+const PRINT_WORDS: [&str; 4] = ["print", "println", "eprint", "eprintln"];
+
+/// One piece of a formatted line.
+#[derive(Debug, Clone, PartialEq)]
+enum Unit {
+    /// Names, numbers, keywords, literals and printed text, written as they are.
+    Word(String),
+    /// A binary operator such as `=`, `+=`, `>=`, `&&` or `->`, with a space on each side.
+    Op(&'static str),
+    /// `!` or a leading `-`, written against what follows.
+    Prefix(&'static str),
+    Open,
+    Close,
+    /// `[` and `]`, around array elements, an index or an array type.
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Colon,
+    LBrace,
+    RBrace,
+    /// A `#` comment, or a `## ... ##` comment that fits on one line.
+    Comment(String),
+}
+
+impl Unit {
+    fn text(&self) -> &str {
+        match self {
+            Unit::Word(text) | Unit::Comment(text) => text,
+            Unit::Op(op) | Unit::Prefix(op) => op,
+            Unit::Open => "(",
+            Unit::Close => ")",
+            Unit::OpenBracket => "[",
+            Unit::CloseBracket => "]",
+            Unit::Comma => ",",
+            Unit::Colon => ":",
+            Unit::LBrace => "{",
+            Unit::RBrace => "}",
+        }
+    }
+}
+
+/// Whether a space goes between two units. `gap` says whether the source had one.
+fn spaced(prev: &Unit, next: &Unit, gap: bool) -> bool {
+    match (prev, next) {
+        (_, Unit::Comment(_)) => true,
+        (_, Unit::Comma | Unit::Colon | Unit::Close | Unit::CloseBracket) => false,
+        (Unit::Open | Unit::OpenBracket | Unit::Prefix(_), _) => false,
+        (Unit::Comma | Unit::Colon | Unit::Op(_) | Unit::Comment(_), _) => true,
+        (Unit::LBrace, _) => true,
+        (_, Unit::Op(_) | Unit::LBrace | Unit::RBrace) | (Unit::RBrace, _) => true,
+        (Unit::Word(word), Unit::Open | Unit::OpenBracket) => {
+            CONTROL_WORDS.contains(&word.as_str())
+        }
+        (_, Unit::Open) => false,
+        // `xs[i]` indexes what is right before it.
+        (Unit::Close | Unit::CloseBracket, Unit::OpenBracket) => false,
+        // A range such as `0..-5` keeps its sign against the bound.
+        (Unit::Word(word), Unit::Prefix(_)) if word.ends_with("..") || word.ends_with("..=") => gap,
+        (Unit::Word(_) | Unit::Close, Unit::Prefix(_)) => true,
+        (Unit::Close | Unit::CloseBracket, Unit::Word(_)) => true,
+        _ => gap,
+    }
+}
+
+#[derive(Default)]
+struct Formatter<'a> {
+    code: &'a str,
+    out: Vec<String>,
+    /// Units of the line being built, each with whether the source had a space before it.
+    line: Vec<(Unit, bool)>,
+    /// Indentation of the line being built, fixed by its first unit.
+    line_indent: usize,
+    depth: usize,
+    parens: usize,
+    /// The previous line ended inside parentheses or after `&&` or `||`.
+    continued: bool,
+    /// A line was just ended by a brace, so the line end that follows is not a blank line.
+    broke_line: bool,
+    /// Whether whitespace came before the next unit.
+    gap: bool,
+    /// Braces opened on the line being built that close on it too, as in a struct literal.
+    inline_braces: usize,
+}
+
+impl Formatter<'_> {
+    fn push(&mut self, unit: Unit) {
+        if self.line.is_empty() {
+            if unit == Unit::RBrace {
+                self.depth = self.depth.saturating_sub(1);
+            }
+            self.line_indent = self.depth + usize::from(self.continued);
+        }
+        match unit {
+            Unit::Open => self.parens += 1,
+            Unit::Close => self.parens = self.parens.saturating_sub(1),
+            _ => {}
+        }
+        self.line.push((unit, self.gap));
+        self.gap = false;
+        self.broke_line = false;
+    }
+
+    /// Writes out the line being built, if it has anything in it.
+    fn end_line(&mut self) {
+        let Some(((first, _), rest)) = self.line.split_first() else {
+            return;
+        };
+        let mut text = INDENT.repeat(self.line_indent);
+        text.push_str(first.text());
+        let mut prev = first;
+        for (unit, gap) in rest {
+            if spaced(prev, unit, *gap) {
+                text.push(' ');
+            }
+            text.push_str(unit.text());
+            prev = unit;
+        }
+        self.continued = self.parens > 0 || matches!(prev, Unit::Op("&&" | "||"));
+        if !self.continued {
+            self.parens = 0;
+        }
+        self.out.push(text);
+        self.line.clear();
+    }
+
+    /// A line end in the source.
+    fn line_break(&mut self) {
+        if !self.line.is_empty() {
+            self.end_line();
+        } else if !self.broke_line && self.out.last().is_some_and(|line| !line.is_empty()) {
+            // Runs of blank lines become one.
+            self.out.push(String::new());
+        }
+        self.broke_line = false;
+        self.gap = false;
+    }
+
+    /// A `## ... ##` comment that spans lines: the markers go on their own lines at the block's
+    /// indentation and the text between them is kept as written.
+    fn block_comment(&mut self, text: &str) {
+        self.end_line();
+        let indent = INDENT.repeat(self.depth);
+        let closed = text.len() >= 4 && text.ends_with("##");
+        let inner = if closed {
+            &text[2..text.len() - 2]
+        } else {
+            &text[2..]
+        };
+        let mut lines = inner.lines();
+        self.out.push(format!("{}##", indent));
+        if let Some(first) = lines
+            .next()
+            .map(str::trim)
+            .filter(|first| !first.is_empty())
+        {
+            self.out.push(format!("{}{}", indent, first));
+        }
+        let mut rest: Vec<&str> = lines.map(str::trim_end).collect();
+        if rest.last().is_some_and(|last| last.trim().is_empty()) {
+            rest.pop();
+        }
+        self.out.extend(rest.into_iter().map(String::from));
+        if closed {
+            self.out.push(format!("{}##", indent));
+        }
+        self.broke_line = true;
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line();
+        while self.out.last().is_some_and(|line| line.is_empty()) {
+            self.out.pop();
+        }
+        if self.out.is_empty() {
+            return String::new();
+        }
+        self.out.join("\n") + "\n"
+    }
+}
+
+/// Where the character literal opened by `tok` ends: just past the token holding the closing
+/// quote, or at the end of the line if it is never closed.
+fn literal_end(code: &str, tokens: &[Token], tok: &Token) -> usize {
+    let line_end = code[tok.span.start..]
+        .find('\n')
+        .map_or(code.len(), |at| tok.span.start + at);
+    let Some(close) = code[tok.span.end..line_end]
+        .find('\'')
+        .map(|at| tok.span.end + at)
+    else {
+        return line_end;
+    };
+    tokens
+        .iter()
+        .find(|tok| tok.span.start <= close && close < tok.span.end)
+        .map_or(close + 1, |tok| tok.span.end)
+}
+
+/// Whether a `{` is closed later on its own line, with `rest` the tokens after it. A block
+/// statement's braces always go on lines of their own.
+fn inline_brace(f: &Formatter, rest: &[Token]) -> bool {
+    let block = match f.line.first() {
+        Some((Unit::Word(word), _)) => BLOCK_WORDS.contains(&word.as_str()),
+        // `} else {`
+        Some((Unit::RBrace, _)) | None => true,
+        Some(_) => false,
+    };
+    if block {
+        return false;
+    }
+    let mut depth = 0;
+    for tok in rest {
+        match tok.kind {
+            TokenKind::EOL | TokenKind::EOF => return false,
+            TokenKind::LCurly => depth += 1,
+            TokenKind::RCurly if depth == 0 => return true,
+            TokenKind::RCurly => depth -= 1,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Formats Neit source code. Returns `None` if the result would not read as the same tokens and
+/// comments, which would be a bug in the formatter; the caller should then leave the file alone.
+pub fn format_source(code: &str) -> Option<String> {
+    let tokens = tokenize(code);
+    let mut f = Formatter {
+        code,
+        ..Formatter::default()
+    };
+    let mut i = 0;
+    while let Some(tok) = tokens.get(i) {
+        i += 1;
+        for comment in &tok.comments {
+            // A block comment on lines of its own is laid out by itself; one inside a line is
+            // kept as written.
+            if comment.block && comment.text.contains('\n') && f.line.is_empty() {
+                f.block_comment(&comment.text);
+            } else {
+                f.gap = true;
+                f.push(Unit::Comment(comment.text.clone()));
+                f.gap = true;
+            }
+        }
+        let adjacent = |next: usize| {
+            tokens
+                .get(next)
+                .filter(|next| next.span.start == tok.span.end)
+                .map(|next| &next.kind)
+        };
+        let with_eq = |i: &mut usize, plain: &'static str, eq: &'static str| {
+            if adjacent(*i) == Some(&TokenKind::EqSign) {
+                *i += 1;
+                eq
+            } else {
+                plain
+            }
+        };
+        let unit = match &tok.kind {
+            TokenKind::Space => {
+                f.gap = true;
+                continue;
+            }
+            TokenKind::EOL => {
+                f.line_break();
+                continue;
+            }
+            TokenKind::EOF => break,
+            // `Point { x: 1.0 }` and `struct Point { x: f64 }` stay on one line.
+            TokenKind::LCurly if inline_brace(&f, &tokens[i..]) => {
+                f.inline_braces += 1;
+                Unit::LBrace
+            }
+            TokenKind::RCurly if f.inline_braces > 0 => {
+                f.inline_braces -= 1;
+                Unit::RBrace
+            }
+            TokenKind::LCurly => {
+                f.push(Unit::LBrace);
+                f.end_line();
+                f.depth += 1;
+                f.broke_line = true;
+                continue;
+            }
+            TokenKind::RCurly => {
+                f.end_line();
+                Unit::RBrace
+            }
+            TokenKind::Iden(word) if f.line.is_empty() && PRINT_WORDS.contains(&word.as_str()) => {
+                f.push(Unit::Word(word.clone()));
+                // Inside a block, a `}` that is not matched in the text closes the block.
+                let mut braces = 0;
+                let mut end = tok.span.end;
+                while let Some(tok) = tokens.get(i) {
+                    match tok.kind {
+                        TokenKind::EOL | TokenKind::EOF => break,
+                        TokenKind::LCurly => braces += 1,
+                        TokenKind::RCurly if braces == 0 && f.depth > 0 => break,
+                        TokenKind::RCurly => braces -= 1,
+                        _ => {}
+                    }
+                    end = tok.span.end;
+                    i += 1;
+                }
+                // The first space only separates the command from its text; every other
+                // character is printed, so the text is kept as it is.
+                let raw = &f.code[tok.span.end..end];
+                let mut text = raw.strip_prefix([' ', '\t']).unwrap_or(raw);
+                // Spaces before a trailing comment are not printed.
+                if tokens.get(i).is_some_and(|tok| !tok.comments.is_empty()) {
+                    text = text.trim_end();
+                }
+                if !text.is_empty() {
+                    f.gap = true;
+                    f.push(Unit::Word(text.to_string()));
+                }
+                continue;
+            }
+            TokenKind::Iden(word) => {
+                let text = if word.starts_with('\'') && word.matches('\'').count() % 2 == 1 {
+                    let end = literal_end(f.code, &tokens, tok);
+                    while tokens.get(i).is_some_and(|tok| tok.span.start < end) {
+                        i += 1;
+                    }
+                    f.code[tok.span.start..end].to_string()
+                } else {
+                    word.clone()
+                };
+                // `a..=b` is one range, not an assignment.
+                match f.line.last_mut() {
+                    Some((Unit::Word(prev), _)) if !f.gap && prev.ends_with("..=") => {
+                        prev.push_str(&text);
+                        continue;
+                    }
+                    _ => Unit::Word(text),
+                }
+            }
+            TokenKind::EqSign => match f.line.last_mut() {
+                Some((Unit::Word(prev), _)) if !f.gap && prev.ends_with("..") => {
+                    prev.push('=');
+                    continue;
+                }
+                _ => Unit::Op("="),
+            },
+            TokenKind::DoubleEqSign => Unit::Op("=="),
+            TokenKind::And => Unit::Op("&&"),
+            TokenKind::Or => Unit::Op("||"),
+            TokenKind::GreaterThan => Unit::Op(with_eq(&mut i, ">", ">=")),
+            TokenKind::LessThan => Unit::Op(with_eq(&mut i, "<", "<=")),
+            TokenKind::ADDOP => Unit::Op(with_eq(&mut i, "+", "+=")),
+            TokenKind::MULTIOP => Unit::Op(with_eq(&mut i, "*", "*=")),
+            TokenKind::DIVOP => Unit::Op(with_eq(&mut i, "/", "/=")),
+            TokenKind::PercentSign => Unit::Op("%"),
+            TokenKind::Not => match with_eq(&mut i, "!", "!=") {
+                "!" => Unit::Prefix("!"),
+                op => Unit::Op(op),
+            },
+            TokenKind::SUBOP if adjacent(i) == Some(&TokenKind::GreaterThan) => {
+                i += 1;
+                Unit::Op("->")
+            }
+            TokenKind::SUBOP => match with_eq(&mut i, "-", "-=") {
+                "-" => {
+                    let operand = match f.line.last() {
+                        Some((Unit::Word(word), _)) => {
+                            !CONTROL_WORDS.contains(&word.as_str())
+                                && !word.ends_with("..")
+                                && !word.ends_with("..=")
+                        }
+                        Some((Unit::Close | Unit::CloseBracket, _)) => true,
+                        _ => false,
+                    };
+                    if operand {
+                        Unit::Op("-")
+                    } else {
+                        Unit::Prefix("-")
+                    }
+                }
+                op => Unit::Op(op),
+            },
+            TokenKind::LSmallBrac => Unit::Open,
+            TokenKind::RSmallBracket => Unit::Close,
+            TokenKind::LSquareBrac => Unit::OpenBracket,
+            TokenKind::RSquareBracket => Unit::CloseBracket,
+            TokenKind::Comma => Unit::Comma,
+            TokenKind::Colon => Unit::Colon,
+            TokenKind::BackSlash => Unit::Word("\\".to_string()),
+            TokenKind::Str { .. } => Unit::Word(f.code[tok.span.start..tok.span.end].to_string()),
+        };
+        f.push(unit);
+    }
+    let formatted = f.finish();
+    // Tokens and the words of comments, without layout.
+    let meaning = |code: &str| -> Vec<String> {
+        let mut words = Vec::new();
+        for tok in tokenize(code) {
+            for comment in &tok.comments {
+                words.push(comment.text.split_whitespace().collect());
+            }
+            if !matches!(tok.kind, TokenKind::Space | TokenKind::EOL) {
+                words.push(format!("{:?}", tok.kind));
+            }
+        }
+        words
+    };
+    (meaning(&formatted) == meaning(code)).then_some(formatted)
+}
@@ -0,0 +1,173 @@
+use super::{c_condmk::mk_c_cond, symbol_table::SymbolTable, BinOp, Expr, UnaryOp};
+use crate::type_system::{elem_type, infer, is_int};
+
+#[inline(always)]
+fn op_str(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+    }
+}
+
+/// Maps a Neit type name to the C type used in the generated code.
+pub fn c_type(typ: &str) -> &str {
+    match typ {
+        "str" => "nstring",
+        "ch" => "char",
+        other if elem_type(other).is_some() => "narray",
+        other => other,
+    }
+}
+
+/// The C name of a user function. Functions are prefixed so that names such as `main` or
+/// `write` cannot clash with C, libc or nulibc.
+pub fn c_fn_name(name: &str) -> String {
+    format!("neit_fn_{}", name)
+}
+
+/// The C element type of an array variable, `int` when the name is not an array.
+fn c_elem(name: &str, collected_vars: &SymbolTable) -> &'static str {
+    let elem = collected_vars
+        .lookup(name)
+        .and_then(|typ| elem_type(typ.trim_start_matches("const;")));
+    match elem {
+        Some(elem) => c_type(elem),
+        None => "int",
+    }
+}
+
+/// Escapes text for use inside a C string or character literal. Control characters become
+/// octal escapes; other characters, including non-ASCII ones, are kept as they are.
+pub fn c_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_ascii_control() => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Renders an expression as C. Every operation is wrapped in parentheses, so the generated code
+/// evaluates in the same order as the tree regardless of C's own precedence rules.
+/// `collected_vars` tells which comparisons are between strings.
+pub fn mk_c_expr(expr: &Expr, collected_vars: &SymbolTable) -> String {
+    match expr {
+        Expr::Int(val) => val.to_string(),
+        Expr::Float(val) => format!("{:?}", val),
+        Expr::Str(text) => format!("nstr_new(\"{}\")", c_escape(text)),
+        Expr::Char(c) => format!("'{}'", c_escape(&c.to_string())),
+        Expr::Bool(val) => val.to_string(),
+        Expr::Var(name) => name.clone(),
+        Expr::Call(name, args) => {
+            let args: Vec<String> = args
+                .iter()
+                .map(|arg| mk_c_expr(arg, collected_vars))
+                .collect();
+            format!("{}({})", c_fn_name(name), args.join(", "))
+        }
+        Expr::Unary(UnaryOp::Neg, operand) => {
+            format!("(-{})", mk_c_expr(operand, collected_vars))
+        }
+        // Integer division stops the program at a zero divisor instead of crashing it.
+        Expr::Binary(left, BinOp::Div, right, line) if is_int(infer(expr, collected_vars)) => {
+            format!(
+                "(({})ndiv({}, {}, {}))",
+                infer(expr, collected_vars),
+                mk_c_expr(left, collected_vars),
+                mk_c_expr(right, collected_vars),
+                line
+            )
+        }
+        Expr::Binary(left, op, right, _) => {
+            format!(
+                "({} {} {})",
+                mk_c_expr(left, collected_vars),
+                op_str(*op),
+                mk_c_expr(right, collected_vars)
+            )
+        }
+        // Operations already carry their own parentheses.
+        Expr::Group(inner) => match inner.as_ref() {
+            Expr::Binary(..) | Expr::Unary(..) => mk_c_expr(inner, collected_vars),
+            _ => format!("({})", mk_c_expr(inner, collected_vars)),
+        },
+        Expr::Cast(inner, typ) => format!("(({}){})", typ, mk_c_expr(inner, collected_vars)),
+        Expr::Cond(cond) => mk_c_cond(cond, collected_vars),
+        Expr::Array(items) => {
+            let elem = elem_type(infer(expr, collected_vars)).unwrap_or("i32");
+            mk_c_array(items, elem, collected_vars)
+        }
+        Expr::Index(name, index, line) => format!(
+            "(*({} *)narray_at(&{}, {}, {}))",
+            c_elem(name, collected_vars),
+            name,
+            mk_c_expr(index, collected_vars),
+            line
+        ),
+        Expr::Len(name) => format!("((i32){}.len)", name),
+        Expr::Pop(name, line) => format!(
+            "(*({} *)narray_pop(&{}, {}))",
+            c_elem(name, collected_vars),
+            name,
+            line
+        ),
+        Expr::Struct(name, fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(field, value)| format!(".{} = {}", field, mk_c_expr(value, collected_vars)))
+                .collect();
+            format!("(({}){{{}}})", name, fields.join(", "))
+        }
+        Expr::Field(var, field) => format!("{}.{}", var, field),
+    }
+}
+
+/// Renders an array literal as a new `narray` of `elem` values.
+pub fn mk_c_array(items: &[Expr], elem: &str, collected_vars: &SymbolTable) -> String {
+    let elem = c_type(elem);
+    if items.is_empty() {
+        return format!("narray_new(sizeof({}), 0, NULL)", elem);
+    }
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| mk_c_expr(item, collected_vars))
+        .collect();
+    format!(
+        "narray_new(sizeof({elem}), {}, ({elem}[]){{{}}})",
+        items.len(),
+        items.join(", ")
+    )
+}
+
+/// Renders a value stored as `typ`. An array literal takes its element type from `typ`, so
+/// `[]` and widened items are built with the right element size.
+pub fn mk_c_value(expr: &Expr, typ: &str, collected_vars: &SymbolTable) -> String {
+    let elem = elem_type(typ.trim_start_matches("const;"));
+    match (expr, elem) {
+        (Expr::Array(items), Some(elem)) => mk_c_array(items, elem, collected_vars),
+        _ => mk_c_expr(expr, collected_vars),
+    }
+}
+
+/// Whether the expression produces an `nstring` in the generated C.
+pub fn is_str_expr(expr: &Expr, collected_vars: &SymbolTable) -> bool {
+    infer(expr, collected_vars) == "str"
+}
+
+/// Renders a string expression as a C `char *`, e.g. for `strcmp` or `printf`.
+pub fn mk_c_str(expr: &Expr, collected_vars: &SymbolTable) -> String {
+    match expr {
+        Expr::Str(text) => format!("\"{}\"", c_escape(text)),
+        other => format!("{}.str", mk_c_expr(other, collected_vars)),
+    }
+}
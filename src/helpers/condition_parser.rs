@@ -1,134 +1,235 @@
-use super::{ChildCond, CondToks, Condition, LogicalJoin, Operand};
-use crate::{err_system::err_types::ErrTypes, tok_system::tokens::Token};
-use std::collections::HashMap;
-
-// Pre-computed valid type combinations using phf for O(1) lookup
-static VALID_TYPE_COMBINATIONS: phf::Map<&'static str, bool> = phf::phf_map! {
-    "i8_i8" => true, "i8_i16" => true, "i8_i32" => true,
-    "i8_i64" => true, "i8_f32" => true, "i8_f64" => true,
-    "i16_i8" => true, "i16_i16" => true, "i16_i32" => true,
-    "i16_i64" => true, "i16_f32" => true, "i16_f64" => true,
-    "i32_i8" => true, "i32_i16" => true, "i32_i32" => true,
-    "i32_i64" => true, "i32_f32" => true, "i32_f64" => true,
-    "i64_i8" => true, "i64_i16" => true, "i64_i32" => true,
-    "i64_i64" => true, "i64_f32" => true, "i64_f64" => true,
-    "f32_i8" => true, "f32_i16" => true, "f32_i32" => true,
-    "f32_i64" => true, "f32_f32" => true, "f32_f64" => true,
-    "f64_i8" => true, "f64_i16" => true, "f64_i32" => true,
-    "f64_i64" => true, "f64_f32" => true, "f64_f64" => true,
-    "str_str" => true, "ch_ch" => true,
+use super::{
+    expr_parser::{parse_operand, resolve_cond},
+    symbol_table::SymbolTable,
+    ChildCond, CondToks, Condition, LogicalJoin,
+};
+use crate::{
+    err_system::err_types::ErrTypes,
+    tok_system::tokens::{span_of, Span, Token, TokenKind},
 };
 
+/// Parses condition tokens into a condition tree and resolves the names in it.
+/// Errors are pushed to `collected_errors` and `None` is returned; `at` is reported when there
+/// are no tokens to point at.
 #[inline(always)]
 pub fn parse_condition(
     raw_cond: &[Token],
     collected_errors: &mut Vec<ErrTypes>,
-    collected_vars: &[(String, &'static str)],
-    line: i32,
-) -> Condition {
-    // Pre-allocate with estimated capacity
-    let mut child_conditions = Vec::with_capacity(raw_cond.len() / 4);
-    let mut tokens = raw_cond.iter().peekable();
-
-    // Create variable type lookup table
-    let var_types: HashMap<&str, &'static str> = collected_vars
-        .iter()
-        .map(|(name, typ)| (name.as_str(), *typ))
-        .collect();
+    collected_vars: &SymbolTable,
+    at: Span,
+) -> Option<Condition> {
+    let cond = parse_cond_tree(raw_cond, collected_errors, at)?;
+    resolve_cond(&cond, collected_vars, collected_errors, raw_cond).then_some(cond)
+}
 
-    #[inline(always)]
-    fn parse_operand(
-        s: &str,
-        var_types: &HashMap<&str, &'static str>,
-        collected_errors: &mut Vec<ErrTypes>,
-        line: i32,
-    ) -> (Operand, &'static str) {
-        if s.starts_with('"') && s.ends_with('"') {
-            (Operand::Literal(s[1..s.len() - 1].to_string()), "str")
-        } else if let Ok(n) = s.parse::<f64>() {
-            (Operand::Numeric(n), "f64")
-        } else if let Some(&var_type) = var_types.get(s) {
-            (Operand::Variable(s.to_string()), var_type)
-        } else {
-            collected_errors.push(ErrTypes::VarNotFound(line));
-            (Operand::Variable(s.to_string()), "unknown")
+/// Parses condition tokens into a condition tree, leaving its names to be resolved later, as
+/// `parse_expr` does.
+pub fn parse_cond_tree(
+    raw_cond: &[Token],
+    collected_errors: &mut Vec<ErrTypes>,
+    at: Span,
+) -> Option<Condition> {
+    let whole = span_of(raw_cond).unwrap_or(at);
+    let mut depth = 0;
+    for tok in raw_cond {
+        match tok.kind {
+            TokenKind::LSmallBrac => depth += 1,
+            TokenKind::RSmallBracket => depth -= 1,
+            _ => {}
         }
+        if depth < 0 {
+            break;
+        }
+    }
+    if depth != 0 {
+        collected_errors.push(ErrTypes::UnbalancedParentheses(whole));
+        return None;
+    }
+    CondParser {
+        collected_errors,
+        whole,
     }
+    .or(raw_cond)
+}
 
-    while let Some(tok) = tokens.peek() {
-        if matches!(tok, Token::Space) {
-            tokens.next();
-            continue;
+/// Strips the spaces around a token slice.
+#[inline(always)]
+fn trim(tokens: &[Token]) -> &[Token] {
+    let start = tokens
+        .iter()
+        .position(|tok| tok.kind != TokenKind::Space)
+        .unwrap_or(tokens.len());
+    let end = tokens
+        .iter()
+        .rposition(|tok| tok.kind != TokenKind::Space)
+        .map_or(start, |end| end + 1);
+    &tokens[start..end]
+}
+
+/// Calls `visit` with each token that is not nested in parentheses, until it returns `true`.
+/// Returns the index of that token.
+#[inline(always)]
+fn find_top(tokens: &[Token], mut visit: impl FnMut(usize, &Token) -> bool) -> Option<usize> {
+    let mut depth = 0;
+    for (i, tok) in tokens.iter().enumerate() {
+        match tok.kind {
+            TokenKind::LSmallBrac | TokenKind::LSquareBrac | TokenKind::LCurly => depth += 1,
+            TokenKind::RSmallBracket | TokenKind::RSquareBracket | TokenKind::RCurly => depth -= 1,
+            _ if depth == 0 && visit(i, tok) => return Some(i),
+            _ => {}
         }
+    }
+    None
+}
 
-        // Parse left operand
-        let (left_operand, left_type) = match tokens.next() {
-            Some(Token::Iden(s)) => parse_operand(s, &var_types, collected_errors, line),
-            _ => continue,
-        };
+/// Reads the comparison operator at `i`, returning it with the number of tokens it spans.
+#[inline(always)]
+fn comparison_at(tokens: &[Token], i: usize) -> Option<(CondToks, usize)> {
+    let followed_by_eq = tokens
+        .get(i + 1)
+        .is_some_and(|tok| tok.kind == TokenKind::EqSign);
+    match tokens[i].kind {
+        TokenKind::GreaterThan if followed_by_eq => Some((CondToks::GreaterThanOrEqual, 2)),
+        TokenKind::LessThan if followed_by_eq => Some((CondToks::LessThanOrEqual, 2)),
+        TokenKind::Not if followed_by_eq => Some((CondToks::NotEqual, 2)),
+        TokenKind::GreaterThan => Some((CondToks::GreaterThan, 1)),
+        TokenKind::LessThan => Some((CondToks::LessThan, 1)),
+        TokenKind::DoubleEqSign | TokenKind::EqSign => Some((CondToks::Equal, 1)),
+        _ => None,
+    }
+}
 
-        // Skip spaces efficiently
-        while matches!(tokens.peek(), Some(Token::Space)) {
-            tokens.next();
+/// Whether the parentheses around `inner` hold a condition rather than part of a value,
+/// as in `(a > 1 || b)` versus `(a + 1) > b`.
+fn is_cond_group(inner: &[Token]) -> bool {
+    // `(a) + (b)` starts and ends with parentheses that do not match each other.
+    let mut depth = 0;
+    for tok in inner {
+        match tok.kind {
+            TokenKind::LSmallBrac => depth += 1,
+            TokenKind::RSmallBracket => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            return false;
         }
+    }
+    if find_top(inner, |i, tok| {
+        matches!(tok.kind, TokenKind::And | TokenKind::Or | TokenKind::Not)
+            || comparison_at(inner, i).is_some()
+    })
+    .is_some()
+    {
+        return true;
+    }
+    // `((a > 1))` only shows its condition one level further in.
+    match trim(inner) {
+        [open, nested @ .., close]
+            if open.kind == TokenKind::LSmallBrac && close.kind == TokenKind::RSmallBracket =>
+        {
+            is_cond_group(nested)
+        }
+        _ => false,
+    }
+}
 
-        // Parse operator with early return
-        let operator = match tokens.next() {
-            Some(Token::DoubleEqSign) | Some(Token::EqSign) => CondToks::Equal,
-            Some(Token::GreaterThan) => CondToks::GreaterThan,
-            Some(Token::LessThan) => CondToks::LessThan,
-            Some(Token::Not) if matches!(tokens.peek(), Some(Token::EqSign)) => {
-                tokens.next();
-                CondToks::NotEqual
-            }
-            _ => {
-                collected_errors.push(ErrTypes::MissingOperator(line));
-                continue;
-            }
-        };
+/// Whether value tokens hold a condition, such as `x > 10` or `!done`, rather than arithmetic.
+pub fn is_condition(tokens: &[Token]) -> bool {
+    is_cond_group(tokens)
+}
 
-        // Skip spaces efficiently
-        while matches!(tokens.peek(), Some(Token::Space)) {
-            tokens.next();
-        }
+struct CondParser<'a> {
+    collected_errors: &'a mut Vec<ErrTypes>,
+    /// Span of the whole condition.
+    whole: Span,
+}
 
-        // Parse right operand
-        let (right_operand, right_type) = match tokens.next() {
-            Some(Token::Iden(s)) => parse_operand(s, &var_types, collected_errors, line),
-            _ => continue,
-        };
+impl CondParser<'_> {
+    fn fail<T>(&mut self, err: ErrTypes) -> Option<T> {
+        self.collected_errors.push(err);
+        None
+    }
 
-        // Check type compatibility using O(1) lookup
-        if !VALID_TYPE_COMBINATIONS.contains_key(&format!("{}_{}", left_type, right_type)) {
-            collected_errors.push(ErrTypes::TypeMismatch(line));
-            continue;
-        }
+    // or := and ('||' and)*
+    fn or(&mut self, tokens: &[Token]) -> Option<Condition> {
+        self.logical(tokens, &TokenKind::Or, LogicalJoin::Or, Self::and)
+    }
+
+    // and := not ('&&' not)*
+    fn and(&mut self, tokens: &[Token]) -> Option<Condition> {
+        self.logical(tokens, &TokenKind::And, LogicalJoin::And, Self::not)
+    }
 
-        // Skip spaces efficiently
-        while matches!(tokens.peek(), Some(Token::Space)) {
-            tokens.next();
+    /// Splits on the top-level joiner and folds the parts from left to right.
+    fn logical(
+        &mut self,
+        mut tokens: &[Token],
+        joiner_tok: &TokenKind,
+        joiner: LogicalJoin,
+        operand: fn(&mut Self, &[Token]) -> Option<Condition>,
+    ) -> Option<Condition> {
+        let mut cond = None;
+        loop {
+            let at = find_top(tokens, |_, tok| &tok.kind == joiner_tok);
+            let part = operand(self, &tokens[..at.unwrap_or(tokens.len())])?;
+            cond = Some(match cond {
+                None => part,
+                Some(left) => Condition::Logical(Box::new(left), joiner, Box::new(part)),
+            });
+            match at {
+                Some(at) => tokens = &tokens[at + 1..],
+                None => return cond,
+            }
         }
+    }
 
-        // Parse logical joiner
-        let joiner = match tokens.peek() {
-            Some(Token::And) => {
-                tokens.next();
-                Some(LogicalJoin::And)
+    // not := '!' not | '(' or ')' | comparison
+    fn not(&mut self, tokens: &[Token]) -> Option<Condition> {
+        let tokens = trim(tokens);
+        match tokens {
+            [] => self.fail(ErrTypes::InvalidConditionSyntax(self.whole)),
+            [not, rest @ ..]
+                if not.kind == TokenKind::Not
+                    && rest.first().map(|tok| &tok.kind) != Some(&TokenKind::EqSign) =>
+            {
+                Some(Condition::Not(Box::new(self.not(rest)?)))
             }
-            Some(Token::Or) => {
-                tokens.next();
-                Some(LogicalJoin::Or)
+            [open, inner @ .., close]
+                if open.kind == TokenKind::LSmallBrac
+                    && close.kind == TokenKind::RSmallBracket
+                    && is_cond_group(inner) =>
+            {
+                Some(Condition::Group(Box::new(self.or(inner)?)))
             }
-            _ => None,
+            _ => self.compare(tokens),
+        }
+    }
+
+    // comparison := expr op expr | expr
+    fn compare(&mut self, tokens: &[Token]) -> Option<Condition> {
+        let span = span_of(tokens).unwrap_or(self.whole);
+        let Some(at) = find_top(tokens, |i, _| comparison_at(tokens, i).is_some()) else {
+            // A value on its own; the type checker makes sure it is a `bool`.
+            let value = parse_operand(tokens, self.collected_errors, span)?;
+            return Some(Condition::Value(value, span));
         };
+        let (operator, width) = comparison_at(tokens, at)?;
+        let op_span = tokens[at].span.to(tokens[at + width - 1].span);
+        let (left, right) = (trim(&tokens[..at]), trim(&tokens[at + width..]));
+        if left.is_empty() {
+            return self.fail(ErrTypes::MissingLeftOperand(op_span));
+        }
+        if right.is_empty() {
+            return self.fail(ErrTypes::MissingRightOperand(op_span));
+        }
 
-        child_conditions.push(ChildCond {
-            left: left_operand,
+        let left = parse_operand(left, self.collected_errors, op_span)?;
+        let right = parse_operand(right, self.collected_errors, op_span)?;
+        Some(Condition::Compare(ChildCond {
+            left,
             operator,
-            right: right_operand,
-            joiner,
-        });
+            right,
+            span,
+        }))
     }
-
-    Condition { child_conditions }
 }
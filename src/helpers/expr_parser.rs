@@ -0,0 +1,680 @@
+use super::{
+    condition_parser::{is_condition, parse_cond_tree},
+    fn_calls::{fn_signature, lookup_fn, tok_text},
+    suggest::closest,
+    symbol_table::{SymbolTable, UNKNOWN},
+    BinOp, Condition, Expr, UnaryOp,
+};
+use crate::{
+    err_system::err_types::ErrTypes,
+    tok_system::tokens::{span_of, unescape, Span, Token, TokenKind},
+    type_system::{infer, is_array, is_value_type},
+};
+use std::iter::Peekable;
+use std::slice::Iter;
+
+// Types a value can be converted to with `value(type)`.
+const CAST_TYPES: [&str; 6] = ["i8", "i16", "i32", "i64", "f32", "f64"];
+
+/// Built-in operations on arrays. They are called like functions, and no function may take
+/// their names.
+pub const ARRAY_BUILTINS: [&str; 3] = ["len", "push", "pop"];
+
+/// Collects the tokens of an expression up to the end of the line, leaving the end of line in place.
+/// A struct literal's braces can span lines; the line ends inside them are dropped.
+pub fn collect_expr_tokens(token_iter: &mut Peekable<Iter<Token>>) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut braces = 0;
+    while let Some(tok) = token_iter.peek() {
+        match tok.kind {
+            TokenKind::EOF => break,
+            TokenKind::EOL if braces <= 0 => break,
+            TokenKind::EOL => {
+                token_iter.next();
+                continue;
+            }
+            TokenKind::LCurly => braces += 1,
+            TokenKind::RCurly => braces -= 1,
+            _ => {}
+        }
+        tokens.push((*tok).clone());
+        token_iter.next();
+    }
+    tokens
+}
+
+/// Parses expression tokens into an `Expr`. A comparison such as `x > 10` is a `bool` value.
+/// Syntax errors are pushed to `collected_errors` and `None` is returned; `at` is reported when
+/// there are no tokens to point at.
+pub fn parse_expr(
+    tokens: &[Token],
+    collected_errors: &mut Vec<ErrTypes>,
+    at: Span,
+) -> Option<Expr> {
+    if is_condition(tokens) {
+        let cond = parse_cond_tree(tokens, collected_errors, at)?;
+        return Some(Expr::Cond(Box::new(cond)));
+    }
+    parse_operand(tokens, collected_errors, at)
+}
+
+/// Parses expression tokens that are not a condition themselves, such as one side of a
+/// comparison. Conditions inside parentheses or call arguments are still allowed.
+pub fn parse_operand(
+    tokens: &[Token],
+    collected_errors: &mut Vec<ErrTypes>,
+    at: Span,
+) -> Option<Expr> {
+    let mut parser = ExprParser {
+        tokens,
+        pos: 0,
+        collected_errors,
+        at,
+    };
+    if parser.peek().is_none() {
+        return parser.fail(ErrTypes::MissingValue(at, None));
+    }
+    let expr = parser.expr()?;
+    let next = parser.next();
+    let span = parser.last_span();
+    match next {
+        None => Some(expr),
+        Some(TokenKind::RSmallBracket | TokenKind::RSquareBracket) => {
+            parser.fail(ErrTypes::UnbalancedParentheses(span))
+        }
+        Some(
+            TokenKind::Iden(_)
+            | TokenKind::Str { .. }
+            | TokenKind::LSmallBrac
+            | TokenKind::LSquareBrac,
+        ) => parser.fail(ErrTypes::MissingOperator(span)),
+        Some(_) => parser.fail(ErrTypes::UnexpectedToken(span)),
+    }
+}
+
+/// Parses expression tokens and resolves their names, returning the expression with its type.
+/// The type is `UNKNOWN` if the expression has a type error, which is left for the type
+/// checker to report.
+pub fn parse_typed_expr(
+    tokens: &[Token],
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    at: Span,
+) -> Option<(Expr, &'static str)> {
+    let expr = parse_expr(tokens, collected_errors, at)?;
+    if !resolve_names(&expr, collected_vars, collected_errors, tokens) {
+        return None;
+    }
+    let typ = infer(&expr, collected_vars);
+    Some((expr, typ))
+}
+
+/// The value of a string literal token spanning `span`, with its escapes decoded.
+pub fn string_value(raw: &str, closed: bool, span: Span) -> Result<String, ErrTypes> {
+    if !closed {
+        return Err(ErrTypes::SyntaxError(span));
+    }
+    unescape(raw).map_err(|bad| {
+        // Point at the escape itself; the literal starts with its quote.
+        ErrTypes::InvalidEscape(Span {
+            start: span.start + 1 + bad.start,
+            end: span.start + 1 + bad.end,
+            line: span.line,
+            col: span.col + 1 + raw[..bad.start].chars().count() as i32,
+        })
+    })
+}
+
+struct ExprParser<'a, 'e> {
+    tokens: &'a [Token],
+    pos: usize,
+    collected_errors: &'e mut Vec<ErrTypes>,
+    at: Span,
+}
+
+impl<'a> ExprParser<'a, '_> {
+    fn fail<T>(&mut self, err: ErrTypes) -> Option<T> {
+        self.collected_errors.push(err);
+        None
+    }
+
+    /// Next non-space token, without consuming it.
+    fn peek(&mut self) -> Option<&'a TokenKind> {
+        while self.kind_at(self.pos) == Some(&TokenKind::Space) {
+            self.pos += 1;
+        }
+        self.kind_at(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&'a TokenKind> {
+        let tok = self.peek();
+        self.pos += 1;
+        tok
+    }
+
+    fn kind_at(&self, pos: usize) -> Option<&'a TokenKind> {
+        self.tokens.get(pos).map(|tok| &tok.kind)
+    }
+
+    /// Span of the last token consumed, or of the last one there is once the tokens run out.
+    fn last_span(&self) -> Span {
+        self.tokens[..self.pos.min(self.tokens.len())]
+            .iter()
+            .rev()
+            .find(|tok| tok.kind != TokenKind::Space)
+            .map_or(self.at, |tok| tok.span)
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Option<Expr> {
+        let mut left = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(TokenKind::ADDOP) => BinOp::Add,
+                Some(TokenKind::SUBOP) => BinOp::Sub,
+                _ => return Some(left),
+            };
+            self.pos += 1;
+            let line = self.last_span().line;
+            let right = self.term()?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right), line);
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Option<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(TokenKind::MULTIOP) => BinOp::Mul,
+                Some(TokenKind::DIVOP) => BinOp::Div,
+                _ => return Some(left),
+            };
+            self.pos += 1;
+            let op_span = self.last_span();
+            let right = self.unary()?;
+            let divisor = op_span.to(self.last_span());
+            if op == BinOp::Div && matches!(right, Expr::Int(0)) {
+                return self.fail(ErrTypes::DivisionByZero(divisor));
+            }
+            if op == BinOp::Div && matches!(right, Expr::Float(val) if val == 0.0) {
+                return self.fail(ErrTypes::DivisionByZero(divisor));
+            }
+            left = Expr::Binary(Box::new(left), op, Box::new(right), op_span.line);
+        }
+    }
+
+    // unary := '-' unary | primary
+    fn unary(&mut self) -> Option<Expr> {
+        if self.peek() != Some(&TokenKind::SUBOP) {
+            return self.primary();
+        }
+        self.pos += 1;
+        // Negative literals are folded so they keep their literal type.
+        match self.unary()? {
+            Expr::Int(val) => Some(Expr::Int(-val)),
+            Expr::Float(val) => Some(Expr::Float(-val)),
+            operand => Some(Expr::Unary(UnaryOp::Neg, Box::new(operand))),
+        }
+    }
+
+    // primary := literal | name | name '(' args ')' | name '[' expr ']' | name '.' field
+    //            | name '{' fields '}' | '(' expr ')' | '[' items ']', optionally followed by
+    //            '(' type ')'
+    fn primary(&mut self) -> Option<Expr> {
+        let at_start = self.tokens[..self.pos]
+            .iter()
+            .all(|tok| tok.kind == TokenKind::Space);
+        let tok = self.next();
+        let span = self.last_span();
+        let expr = match tok {
+            None if at_start => return self.fail(ErrTypes::MissingValue(self.at, None)),
+            None => return self.fail(ErrTypes::MissingRightOperand(span)),
+            Some(TokenKind::LSmallBrac) => {
+                let inner = self.inner_value()?;
+                if self.next() != Some(&TokenKind::RSmallBracket) {
+                    // Point from the unclosed `(` to where the `)` was expected.
+                    let unclosed = span.to(self.last_span());
+                    return self.fail(ErrTypes::UnbalancedParentheses(unclosed));
+                }
+                Expr::Group(Box::new(inner))
+            }
+            Some(TokenKind::LSquareBrac) => {
+                let mut items = Vec::new();
+                if self.peek() == Some(&TokenKind::RSquareBracket) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        items.push(self.inner_value()?);
+                        match self.next() {
+                            Some(TokenKind::Comma) => continue,
+                            Some(TokenKind::RSquareBracket) => break,
+                            _ => {
+                                let unclosed = span.to(self.last_span());
+                                return self.fail(ErrTypes::UnbalancedParentheses(unclosed));
+                            }
+                        }
+                    }
+                }
+                Expr::Array(items)
+            }
+            Some(TokenKind::RSmallBracket | TokenKind::RSquareBracket) => {
+                return self.fail(ErrTypes::UnbalancedParentheses(span))
+            }
+            Some(TokenKind::ADDOP | TokenKind::MULTIOP | TokenKind::DIVOP) if at_start => {
+                return self.fail(ErrTypes::MissingLeftOperand(span))
+            }
+            Some(TokenKind::ADDOP | TokenKind::MULTIOP | TokenKind::DIVOP) => {
+                return self.fail(ErrTypes::DuplicateOperator(span))
+            }
+            Some(TokenKind::Iden(text)) => self.atom(text)?,
+            Some(TokenKind::Str { raw, closed }) => match string_value(raw, *closed, span) {
+                Ok(text) => Expr::Str(text),
+                Err(err) => return self.fail(err),
+            },
+            Some(_) => return self.fail(ErrTypes::UnexpectedToken(span)),
+        };
+        self.cast_suffix(expr)
+    }
+
+    /// Parses a literal, a variable, an array element, a struct field, a struct literal or a
+    /// call starting with the given word.
+    fn atom(&mut self, text: &'a str) -> Option<Expr> {
+        let span = self.last_span();
+        if text.starts_with('\'') {
+            let value = self.quoted(text)?;
+            let mut chars = value.chars();
+            // A `ch` is one byte in C.
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii() => Some(Expr::Char(c)),
+                _ => self.fail(ErrTypes::CharVarLen(span.to(self.last_span()))),
+            };
+        }
+        if text.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return self.number(text, span);
+        }
+        let is_name =
+            |word: &str| !word.is_empty() && word.chars().all(|c| c.is_alphanumeric() || c == '_');
+        // `.` belongs to words, so `p.x` arrives as one.
+        if let Some((var, field)) = text.split_once('.') {
+            if is_name(var) && is_name(field) {
+                return Some(Expr::Field(var.to_string(), field.to_string()));
+            }
+        }
+        if !is_name(text) {
+            return self.fail(ErrTypes::UnexpectedToken(span));
+        }
+        match text {
+            "true" => return Some(Expr::Bool(true)),
+            "false" => return Some(Expr::Bool(false)),
+            _ => {}
+        }
+        let after = self.pos;
+        if self.peek() == Some(&TokenKind::LCurly) {
+            self.pos += 1;
+            return self.struct_literal(text, span);
+        }
+        self.pos = after;
+        if self.kind_at(self.pos) == Some(&TokenKind::LSquareBrac) {
+            self.pos += 1;
+            let index = self.inner_value()?;
+            if self.next() != Some(&TokenKind::RSquareBracket) {
+                return self.fail(ErrTypes::UnbalancedParentheses(span.to(self.last_span())));
+            }
+            return Some(Expr::Index(text.to_string(), Box::new(index), span.line));
+        }
+        if self.kind_at(self.pos) != Some(&TokenKind::LSmallBrac) || self.at_cast() {
+            return Some(Expr::Var(text.to_string()));
+        }
+
+        // Call arguments: `name(a, b + 1)`.
+        self.pos += 1;
+        let mut args = Vec::new();
+        if self.peek() == Some(&TokenKind::RSmallBracket) {
+            self.pos += 1;
+        } else {
+            loop {
+                args.push(self.inner_value()?);
+                match self.next() {
+                    Some(TokenKind::Comma) => continue,
+                    Some(TokenKind::RSmallBracket) => break,
+                    _ => {
+                        let unclosed = span.to(self.last_span());
+                        return self.fail(ErrTypes::UnbalancedParentheses(unclosed));
+                    }
+                }
+            }
+        }
+        // `len(xs)` and `pop(xs)` take the array by name.
+        let call = span.to(self.last_span());
+        match (text, args.as_slice()) {
+            ("len", [Expr::Var(array)]) => Some(Expr::Len(array.clone())),
+            ("pop", [Expr::Var(array)]) => Some(Expr::Pop(array.clone(), span.line)),
+            ("len" | "pop", [_]) => self.fail(ErrTypes::UnexpectedToken(call)),
+            ("len" | "pop", _) => self.fail(ErrTypes::ArgCountMismatch(span)),
+            _ => Some(Expr::Call(text.to_string(), args)),
+        }
+    }
+
+    /// Parses the fields of a struct literal after its `{`: `field: value`, separated by commas.
+    fn struct_literal(&mut self, name: &str, span: Span) -> Option<Expr> {
+        let mut fields = Vec::new();
+        loop {
+            if self.peek() == Some(&TokenKind::RCurly) {
+                self.pos += 1;
+                break;
+            }
+            let field = match self.next() {
+                Some(TokenKind::Iden(field)) => field.clone(),
+                None => {
+                    return self.fail(ErrTypes::UnbalancedParentheses(span.to(self.last_span())))
+                }
+                Some(_) => return self.fail(ErrTypes::UnexpectedToken(self.last_span())),
+            };
+            if self.next() != Some(&TokenKind::Colon) {
+                return self.fail(ErrTypes::MissingOperator(self.last_span()));
+            }
+            fields.push((field, self.inner_value()?));
+            match self.next() {
+                Some(TokenKind::Comma) => continue,
+                Some(TokenKind::RCurly) => break,
+                None => {
+                    let unclosed = span.to(self.last_span());
+                    return self.fail(ErrTypes::UnbalancedParentheses(unclosed));
+                }
+                // Fields on separate lines still need commas between them.
+                Some(_) => return self.fail(ErrTypes::UnexpectedToken(self.last_span())),
+            }
+        }
+        Some(Expr::Struct(name.to_string(), fields))
+    }
+
+    /// Index of the `,`, `)`, `]` or `}` that ends the value starting at the current token, or
+    /// the end of the tokens if nothing does.
+    fn value_end(&self) -> usize {
+        let mut depth = 0;
+        for (i, tok) in self.tokens.iter().enumerate().skip(self.pos) {
+            match tok.kind {
+                TokenKind::Comma
+                | TokenKind::RSmallBracket
+                | TokenKind::RSquareBracket
+                | TokenKind::RCurly
+                    if depth == 0 =>
+                {
+                    return i
+                }
+                TokenKind::LSmallBrac | TokenKind::LSquareBrac | TokenKind::LCurly => depth += 1,
+                TokenKind::RSmallBracket | TokenKind::RSquareBracket | TokenKind::RCurly => {
+                    depth -= 1
+                }
+                _ => {}
+            }
+        }
+        self.tokens.len()
+    }
+
+    /// Parses a value inside parentheses or brackets, such as a group, a call argument or an
+    /// array item, which may be a condition as in `check(x > 1)`.
+    fn inner_value(&mut self) -> Option<Expr> {
+        let end = self.value_end();
+        let inner = &self.tokens[self.pos..end];
+        if !is_condition(inner) {
+            return self.expr();
+        }
+        let at = self.last_span();
+        let cond = parse_cond_tree(inner, self.collected_errors, at)?;
+        self.pos = end;
+        Some(Expr::Cond(Box::new(cond)))
+    }
+
+    /// Reads a character literal that may span several tokens and returns the text between the
+    /// quotes.
+    fn quoted(&mut self, first: &'a str) -> Option<String> {
+        let quote = '\'';
+        let span = self.last_span();
+        let mut value = first[1..].to_string();
+        if first.len() > 1 && value.ends_with(quote) {
+            value.pop();
+            return Some(value);
+        }
+        while let Some(tok) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            match &tok.kind {
+                TokenKind::Space => value.push(' '),
+                TokenKind::Iden(text) if text.ends_with(quote) => {
+                    value.push_str(&text[..text.len() - 1]);
+                    return Some(value);
+                }
+                _ => value.push_str(tok_text(tok)),
+            }
+        }
+        self.fail(ErrTypes::SyntaxError(span.to(self.last_span())))
+    }
+
+    fn number(&mut self, text: &str, span: Span) -> Option<Expr> {
+        let is_float = text.contains('.') || text.ends_with(['f', 'F']);
+        let cleaned = text.trim_end_matches(['f', 'F']);
+        let parsed = if is_float {
+            cleaned.parse::<f64>().ok().map(Expr::Float)
+        } else {
+            cleaned.parse::<i64>().ok().map(Expr::Int)
+        };
+        match parsed {
+            Some(expr) => Some(expr),
+            // Every integer literal has to fit an `i64`.
+            None if !is_float
+                && !cleaned.is_empty()
+                && cleaned.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                self.fail(ErrTypes::OutOfRange(span, "i64"))
+            }
+            None => self.fail(ErrTypes::InvalidNumberFormat(span)),
+        }
+    }
+
+    /// Whether the upcoming tokens are a `(type)` conversion suffix.
+    fn at_cast(&self) -> bool {
+        matches!(
+            self.tokens.get(self.pos..self.pos + 3),
+            Some([open, typ, close])
+                if open.kind == TokenKind::LSmallBrac
+                    && close.kind == TokenKind::RSmallBracket
+                    && matches!(&typ.kind, TokenKind::Iden(typ) if CAST_TYPES.contains(&typ.as_str()))
+        )
+    }
+
+    fn cast_suffix(&mut self, expr: Expr) -> Option<Expr> {
+        if !self.at_cast() {
+            return Some(expr);
+        }
+        let TokenKind::Iden(typ) = &self.tokens[self.pos + 1].kind else {
+            unreachable!()
+        };
+        let typ = CAST_TYPES.into_iter().find(|t| t == typ).unwrap();
+        let span = self.last_span();
+        self.pos += 3;
+        let span = span.to(self.last_span());
+        let is_float = typ.starts_with('f');
+        // Literals are converted right away so `"1"(i32)` becomes a plain number.
+        let literal = match &expr {
+            Expr::Str(text) => self.number(text.trim(), span)?,
+            Expr::Int(val) => Expr::Int(*val),
+            Expr::Float(val) => Expr::Float(*val),
+            _ => return Some(Expr::Cast(Box::new(expr), typ)),
+        };
+        let literal = match literal {
+            Expr::Int(val) if is_float => Expr::Float(val as f64),
+            Expr::Float(_) if !is_float => return self.fail(ErrTypes::InvalidNumberFormat(span)),
+            literal => literal,
+        };
+        Some(Expr::Cast(Box::new(literal), typ))
+    }
+}
+
+/// Span of the first word in `src` spelling `name`, or of all of `src` if there is none.
+fn name_span(src: &[Token], name: &str) -> Span {
+    src.iter()
+        .find(|tok| matches!(&tok.kind, TokenKind::Iden(word) if word == name))
+        .map(|tok| tok.span)
+        .or_else(|| span_of(src))
+        .unwrap_or_default()
+}
+
+/// Checks that every variable and function an expression names is declared, and that calls
+/// pass as many arguments as the function takes. Variables are marked as read. `src` holds the
+/// tokens the expression was parsed from, which errors point into. Errors are pushed to
+/// `collected_errors` and `false` is returned. Types are left to the type checker.
+pub fn resolve_names(
+    expr: &Expr,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    src: &[Token],
+) -> bool {
+    match expr {
+        Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Char(_) | Expr::Bool(_) => true,
+        Expr::Cond(cond) => resolve_cond(cond, collected_vars, collected_errors, src),
+        // Already reported where it was declared.
+        Expr::Var(name)
+        | Expr::Call(name, _)
+        | Expr::Index(name, ..)
+        | Expr::Len(name)
+        | Expr::Pop(name, _)
+        | Expr::Field(name, _)
+            if collected_vars.lookup(name) == Some(UNKNOWN) =>
+        {
+            false
+        }
+        Expr::Var(name) | Expr::Len(name) | Expr::Pop(name, _) => {
+            resolve_var(name, collected_vars, collected_errors, src)
+        }
+        Expr::Index(name, index, _) => {
+            resolve_var(name, collected_vars, collected_errors, src)
+                && resolve_names(index, collected_vars, collected_errors, src)
+        }
+        Expr::Array(items) => items
+            .iter()
+            .all(|item| resolve_names(item, collected_vars, collected_errors, src)),
+        Expr::Field(var, field) => {
+            let path = format!("{}.{}", var, field);
+            let Some(typ) = collected_vars.lookup(var) else {
+                let similar = collected_vars.similar(var, |typ| !typ.starts_with("fn;"));
+                collected_errors.push(ErrTypes::VarNotFound(name_span(src, &path), similar));
+                return false;
+            };
+            let Some(def) = collected_vars.struct_def(typ.trim_start_matches("const;")) else {
+                collected_errors.push(ErrTypes::UnknownField(name_span(src, &path), None));
+                return false;
+            };
+            if def.field(field).is_none() {
+                let similar = closest(field, def.fields.iter().map(|(name, _)| name.as_str()))
+                    .map(|name| &*Box::leak(format!("{}.{}", var, name).into_boxed_str()));
+                collected_errors.push(ErrTypes::UnknownField(name_span(src, &path), similar));
+                return false;
+            }
+            collected_vars.mark_read(var);
+            true
+        }
+        Expr::Struct(name, fields) => {
+            let Some(def) = collected_vars.struct_def(name) else {
+                collected_errors.push(ErrTypes::UnsupportedVarType(name_span(src, name)));
+                return false;
+            };
+            for (at, (field, _)) in fields.iter().enumerate() {
+                if def.field(field).is_none() {
+                    let similar = closest(field, def.fields.iter().map(|(name, _)| name.as_str()))
+                        .map(|name| &*Box::leak(name.to_string().into_boxed_str()));
+                    collected_errors.push(ErrTypes::UnknownField(name_span(src, field), similar));
+                    return false;
+                }
+                if fields[..at].iter().any(|(prev, _)| prev == field) {
+                    // Point at the repeated field, with the first one as the earlier declaration.
+                    let mut spans = src
+                        .iter()
+                        .filter(|tok| matches!(&tok.kind, TokenKind::Iden(word) if word == field))
+                        .map(|tok| tok.span);
+                    let first = spans.next();
+                    let again = spans.next().or(first).unwrap_or_default();
+                    collected_errors.push(ErrTypes::VarAlreadyExists(again, first));
+                    return false;
+                }
+            }
+            // Every field needs a value; the literal is the struct's only constructor.
+            if let Some((missing, _)) = def
+                .fields
+                .iter()
+                .find(|(field, _)| !fields.iter().any(|(given, _)| given == field))
+            {
+                let missing = &*Box::leak(missing.clone().into_boxed_str());
+                collected_errors.push(ErrTypes::MissingValue(name_span(src, name), Some(missing)));
+                return false;
+            }
+            fields
+                .iter()
+                .all(|(_, value)| resolve_names(value, collected_vars, collected_errors, src))
+        }
+        Expr::Call(name, args) => {
+            let Some((_, params)) = lookup_fn(name, collected_vars).and_then(fn_signature) else {
+                let similar = collected_vars.similar(name, |typ| typ.starts_with("fn;"));
+                collected_errors.push(ErrTypes::UnknownFunction(name_span(src, name), similar));
+                return false;
+            };
+            if args.len() != params.len() {
+                collected_errors.push(ErrTypes::ArgCountMismatch(name_span(src, name)));
+                return false;
+            }
+            args.iter()
+                .all(|arg| resolve_names(arg, collected_vars, collected_errors, src))
+        }
+        Expr::Binary(left, _, right, _) => {
+            resolve_names(left, collected_vars, collected_errors, src)
+                && resolve_names(right, collected_vars, collected_errors, src)
+        }
+        Expr::Unary(_, inner) | Expr::Group(inner) | Expr::Cast(inner, _) => {
+            resolve_names(inner, collected_vars, collected_errors, src)
+        }
+    }
+}
+
+/// Checks that a variable is declared, and marks it as read.
+pub fn resolve_var(
+    name: &str,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    src: &[Token],
+) -> bool {
+    let typ = collected_vars
+        .lookup(name)
+        .map(|typ| typ.trim_start_matches("const;"));
+    let is_var =
+        |typ: &str| is_value_type(typ) || is_array(typ) || collected_vars.struct_def(typ).is_some();
+    if typ.is_some_and(is_var) {
+        collected_vars.mark_read(name);
+        return true;
+    }
+    let similar = collected_vars.similar(name, |typ| !typ.starts_with("fn;"));
+    collected_errors.push(ErrTypes::VarNotFound(name_span(src, name), similar));
+    false
+}
+
+/// Resolves the names in every value of a condition, as `resolve_names` does for an expression.
+pub fn resolve_cond(
+    cond: &Condition,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    src: &[Token],
+) -> bool {
+    match cond {
+        Condition::Compare(child) => {
+            resolve_names(&child.left, collected_vars, collected_errors, src)
+                && resolve_names(&child.right, collected_vars, collected_errors, src)
+        }
+        Condition::Value(value, _) => resolve_names(value, collected_vars, collected_errors, src),
+        Condition::Logical(left, _, right) => {
+            resolve_cond(left, collected_vars, collected_errors, src)
+                && resolve_cond(right, collected_vars, collected_errors, src)
+        }
+        Condition::Not(inner) | Condition::Group(inner) => {
+            resolve_cond(inner, collected_vars, collected_errors, src)
+        }
+    }
+}
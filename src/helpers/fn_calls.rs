@@ -0,0 +1,89 @@
+use super::symbol_table::SymbolTable;
+use crate::tok_system::tokens::{Token, TokenKind};
+use std::iter::Peekable;
+use std::slice::Iter;
+
+/// Builds the tag stored in `collected_vars` for a function: `fn;<ret>;<param types>`.
+pub fn fn_tag(ret: &str, params: &[(String, &'static str)]) -> &'static str {
+    let param_types: Vec<&str> = params.iter().map(|(_, typ)| *typ).collect();
+    Box::leak(format!("fn;{};{}", ret, param_types.join(",")).into_boxed_str())
+}
+
+/// Splits a function tag back into its return type and parameter types.
+pub fn fn_signature(tag: &'static str) -> Option<(&'static str, Vec<&'static str>)> {
+    let rest = tag.strip_prefix("fn;")?;
+    let (ret, params) = rest.split_once(';').unwrap_or((rest, ""));
+    Some((ret, params.split(',').filter(|p| !p.is_empty()).collect()))
+}
+
+/// Looks up the tag of a declared function.
+/// A variable shadowing the function hides it.
+pub fn lookup_fn(name: &str, collected_vars: &SymbolTable) -> Option<&'static str> {
+    collected_vars
+        .lookup(name)
+        .filter(|typ| typ.starts_with("fn;"))
+}
+
+/// Source text of a token (spaces and line ends are dropped, and strings lose their quotes).
+#[inline(always)]
+pub fn tok_text(tok: &Token) -> &str {
+    match &tok.kind {
+        TokenKind::Iden(text) | TokenKind::Str { raw: text, .. } => text,
+        TokenKind::LSmallBrac => "(",
+        TokenKind::RSmallBracket => ")",
+        TokenKind::LSquareBrac => "[",
+        TokenKind::RSquareBracket => "]",
+        TokenKind::Comma => ",",
+        TokenKind::Colon => ":",
+        TokenKind::ADDOP => "+",
+        TokenKind::SUBOP => "-",
+        TokenKind::MULTIOP => "*",
+        TokenKind::DIVOP => "/",
+        TokenKind::BackSlash => "\\",
+        TokenKind::EqSign => "=",
+        TokenKind::DoubleEqSign => "==",
+        TokenKind::PercentSign => "%",
+        TokenKind::LCurly => "{",
+        TokenKind::RCurly => "}",
+        TokenKind::And => "&&",
+        TokenKind::Or => "||",
+        TokenKind::Not => "!",
+        TokenKind::GreaterThan => ">",
+        TokenKind::LessThan => "<",
+        TokenKind::Space | TokenKind::EOL | TokenKind::EOF => "",
+    }
+}
+
+/// Consumes a parenthesised group (the next token must be `(`) and returns its tokens, parentheses included.
+/// Stops without consuming the end of line if the group is never closed.
+pub fn collect_paren_group(tokens: &mut Peekable<Iter<Token>>) -> Option<Vec<Token>> {
+    collect_group(tokens, TokenKind::LSmallBrac, TokenKind::RSmallBracket)
+}
+
+/// Consumes a group between `open` and `close` (the next token must be `open`), as
+/// `collect_paren_group` does for parentheses.
+pub fn collect_group(
+    tokens: &mut Peekable<Iter<Token>>,
+    open: TokenKind,
+    close: TokenKind,
+) -> Option<Vec<Token>> {
+    if !matches!(tokens.peek(), Some(tok) if tok.kind == open) {
+        return None;
+    }
+    let mut depth = 0;
+    let mut group = Vec::new();
+    while let Some(tok) = tokens.peek() {
+        match &tok.kind {
+            TokenKind::EOL | TokenKind::EOF => return None,
+            kind if *kind == open => depth += 1,
+            kind if *kind == close => depth -= 1,
+            _ => {}
+        }
+        group.push((*tok).clone());
+        tokens.next();
+        if depth == 0 {
+            return Some(group);
+        }
+    }
+    None
+}
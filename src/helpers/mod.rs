@@ -1,19 +1,68 @@
+use crate::tok_system::tokens::Span;
+
 // Define the new enum for logical joiners.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LogicalJoin {
     And,
     Or,
 }
 
-/// Represents a value used in a condition: a literal string, a numeric literal, or a variable name.
+/// Arithmetic operators, from lowest to highest precedence group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Prefix operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+}
+
+/// An expression tree shared by every statement that takes a value.
 #[derive(Debug, Clone, PartialEq)]
-pub enum Operand {
-    /// A string literal (e.g. "hello")
-    Literal(String),
-    /// A numeric literal (e.g. 3.14)
-    Numeric(f64),
-    /// A variable (e.g. x)
-    Variable(String),
+pub enum Expr {
+    /// An integer literal (e.g. 42)
+    Int(i64),
+    /// A floating point literal (e.g. 3.14)
+    Float(f64),
+    /// A string literal, without its quotes
+    Str(String),
+    /// A character literal (e.g. 'a')
+    Char(char),
+    /// A boolean literal, `true` or `false`
+    Bool(bool),
+    /// A variable reference
+    Var(String),
+    /// A function call: name and argument expressions
+    Call(String, Vec<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    /// An arithmetic operation, with the line it is on, which is reported when an integer
+    /// division divides by zero
+    Binary(Box<Expr>, BinOp, Box<Expr>, i32),
+    /// A parenthesised sub-expression
+    Group(Box<Expr>),
+    /// An explicit conversion, written `value(type)`
+    Cast(Box<Expr>, &'static str),
+    /// A condition used as a `bool` value (e.g. x > 10)
+    Cond(Box<Condition>),
+    /// An array literal (e.g. [1, 2, 3])
+    Array(Vec<Expr>),
+    /// An element of an array variable: the array, the index and the line it is on, which is
+    /// reported when the index is out of range
+    Index(String, Box<Expr>, i32),
+    /// The number of elements in an array variable, written `len(xs)`
+    Len(String),
+    /// Removes the last element of a growable array and gives it, written `pop(xs)`; with the
+    /// line it is on, which is reported when the array is empty
+    Pop(String, i32),
+    /// A struct literal: the struct's name and each field's value (e.g. Point { x: 1.0, y: 2.0 })
+    Struct(String, Vec<(String, Expr)>),
+    /// A field of a struct variable: the variable and the field (e.g. p.x)
+    Field(String, String),
 }
 
 /// Tokens for conditional operators.
@@ -27,21 +76,62 @@ pub enum CondToks {
     NotEqual,
 }
 
-/// Represents a single condition (or comparison) in a compound condition.
+/// Represents a single comparison in a condition.
 /// For example: x >= 10
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ChildCond {
-    pub left: Operand,
+    pub left: Expr,
     pub operator: CondToks,
-    pub right: Operand,
-    /// The logical joiner (e.g. AND/OR) following this condition, if any.
-    pub joiner: Option<LogicalJoin>,
+    pub right: Expr,
+    /// The whole comparison, as written.
+    pub span: Span,
+}
+
+/// A condition tree. `&&` binds tighter than `||`, and `!` applies to the condition right after it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare(ChildCond),
+    /// A `bool` value on its own, such as `done`, and where it was written.
+    Value(Expr, Span),
+    Logical(Box<Condition>, LogicalJoin, Box<Condition>),
+    Not(Box<Condition>),
+    /// A parenthesised condition
+    Group(Box<Condition>),
+}
+
+impl Expr {
+    /// Whether evaluating the expression does more than give a value: a call or a `pop` may
+    /// have side effects, and an index may stop the program.
+    pub fn has_effects(&self) -> bool {
+        match self {
+            Expr::Call(..) | Expr::Pop(..) | Expr::Index(..) => true,
+            Expr::Array(items) => items.iter().any(Expr::has_effects),
+            Expr::Struct(_, fields) => fields.iter().any(|(_, value)| value.has_effects()),
+            Expr::Unary(_, inner) | Expr::Group(inner) | Expr::Cast(inner, _) => {
+                inner.has_effects()
+            }
+            Expr::Binary(left, _, right, _) => left.has_effects() || right.has_effects(),
+            Expr::Cond(cond) => cond.has_effects(),
+            _ => false,
+        }
+    }
 }
 
-/// Represents a complete condition block that may contain one or more child conditions.
-#[derive(Debug, Clone)]
-pub struct Condition {
-    pub child_conditions: Vec<ChildCond>,
+impl Condition {
+    /// Whether evaluating the condition does more than give a value; see [`Expr::has_effects`].
+    pub fn has_effects(&self) -> bool {
+        match self {
+            Condition::Compare(child) => child.left.has_effects() || child.right.has_effects(),
+            Condition::Value(value, _) => value.has_effects(),
+            Condition::Logical(left, _, right) => left.has_effects() || right.has_effects(),
+            Condition::Not(inner) | Condition::Group(inner) => inner.has_effects(),
+        }
+    }
 }
 pub mod c_condmk;
+pub mod c_exprmk;
 pub mod condition_parser;
+pub mod expr_parser;
+pub mod fn_calls;
+pub mod suggest;
+pub mod symbol_table;
@@ -0,0 +1,387 @@
+use super::suggest::closest;
+use crate::{err_system::warn_types::WarnTypes, tok_system::tokens::Span};
+use std::cell::Cell;
+
+/// Type tag of a name whose declaration failed. Uses of it report nothing more, since the
+/// declaration already has an error.
+pub const UNKNOWN: &str = "?";
+
+/// What opened a scope; decides where `break`, `continue` and `return` are allowed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScopeKind {
+    /// The top level of a file.
+    Global,
+    /// An `if`, `else if` or `else` body.
+    Block,
+    /// A `while` or `for` body.
+    Loop,
+    /// A function body, with the function's return type (`void` when it has none).
+    Function(&'static str),
+}
+
+/// A struct type declared with `struct Name { field: type, ... }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDef {
+    /// The name, which is also the type tag of the struct's variables.
+    pub name: &'static str,
+    /// Field names and types, in declaration order.
+    pub fields: Vec<(String, &'static str)>,
+    /// Where the name was written.
+    pub span: Span,
+}
+
+impl StructDef {
+    /// Type of the field with this name.
+    pub fn field(&self, name: &str) -> Option<&'static str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, typ)| *typ)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Scope {
+    kind: ScopeKind,
+    vars: Vec<(String, &'static str, Span)>,
+    /// How each entry of `vars` is used, at the same index.
+    usage: Vec<Usage>,
+}
+
+/// Reads and writes of one entry, for the unused-variable and unused-assignment warnings.
+/// Reads happen through `&SymbolTable`, so the fields are cells.
+#[derive(Debug, Clone, Default)]
+struct Usage {
+    read: Cell<bool>,
+    /// The last write not read yet, with the scope depth it was made at.
+    pending: Cell<Option<(Span, usize)>>,
+    /// Writes overwritten before anything read them.
+    overwritten: Vec<Span>,
+}
+
+/// Variables and functions visible at a point in the program, one scope per block.
+/// Entries are `(name, type tag, declaration span)`, with tags such as `i32`, `const;f32` or
+/// `fn;i32;i32,i32`. Entries added without a position have a default span.
+/// Struct types are kept apart from the entries, and a struct variable's tag is its struct's
+/// name.
+#[derive(Debug, Clone)]
+pub struct SymbolTable {
+    scopes: Vec<Scope>,
+    /// Struct types, which are only declared at the top level and so belong to no scope.
+    structs: Vec<StructDef>,
+    /// Warnings about entries whose scope has ended.
+    warnings: Vec<WarnTypes>,
+    /// Every entry ever declared with a position, including ones whose scope has ended.
+    declared: Vec<(String, &'static str, Span)>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        SymbolTable {
+            scopes: vec![Scope {
+                kind: ScopeKind::Global,
+                vars: Vec::new(),
+                usage: Vec::new(),
+            }],
+            structs: Vec::new(),
+            warnings: Vec::new(),
+            declared: Vec::new(),
+        }
+    }
+}
+
+impl SymbolTable {
+    /// Drops every scope and entry, leaving an empty global scope.
+    pub fn clear(&mut self) {
+        *self = SymbolTable::default();
+    }
+
+    pub fn push_scope(&mut self, kind: ScopeKind) {
+        self.scopes.push(Scope {
+            kind,
+            vars: Vec::new(),
+            usage: Vec::new(),
+        });
+    }
+
+    /// Leaves the innermost scope, forgetting everything declared in it and warning about
+    /// entries it never read. The global scope is never popped.
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            if let Some(scope) = self.scopes.pop() {
+                self.report_unused(&scope);
+            }
+            // A write made inside the block may not have happened, so it can still be read.
+            let depth = self.scopes.len();
+            for usage in self.scopes.iter().flat_map(|scope| scope.usage.iter()) {
+                if usage.pending.get().is_some_and(|(_, at)| at > depth) {
+                    usage.pending.set(None);
+                }
+            }
+        }
+    }
+
+    /// Ends every scope, leaving an empty table, and returns all the warnings collected.
+    pub fn finish(&mut self) -> Vec<WarnTypes> {
+        for scope in std::mem::take(&mut self.scopes).iter().rev() {
+            self.report_unused(scope);
+        }
+        let warnings = std::mem::take(&mut self.warnings);
+        self.clear();
+        warnings
+    }
+
+    /// Adds a warning found outside of the table, such as one from a function body's table.
+    pub fn warn(&mut self, warning: WarnTypes) {
+        self.warnings.push(warning);
+    }
+
+    /// Takes over the warnings and declarations of a finished function body's table.
+    pub fn absorb(&mut self, mut body: SymbolTable) {
+        let declared = std::mem::take(&mut body.declared);
+        for warning in body.finish() {
+            self.warn(warning);
+        }
+        for entry in declared {
+            // The functions the body was given are already known here.
+            if !self.declared.iter().any(|(_, _, span)| *span == entry.2) {
+                self.declared.push(entry);
+            }
+        }
+    }
+
+    /// Every entry declared with a position so far, in declaration order, whether or not it is
+    /// still visible. Used by the language server for hover and go-to-definition.
+    pub fn declarations(&self) -> &[(String, &'static str, Span)] {
+        &self.declared
+    }
+
+    /// Warns about the variables of a finished scope that were never read, or whose values
+    /// were not. Functions, entries without a position and names starting with `_` are skipped.
+    fn report_unused(&mut self, scope: &Scope) {
+        for ((name, typ, span), usage) in scope.vars.iter().zip(&scope.usage) {
+            if typ.starts_with("fn;")
+                || *typ == UNKNOWN
+                || *span == Span::default()
+                || name.starts_with('_')
+            {
+                continue;
+            }
+            if !usage.read.get() {
+                self.warnings.push(WarnTypes::UnusedVariable(*span));
+                continue;
+            }
+            let dead = usage.overwritten.iter().copied();
+            let last = usage.pending.get().map(|(write, _)| write);
+            self.warnings
+                .extend(dead.chain(last).map(WarnTypes::UnusedAssignment));
+        }
+    }
+
+    /// Adds an entry to the innermost scope.
+    pub fn declare(&mut self, name: String, typ: &'static str) {
+        self.declare_at(name, typ, Span::default());
+    }
+
+    /// Adds an entry to the innermost scope, remembering where it was declared.
+    /// The declaration counts as the entry's first write.
+    pub fn declare_at(&mut self, name: String, typ: &'static str, span: Span) {
+        let depth = self.scopes.len();
+        if span != Span::default() {
+            self.declared.push((name.clone(), typ, span));
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.vars.push((name, typ, span));
+            scope.usage.push(Usage {
+                pending: Cell::new(Some((span, depth))),
+                ..Usage::default()
+            });
+        }
+    }
+
+    /// Like `declare_at`, for a declaration without a value. Its zero value is not counted as
+    /// a write, so assigning one before reading it is not reported.
+    pub fn declare_unset(&mut self, name: String, typ: &'static str, span: Span) {
+        self.declare_at(name, typ, span);
+        if let Some(usage) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.usage.last_mut())
+        {
+            usage.pending.set(None);
+        }
+    }
+
+    /// Changes the type tag of the innermost visible entry with this name.
+    pub fn retype(&mut self, name: &str, typ: &'static str) {
+        if let Some(entry) = self
+            .scopes
+            .iter_mut()
+            .flat_map(|scope| scope.vars.iter_mut())
+            .rev()
+            .find(|(var, _, _)| var == name)
+        {
+            entry.1 = typ;
+            let span = entry.2;
+            if let Some(logged) = self
+                .declared
+                .iter_mut()
+                .rev()
+                .find(|(var, _, at)| var == name && *at == span)
+            {
+                logged.1 = typ;
+            }
+        }
+    }
+
+    /// Usage of the innermost visible entry with this name.
+    fn usage(&self, name: &str) -> Option<&Usage> {
+        self.scopes
+            .iter()
+            .flat_map(|scope| scope.vars.iter().zip(&scope.usage))
+            .rev()
+            .find(|((var, _, _), _)| var == name)
+            .map(|(_, usage)| usage)
+    }
+
+    /// Records that a variable's value is read.
+    pub fn mark_read(&self, name: &str) {
+        if let Some(usage) = self.usage(name) {
+            usage.read.set(true);
+            usage.pending.set(None);
+        }
+    }
+
+    /// Records an assignment at `span`. An unread earlier write in the same block is overwritten.
+    pub fn mark_write(&mut self, name: &str, span: Span) {
+        let depth = self.scopes.len();
+        let Some(usage) = self
+            .scopes
+            .iter_mut()
+            .flat_map(|scope| scope.vars.iter().zip(scope.usage.iter_mut()))
+            .rev()
+            .find(|((var, _, _), _)| var == name)
+            .map(|(_, usage)| usage)
+        else {
+            return;
+        };
+        if let Some((prev, at)) = usage.pending.get() {
+            if at == depth {
+                usage.overwritten.push(prev);
+            }
+        }
+        usage.pending.set(Some((span, depth)));
+    }
+
+    /// A `break`, `continue` or `return`: code after the jump target may read anything written
+    /// so far, so no pending write is reported.
+    pub fn forget_writes(&self) {
+        for usage in self.scopes.iter().flat_map(|scope| scope.usage.iter()) {
+            usage.pending.set(None);
+        }
+    }
+
+    /// Type tag of the innermost visible entry with this name.
+    pub fn lookup(&self, name: &str) -> Option<&'static str> {
+        self.iter()
+            .rev()
+            .find(|(var, _, _)| var == name)
+            .map(|(_, typ, _)| *typ)
+    }
+
+    /// Where the innermost visible entry with this name was declared, if that is known.
+    pub fn declared_span(&self, name: &str) -> Option<Span> {
+        self.iter()
+            .rev()
+            .find(|(var, _, _)| var == name)
+            .map(|(_, _, span)| *span)
+            .filter(|span| *span != Span::default())
+    }
+
+    /// Whether the name is declared in the innermost scope itself.
+    pub fn declared_here(&self, name: &str) -> bool {
+        self.scopes
+            .last()
+            .is_some_and(|scope| scope.vars.iter().any(|(var, _, _)| var == name))
+    }
+
+    /// The visible name closest in spelling to `name` whose type tag passes `keep`, for
+    /// "did you mean" suggestions. The name is leaked so errors can hold on to it.
+    pub fn similar(&self, name: &str, keep: impl Fn(&str) -> bool) -> Option<&'static str> {
+        let found = closest(
+            name,
+            self.iter()
+                .filter(|(_, typ, _)| keep(typ))
+                .map(|(var, _, _)| var.as_str()),
+        )?;
+        Some(Box::leak(found.to_string().into_boxed_str()))
+    }
+
+    /// Every visible entry, from the outermost scope to the innermost.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(String, &'static str, Span)> {
+        self.scopes.iter().flat_map(|scope| scope.vars.iter())
+    }
+
+    /// Adds a struct type, replacing an earlier one with the same name.
+    pub fn declare_struct(&mut self, def: StructDef) {
+        self.structs.retain(|known| known.name != def.name);
+        self.structs.push(def);
+    }
+
+    /// The struct type with this name.
+    pub fn struct_def(&self, name: &str) -> Option<&StructDef> {
+        self.structs.iter().find(|def| def.name == name)
+    }
+
+    /// Every struct type declared so far.
+    pub fn structs(&self) -> &[StructDef] {
+        &self.structs
+    }
+
+    /// Type tag of a variable, or of a struct variable's field written `var.field`.
+    pub fn lookup_path(&self, path: &str) -> Option<&'static str> {
+        let Some((var, field)) = path.split_once('.') else {
+            return self.lookup(path);
+        };
+        let typ = self.lookup(var)?.trim_start_matches("const;");
+        self.struct_def(typ)?.field(field)
+    }
+
+    /// Whether this is the top level of a file, outside of every block and function.
+    pub fn at_top_level(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    /// Whether `break` and `continue` are allowed here.
+    pub fn in_loop(&self) -> bool {
+        for scope in self.scopes.iter().rev() {
+            match scope.kind {
+                ScopeKind::Loop => return true,
+                ScopeKind::Function(_) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Return type of the enclosing function, or `None` outside of one.
+    pub fn return_type(&self) -> Option<&'static str> {
+        self.scopes.iter().rev().find_map(|scope| match scope.kind {
+            ScopeKind::Function(ret) => Some(ret),
+            _ => None,
+        })
+    }
+
+    /// A new table holding only the functions and struct types visible here, which is all a
+    /// function body sees.
+    pub fn functions(&self) -> SymbolTable {
+        let mut table = SymbolTable {
+            structs: self.structs.clone(),
+            ..SymbolTable::default()
+        };
+        for (name, typ, span) in self.iter().filter(|(_, typ, _)| typ.starts_with("fn;")) {
+            table.declare_at(name.clone(), typ, *span);
+        }
+        table
+    }
+}
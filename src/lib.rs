@@ -0,0 +1,66 @@
+use c_gens::makec::make_c;
+use err_system::diagnostic::Diagnostic;
+use helpers::symbol_table::SymbolTable;
+use optimisers::pass1::pass1;
+use parse_systems::{parse_program, AST};
+use tok_system::{lexer::LexicalAnalysis, tokens::Token};
+
+pub mod c_gens;
+pub mod err_system;
+pub mod fmt_system;
+pub mod helpers;
+pub mod lsp_system;
+pub mod nulibc;
+pub mod optimisers;
+pub mod parse_systems;
+pub mod run_system;
+pub mod tok_system;
+pub mod type_system;
+
+/// Settings for [`parse_source`] and [`compile_to_c`].
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// Drop declarations whose variable is never read.
+    pub optimise: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions { optimise: true }
+    }
+}
+
+/// Splits source code into tokens.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    tokens.run_lexical_analysis(source);
+    tokens
+}
+
+/// Parses and type-checks source code into an AST. Nothing is printed; every error is returned
+/// as a diagnostic.
+pub fn parse_source(source: &str, options: &CompileOptions) -> Result<Vec<AST>, Vec<Diagnostic>> {
+    let tokens = tokenize(source);
+    let mut collected_vars = SymbolTable::default();
+    let mut collected_errors = Vec::new();
+    let mut ast = parse_program(
+        &tokens,
+        &source.to_string(),
+        &mut collected_vars,
+        &mut collected_errors,
+    );
+    if !collected_errors.is_empty() {
+        return Err(collected_errors.into_iter().map(Diagnostic::from).collect());
+    }
+    if options.optimise {
+        pass1(&mut ast);
+    }
+    Ok(ast)
+}
+
+/// Compiles source code to C. The code includes `nulibc.h`; the sources it needs are
+/// [`nulibc::NULIBCH`] and [`nulibc::NULIBC`].
+pub fn compile_to_c(source: &str, options: &CompileOptions) -> Result<String, Vec<Diagnostic>> {
+    let ast = parse_source(source, options)?;
+    Ok(make_c(&ast, true, &mut SymbolTable::default()))
+}
@@ -0,0 +1,253 @@
+// What the language server knows about one open file: its diagnostics, and which declaration
+// each name refers to.
+use crate::{
+    err_system::diagnostic::Diagnostic,
+    helpers::{
+        fn_calls::fn_signature,
+        symbol_table::{SymbolTable, UNKNOWN},
+    },
+    parse_systems::{collect_warnings, parse_program},
+    tok_system::tokens::{Span, Token, TokenKind},
+    tokenize,
+};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Words offered by completion everywhere.
+pub const KEYWORDS: [&str; 24] = [
+    "may", "const", "shadow", "if", "else", "while", "for", "in", "step", "fn", "return", "break",
+    "continue", "print", "println", "eprint", "eprintln", "input", "true", "false", "len", "push",
+    "pop", "struct",
+];
+
+/// A declaration: `(name, type tag, span of the name)`, as kept by `SymbolTable`.
+pub type Decl = (String, &'static str, Span);
+
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    tokens: Vec<Token>,
+    declared: Vec<Decl>,
+    /// Every `{ ... }` block as byte offsets of its braces, and whether it is a function body.
+    blocks: Vec<(usize, usize, bool)>,
+}
+
+/// Parses a file the way `neit build` would, keeping what the parser learned instead of
+/// printing it. Warnings are only given for files without errors, as in a build.
+pub fn analyze(code: &str) -> Analysis {
+    let tokens = tokenize(code);
+    let parsed = catch_unwind(AssertUnwindSafe(|| {
+        let mut collected_vars = SymbolTable::default();
+        let mut collected_errors = Vec::new();
+        parse_program(
+            &tokens,
+            &code.to_string(),
+            &mut collected_vars,
+            &mut collected_errors,
+        );
+        (collected_vars, collected_errors)
+    }));
+    // A parser bug should not take the editor's server down with it.
+    let (collected_vars, collected_errors) = parsed.unwrap_or_default();
+    let diagnostics = if collected_errors.is_empty() {
+        collect_warnings(&collected_vars, code)
+    } else {
+        collected_errors.into_iter().map(Diagnostic::from).collect()
+    };
+    let mut analysis = Analysis {
+        diagnostics,
+        blocks: Vec::new(),
+        declared: collected_vars.declarations().to_vec(),
+        tokens,
+    };
+    analysis.blocks = analysis.find_blocks(code.len());
+    analysis
+}
+
+impl Analysis {
+    /// The first word on a line, which says what kind of statement it is.
+    fn line_head(&self, line: i32) -> Option<&str> {
+        self.tokens
+            .iter()
+            .filter(|tok| tok.span.line == line)
+            .find_map(|tok| match &tok.kind {
+                TokenKind::Iden(text) => Some(text.as_str()),
+                _ => None,
+            })
+    }
+
+    fn find_blocks(&self, len: usize) -> Vec<(usize, usize, bool)> {
+        let mut blocks = Vec::new();
+        let mut open = Vec::new();
+        for tok in &self.tokens {
+            match tok.kind {
+                TokenKind::LCurly => open.push((tok.span.start, tok.span.line)),
+                TokenKind::RCurly => {
+                    if let Some((start, line)) = open.pop() {
+                        blocks.push((start, tok.span.start, self.line_head(line) == Some("fn")));
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Unclosed blocks run to the end of the file.
+        for (start, line) in open {
+            blocks.push((start, len, self.line_head(line) == Some("fn")));
+        }
+        blocks
+    }
+
+    /// The innermost block around `offset`.
+    fn block_at(&self, offset: usize) -> Option<(usize, usize, bool)> {
+        self.blocks
+            .iter()
+            .filter(|(start, end, _)| *start < offset && offset <= *end)
+            .max_by_key(|(start, _, _)| *start)
+            .copied()
+    }
+
+    /// The byte range a declaration is visible in, from where it takes effect to the end of
+    /// its block. Loop variables and parameters belong to the block after their header.
+    fn scope_of(&self, decl: &Decl) -> (usize, usize) {
+        let span = decl.2;
+        let head = self.line_head(span.line);
+        let on_line = || self.tokens.iter().filter(|tok| tok.span.line == span.line);
+        let in_header = head == Some("for")
+            || (head == Some("fn")
+                && on_line()
+                    .any(|tok| tok.kind == TokenKind::LSmallBrac && tok.span.start < span.start));
+        if in_header {
+            let body = self
+                .tokens
+                .iter()
+                .find(|tok| tok.kind == TokenKind::LCurly && tok.span.start > span.start)
+                .and_then(|tok| self.block_at(tok.span.start + 1));
+            if let Some((_, end, _)) = body {
+                return (span.end, end);
+            }
+        }
+        // A `may` or `const` takes effect after its line, so `may shadow x = x + 1` reads the
+        // outer `x`.
+        let from = match head {
+            Some("may" | "const") => on_line()
+                .find(|tok| matches!(tok.kind, TokenKind::EOL | TokenKind::EOF))
+                .map_or(span.end, |tok| tok.span.start),
+            _ => span.end,
+        };
+        match self.block_at(span.start) {
+            Some((_, end, _)) => (from, end),
+            None => (from, usize::MAX),
+        }
+    }
+
+    /// Declarations visible at `offset`, innermost last. A function body only sees functions
+    /// and its own names.
+    pub fn visible(&self, offset: usize) -> Vec<&Decl> {
+        let function = self
+            .blocks
+            .iter()
+            .filter(|(start, end, is_fn)| *is_fn && *start < offset && offset <= *end)
+            .max_by_key(|(start, _, _)| *start);
+        let mut found: Vec<(&Decl, usize)> = self
+            .declared
+            .iter()
+            .filter_map(|decl| {
+                let (from, to) = self.scope_of(decl);
+                let inside =
+                    function.is_none_or(|(_, end, _)| decl.1.starts_with("fn;") || to <= *end);
+                (from <= offset && offset <= to && inside).then_some((decl, from))
+            })
+            .collect();
+        found.sort_by_key(|(_, from)| *from);
+        found.into_iter().map(|(decl, _)| decl).collect()
+    }
+
+    /// The name under the cursor, if it is one that can refer to a declaration. Words of
+    /// printed text are not names; `%name` in printed text is. Strings and comments are not
+    /// words, so nothing in them is found.
+    pub fn name_at(&self, code: &str, offset: usize) -> Option<(&str, Span)> {
+        let tok = self.tokens.iter().find(|tok| {
+            matches!(tok.kind, TokenKind::Iden(_))
+                && tok.span.start <= offset
+                && offset <= tok.span.end
+        })?;
+        let TokenKind::Iden(text) = &tok.kind else {
+            return None;
+        };
+        let mut chars = text.chars();
+        if !chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            || !chars.all(|c| c.is_alphanumeric() || c == '_')
+        {
+            return None;
+        }
+        let line_start = code[..tok.span.start].rfind('\n').map_or(0, |at| at + 1);
+        let before = &code[line_start..tok.span.start];
+        let printed = matches!(
+            self.line_head(tok.span.line),
+            Some("print" | "println" | "eprint" | "eprintln")
+        );
+        if printed && !before.trim_start().is_empty() && !before.ends_with('%') {
+            return None;
+        }
+        Some((text, tok.span))
+    }
+
+    /// The declaration the name at `offset` refers to, or the declaration the cursor is on.
+    pub fn definition(&self, code: &str, offset: usize) -> Option<&Decl> {
+        let (name, span) = self.name_at(code, offset)?;
+        if let Some(decl) = self.declared.iter().find(|decl| decl.2.start == span.start) {
+            return Some(decl);
+        }
+        self.visible(span.start)
+            .into_iter()
+            .rev()
+            .find(|decl| decl.0 == name)
+    }
+}
+
+/// How a declaration is shown on hover and in completion, e.g. `count: i32`,
+/// `const limit: i32` or `fn add(i32, i32) -> i32`.
+pub fn signature(decl: &Decl) -> String {
+    let (name, typ, _) = decl;
+    if let Some((ret, params)) = fn_signature(typ) {
+        let ret = if ret == "void" {
+            String::new()
+        } else {
+            format!(" -> {}", ret)
+        };
+        return format!("fn {}({}){}", name, params.join(", "), ret);
+    }
+    match typ.strip_prefix("const;") {
+        Some(typ) => format!("const {}: {}", name, typ),
+        None if *typ == UNKNOWN => format!("{}: unknown (its declaration has an error)", name),
+        None => format!("{}: {}", name, typ),
+    }
+}
+
+/// LSP position of a byte offset: the 0-based line and the column in UTF-16 code units.
+pub fn position(code: &str, offset: usize) -> (usize, usize) {
+    let before = &code[..offset.min(code.len())];
+    let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+    (
+        before.matches('\n').count(),
+        before[line_start..].encode_utf16().count(),
+    )
+}
+
+/// Byte offset of an LSP position. Positions past the end of a line stop at the line end.
+pub fn offset(code: &str, line: usize, character: usize) -> usize {
+    let line_start = if line == 0 {
+        0
+    } else {
+        match code.match_indices('\n').nth(line - 1) {
+            Some((at, _)) => at + 1,
+            None => return code.len(),
+        }
+    };
+    let mut units = 0;
+    for (at, c) in code[line_start..].char_indices() {
+        if c == '\n' || units >= character {
+            return line_start + at;
+        }
+        units += c.len_utf16();
+    }
+    code.len()
+}
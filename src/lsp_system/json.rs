@@ -0,0 +1,238 @@
+// A small JSON value with a parser and a writer, enough for the language server's messages.
+use crate::err_system::json_msg_gen::json_str;
+use std::{fmt, iter::Peekable, str::Chars};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    /// Members in the order they were written.
+    Obj(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a whole JSON text, or returns `None` if it is not valid JSON.
+    pub fn parse(text: &str) -> Option<Json> {
+        let mut chars = text.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_blank(&mut chars);
+        chars.peek().is_none().then_some(value)
+    }
+
+    /// An object built from `(key, value)` pairs.
+    pub fn obj<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Obj(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// The member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Obj(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Follows a path of object keys, e.g. `["textDocument", "uri"]`.
+    pub fn at(&self, path: &[&str]) -> Option<&Json> {
+        path.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// A whole, non-negative number such as a line or character position.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Num(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Arr(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Self {
+        Json::Str(text.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Self {
+        Json::Str(text)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Num(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(flag: bool) -> Self {
+        Json::Bool(flag)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(flag) => write!(f, "{}", flag),
+            Json::Num(n) => write!(f, "{}", n),
+            Json::Str(text) => write!(f, "{}", json_str(text)),
+            Json::Arr(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Obj(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_str(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn skip_blank(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+}
+
+/// Consumes `word` (after its first character, which the caller has seen) and returns `value`.
+fn parse_word(chars: &mut Peekable<Chars>, word: &str, value: Json) -> Option<Json> {
+    for expected in word.chars() {
+        if chars.next()? != expected {
+            return None;
+        }
+    }
+    Some(value)
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Option<Json> {
+    skip_blank(chars);
+    match *chars.peek()? {
+        'n' => parse_word(chars, "null", Json::Null),
+        't' => parse_word(chars, "true", Json::Bool(true)),
+        'f' => parse_word(chars, "false", Json::Bool(false)),
+        '"' => parse_string(chars).map(Json::Str),
+        '[' => {
+            chars.next();
+            let mut items = Vec::new();
+            skip_blank(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Some(Json::Arr(items));
+            }
+            loop {
+                items.push(parse_value(chars)?);
+                skip_blank(chars);
+                match chars.next()? {
+                    ',' => {}
+                    ']' => return Some(Json::Arr(items)),
+                    _ => return None,
+                }
+            }
+        }
+        '{' => {
+            chars.next();
+            let mut members = Vec::new();
+            skip_blank(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Some(Json::Obj(members));
+            }
+            loop {
+                skip_blank(chars);
+                let key = parse_string(chars)?;
+                skip_blank(chars);
+                chars.next_if_eq(&':')?;
+                members.push((key, parse_value(chars)?));
+                skip_blank(chars);
+                match chars.next()? {
+                    ',' => {}
+                    '}' => return Some(Json::Obj(members)),
+                    _ => return None,
+                }
+            }
+        }
+        _ => {
+            let mut number = String::new();
+            while let Some(c) =
+                chars.next_if(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+            {
+                number.push(c);
+            }
+            number.parse().ok().map(Json::Num)
+        }
+    }
+}
+
+/// Reads a quoted string, decoding its escapes. Surrogate pairs in `\u` escapes are joined.
+fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    chars.next_if_eq(&'"')?;
+    let mut text = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(text),
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                't' => text.push('\t'),
+                'r' => text.push('\r'),
+                'b' => text.push('\u{8}'),
+                'f' => text.push('\u{c}'),
+                'u' => {
+                    let high = parse_hex4(chars)?;
+                    let code = if (0xD800..0xDC00).contains(&high) {
+                        chars.next_if_eq(&'\\')?;
+                        chars.next_if_eq(&'u')?;
+                        let low = parse_hex4(chars)?;
+                        0x10000 + ((high - 0xD800) << 10) + (low.checked_sub(0xDC00)? & 0x3FF)
+                    } else {
+                        high
+                    };
+                    text.push(char::from_u32(code)?);
+                }
+                other => text.push(other),
+            },
+            c => text.push(c),
+        }
+    }
+}
+
+fn parse_hex4(chars: &mut Peekable<Chars>) -> Option<u32> {
+    let digits: String = (0..4).filter_map(|_| chars.next()).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+    u32::from_str_radix(&digits, 16).ok()
+}
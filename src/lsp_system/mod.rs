@@ -0,0 +1,349 @@
+// `neit lsp`: a Language Server Protocol server over stdin and stdout. Files are parsed again
+// on every change; diagnostics, hover, go-to-definition and completion come from that parse.
+use crate::err_system::diagnostic::{Diagnostic, Severity};
+use crate::tok_system::tokens::Span;
+use analysis::{analyze, offset, position, signature, Analysis, KEYWORDS};
+use json::Json;
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+pub mod analysis;
+pub mod json;
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+// LSP enum values.
+const SEVERITY_ERROR: usize = 1;
+const SEVERITY_WARNING: usize = 2;
+const TEXT_SYNC_FULL: usize = 1;
+const KIND_FUNCTION: usize = 3;
+const KIND_VARIABLE: usize = 6;
+const KIND_KEYWORD: usize = 14;
+const KIND_CONSTANT: usize = 21;
+
+/// An open file.
+struct Document {
+    code: String,
+    analysis: Analysis,
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, Document>,
+    shut_down: bool,
+    /// Set by the `exit` notification, with the process exit code.
+    exit: Option<i32>,
+}
+
+/// Serves requests from stdin until the client sends `exit` or closes the stream, and returns
+/// the exit code: 0 if the client asked to shut down first.
+pub fn serve() -> i32 {
+    serve_on(&mut io::stdin().lock(), &mut io::stdout().lock())
+}
+
+/// Like [`serve`], but reads requests from `input` and writes replies to `output`.
+pub fn serve_on(input: &mut impl BufRead, output: &mut impl Write) -> i32 {
+    let mut server = Server::default();
+    loop {
+        let message = match read_message(input) {
+            Ok(Some(text)) => text,
+            Ok(None) | Err(_) => return if server.shut_down { 0 } else { 1 },
+        };
+        let replies = match Json::parse(&message) {
+            Some(message) => server.handle(&message),
+            None => vec![error_reply(Json::Null, PARSE_ERROR, "invalid JSON")],
+        };
+        for reply in replies {
+            if write_message(output, &reply).is_err() {
+                return 1;
+            }
+        }
+        if let Some(code) = server.exit {
+            return code;
+        }
+    }
+}
+
+/// Reads one `Content-Length` framed message, or `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn reply(id: Json, result: Json) -> Json {
+    Json::obj([("jsonrpc", "2.0".into()), ("id", id), ("result", result)])
+}
+
+fn error_reply(id: Json, code: i32, message: &str) -> Json {
+    Json::obj([
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Json::obj([
+                ("code", Json::Num(code as f64)),
+                ("message", message.into()),
+            ]),
+        ),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::obj([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+/// An LSP range covering a span.
+fn range(code: &str, span: Span) -> Json {
+    let point = |at: usize| {
+        let (line, character) = position(code, at);
+        Json::obj([("line", line.into()), ("character", character.into())])
+    };
+    Json::obj([("start", point(span.start)), ("end", point(span.end))])
+}
+
+fn location(uri: &str, code: &str, span: Span) -> Json {
+    Json::obj([("uri", uri.into()), ("range", range(code, span))])
+}
+
+/// An LSP diagnostic. Notes become related locations; the hint and suggestions are added to
+/// the message.
+fn lsp_diagnostic(uri: &str, code: &str, diag: &Diagnostic) -> Json {
+    let mut message = format!("{}: {}", diag.title, diag.label);
+    if !diag.hint.is_empty() {
+        message.push_str(&format!("\nHint: {}", diag.hint));
+    }
+    for suggestion in &diag.suggestions {
+        message.push_str(&format!("\n{}", suggestion.message));
+    }
+    let related = diag
+        .notes
+        .iter()
+        .map(|note| {
+            Json::obj([
+                ("location", location(uri, code, note.span)),
+                ("message", note.message.into()),
+            ])
+        })
+        .collect();
+    let severity = match diag.severity {
+        Severity::Error => SEVERITY_ERROR,
+        Severity::Warning => SEVERITY_WARNING,
+    };
+    Json::obj([
+        ("range", range(code, diag.span)),
+        ("severity", severity.into()),
+        ("code", diag.code.into()),
+        ("source", "neit".into()),
+        ("message", message.into()),
+        ("relatedInformation", Json::Arr(related)),
+    ])
+}
+
+impl Server {
+    /// Handles one message and returns what to send back: a reply for requests, and
+    /// diagnostics whenever a file changes.
+    fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        if self.shut_down && method != "exit" {
+            return id
+                .map(|id| error_reply(id, INVALID_REQUEST, "the server is shut down"))
+                .into_iter()
+                .collect();
+        }
+        match method {
+            "initialize" => vec![reply(id.unwrap_or(Json::Null), capabilities())],
+            "shutdown" => {
+                self.shut_down = true;
+                vec![reply(id.unwrap_or(Json::Null), Json::Null)]
+            }
+            "exit" => {
+                self.exit = Some(if self.shut_down { 0 } else { 1 });
+                Vec::new()
+            }
+            "textDocument/didOpen" => {
+                let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str);
+                let text = params.at(&["textDocument", "text"]).and_then(Json::as_str);
+                match (uri, text) {
+                    (Some(uri), Some(text)) => vec![self.update(uri, text.to_string())],
+                    _ => Vec::new(),
+                }
+            }
+            "textDocument/didChange" => {
+                // Full sync: the last change holds the whole new text.
+                let uri = params.at(&["textDocument", "uri"]).and_then(Json::as_str);
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                match (uri, text) {
+                    (Some(uri), Some(text)) => vec![self.update(uri, text.to_string())],
+                    _ => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                let Some(uri) = params.at(&["textDocument", "uri"]).and_then(Json::as_str) else {
+                    return Vec::new();
+                };
+                self.documents.remove(uri);
+                vec![publish(uri, Vec::new())]
+            }
+            "textDocument/hover" => {
+                let result = self.hover(params).unwrap_or(Json::Null);
+                vec![reply(id.unwrap_or(Json::Null), result)]
+            }
+            "textDocument/definition" => {
+                let result = self.definition(params).unwrap_or(Json::Null);
+                vec![reply(id.unwrap_or(Json::Null), result)]
+            }
+            "textDocument/completion" => {
+                let result = self.completion(params).unwrap_or(Json::Arr(Vec::new()));
+                vec![reply(id.unwrap_or(Json::Null), result)]
+            }
+            // Other requests are answered with an error; other notifications are ignored.
+            _ => id
+                .map(|id| error_reply(id, METHOD_NOT_FOUND, "method not supported"))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Stores a file's new text and returns its diagnostics.
+    fn update(&mut self, uri: &str, code: String) -> Json {
+        let analysis = analyze(&code);
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diag| lsp_diagnostic(uri, &code, diag))
+            .collect();
+        self.documents
+            .insert(uri.to_string(), Document { code, analysis });
+        publish(uri, diagnostics)
+    }
+
+    /// The document and byte offset a position request points at.
+    fn target<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a Document, usize)> {
+        let uri = params.at(&["textDocument", "uri"])?.as_str()?;
+        let line = params.at(&["position", "line"])?.as_usize()?;
+        let character = params.at(&["position", "character"])?.as_usize()?;
+        let document = self.documents.get(uri)?;
+        Some((uri, document, offset(&document.code, line, character)))
+    }
+
+    fn hover(&self, params: &Json) -> Option<Json> {
+        let (_, document, at) = self.target(params)?;
+        let (_, span) = document.analysis.name_at(&document.code, at)?;
+        let decl = document.analysis.definition(&document.code, at)?;
+        Some(Json::obj([
+            (
+                "contents",
+                Json::obj([
+                    ("kind", "markdown".into()),
+                    ("value", format!("```neit\n{}\n```", signature(decl)).into()),
+                ]),
+            ),
+            ("range", range(&document.code, span)),
+        ]))
+    }
+
+    fn definition(&self, params: &Json) -> Option<Json> {
+        let (uri, document, at) = self.target(params)?;
+        let decl = document.analysis.definition(&document.code, at)?;
+        Some(location(uri, &document.code, decl.2))
+    }
+
+    /// Keywords, then the names visible at the cursor.
+    fn completion(&self, params: &Json) -> Option<Json> {
+        let (_, document, at) = self.target(params)?;
+        let mut items: Vec<Json> = KEYWORDS
+            .iter()
+            .map(|keyword| Json::obj([("label", (*keyword).into()), ("kind", KIND_KEYWORD.into())]))
+            .collect();
+        let mut seen = Vec::new();
+        for decl in document.analysis.visible(at).into_iter().rev() {
+            if seen.contains(&&decl.0) {
+                continue;
+            }
+            seen.push(&decl.0);
+            let kind = if decl.1.starts_with("fn;") {
+                KIND_FUNCTION
+            } else if decl.1.starts_with("const;") {
+                KIND_CONSTANT
+            } else {
+                KIND_VARIABLE
+            };
+            items.push(Json::obj([
+                ("label", decl.0.as_str().into()),
+                ("kind", kind.into()),
+                ("detail", signature(decl).into()),
+            ]));
+        }
+        Some(Json::Arr(items))
+    }
+}
+
+fn capabilities() -> Json {
+    Json::obj([
+        (
+            "capabilities",
+            Json::obj([
+                ("textDocumentSync", TEXT_SYNC_FULL.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("completionProvider", Json::obj([])),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::obj([
+                ("name", "neit".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    notification(
+        "textDocument/publishDiagnostics",
+        Json::obj([("uri", uri.into()), ("diagnostics", Json::Arr(diagnostics))]),
+    )
+}
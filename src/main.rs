@@ -1,7 +1,18 @@
 use build_system::linux_b::linux_b_64;
-use c_gens::makec::make_c;
 use colored::*;
-use parse_systems::parse;
+use neit::{
+    c_gens::makec::make_c,
+    err_system::{
+        diagnostic::{MessageFormat, ReportOptions},
+        explain::explain,
+    },
+    fmt_system::format_source,
+    helpers::symbol_table::SymbolTable,
+    lsp_system::serve,
+    parse_systems::parse,
+    run_system::{interp::Interpreter, repl::repl},
+    tok_system::{lexer::LexicalAnalysis, tokens::Token},
+};
 use std::collections::HashMap;
 use std::{
     env::args,
@@ -11,16 +22,8 @@ use std::{
     process::exit,
     time::{Duration, Instant},
 };
-use tok_system::{lexer::LexicalAnalysis, tokens::Token};
 
 pub mod build_system;
-pub mod c_gens;
-pub mod err_system;
-pub mod helpers;
-pub mod nulibc;
-pub mod optimisers;
-pub mod parse_systems;
-pub mod tok_system;
 
 #[allow(dead_code)]
 pub struct Config {
@@ -30,6 +33,9 @@ pub struct Config {
     out: &'static str,
     targets: Vec<&'static str>,
     cc: &'static str,
+    report: ReportOptions,
+    /// `fmt --check`: report unformatted files instead of rewriting them.
+    check: bool,
 }
 
 fn normalize_target(input: &str) -> &'static str {
@@ -42,12 +48,14 @@ fn normalize_target(input: &str) -> &'static str {
 
 fn parse_config() -> Config {
     let args: Vec<String> = args().collect();
-    if args.len() < 3 {
+    // `help`, `repl` and `lsp` are the only commands that take no file.
+    let no_file = args.len() == 2 && matches!(args[1].as_str(), "help" | "repl" | "lsp");
+    if args.len() < 3 && !no_file {
         print_help();
         exit(1);
     }
     let command: &'static str = Box::leak(args[1].clone().into_boxed_str());
-    let path: &'static str = Box::leak(args[2].clone().into_boxed_str());
+    let path: &'static str = Box::leak(args.get(2).cloned().unwrap_or_default().into_boxed_str());
     let mut static_flag = false;
     let mut out: &'static str = "out";
     let default_target = if cfg!(target_os = "windows") {
@@ -57,6 +65,8 @@ fn parse_config() -> Config {
     };
     let mut targets: Vec<&'static str> = vec![default_target];
     let mut cc: &'static str = "";
+    let mut report = ReportOptions::default();
+    let mut check = false;
     for arg in args.iter().skip(3) {
         let arg_static: &'static str = Box::leak(arg.clone().into_boxed_str());
         if arg_static == "--static" {
@@ -68,6 +78,26 @@ fn parse_config() -> Config {
             targets = value.split(',').map(|s| normalize_target(s)).collect();
         } else if arg_static.starts_with("--cc=") {
             cc = Box::leak(arg_static["--cc=".len()..].to_string().into_boxed_str());
+        } else if arg_static == "--check" {
+            check = true;
+        } else if arg_static == "--deny-warnings" {
+            report.deny_warnings = true;
+        } else if let Some(value) = arg_static.strip_prefix("--message-format=") {
+            report.format = match value {
+                "human" => MessageFormat::Human,
+                "json" => MessageFormat::Json,
+                _ => {
+                    println!(
+                        "{}",
+                        format!(
+                            "┌[Warning] Unknown message format '{}', using 'human'",
+                            value
+                        )
+                        .yellow()
+                    );
+                    MessageFormat::Human
+                }
+            };
         } else {
             println!(
                 "{}",
@@ -82,6 +112,8 @@ fn parse_config() -> Config {
         out,
         targets,
         cc,
+        report,
+        check,
     }
 }
 
@@ -96,6 +128,26 @@ fn print_help() {
         "{}",
         "│   ├─ build   - Build a Neit source file/folder".blue()
     );
+    println!(
+        "{}",
+        "│   ├─ run     - Run a Neit source file without a C compiler".blue()
+    );
+    println!(
+        "{}",
+        "│   ├─ repl    - Start an interactive session (no file needed)".blue()
+    );
+    println!(
+        "{}",
+        "│   ├─ explain - Explain an error code, e.g. 'neit explain N0006'".blue()
+    );
+    println!(
+        "{}",
+        "│   ├─ fmt     - Format a Neit source file in place".blue()
+    );
+    println!(
+        "{}",
+        "│   ├─ lsp     - Start a language server on stdin/stdout (no file needed)".blue()
+    );
     println!("{}", "│   └─ help    - Display this help message".blue());
     println!("{}", "├─ Options:".blue());
     println!(
@@ -124,7 +176,19 @@ fn print_help() {
     );
     println!(
         "{}",
-        "│   └─ --cc=<compiler>         - Specify compiler (zig, clang, gcc)".blue()
+        "│   ├─ --cc=<compiler>         - Specify compiler (zig, clang, gcc)".blue()
+    );
+    println!(
+        "{}",
+        "│   ├─ --check                 - With fmt: fail if the file is not formatted, without changing it".blue()
+    );
+    println!(
+        "{}",
+        "│   ├─ --message-format=<fmt>  - Print errors as 'human' (default) or 'json'".blue()
+    );
+    println!(
+        "{}",
+        "│   └─ --deny-warnings         - Fail when there are warnings".blue()
     );
     println!("{}", "└─ Example: neit build ./source.neit --out=program --target=linux-x86-64,winx8664 --cc=zig".blue());
 }
@@ -184,39 +248,14 @@ fn format_duration(duration: Duration) -> String {
     }
 }
 
-fn main_logic() {
-    let total_start = Instant::now();
-    let config = parse_config();
-    if config.command == "help" {
-        print_help();
-        exit(0);
-    }
-    if config.command != "build" && config.command != "neit" {
-        eprintln!("{}", format!("┌[!!] CRITICAL ERROR").red());
-        eprintln!(
-            "{}",
-            format!("├─ Command '{}' is not recognized.", config.command).red()
-        );
-        eprintln!(
-            "{}",
-            "└─ Please refer to 'neit help' for a list of valid commands.".red()
-        );
-        exit(1);
-    }
-    println!(
-        "{}",
-        "┌[*] Neit Build System - Initiating Build Process".blue()
-    );
-    println!(
-        "{}",
-        format!("├─ Compiling source file: '{}'", config.path).cyan()
-    );
-    let proj = Path::new(config.path);
+/// Reads a source file, exiting with an error message when it cannot be used.
+fn load_source(path: &str) -> String {
+    let proj = Path::new(path);
     if !proj.exists() {
-        eprintln!("{}", format!("┌[Error] File/Directory Not Found").red());
+        eprintln!("{}", "┌[Error] File/Directory Not Found".red());
         eprintln!(
             "{}",
-            format!("├─ The path '{}' does not exist.", config.path).red()
+            format!("├─ The path '{}' does not exist.", path).red()
         );
         eprintln!("{}", "└─ Verify the path and try again.".red());
         exit(1);
@@ -227,29 +266,184 @@ fn main_logic() {
         eprintln!("{}", "└─ Please provide a valid file path.".red());
         exit(1);
     }
-    let mut file = File::open(config.path).unwrap_or_else(|e| {
+    let mut file = File::open(path).unwrap_or_else(|e| {
         eprintln!("{}", "┌[Error] Unable to Open Source File".red());
-        eprintln!(
-            "{}",
-            format!("├─ Failed to open '{}': {}", config.path, e).red()
-        );
+        eprintln!("{}", format!("├─ Failed to open '{}': {}", path, e).red());
         eprintln!("{}", "└─ Check file permissions and try again.".red());
         exit(1);
     });
     let mut code = String::new();
     if file.read_to_string(&mut code).is_err() {
         eprintln!("{}", "┌[Error] File Read Failure".red());
-        eprintln!("{}", format!("├─ Unable to read '{}'.", config.path).red());
+        eprintln!("{}", format!("├─ Unable to read '{}'.", path).red());
         eprintln!(
             "{}",
             "└─ Ensure the file is not corrupted and is accessible.".red()
         );
         exit(1);
     }
+    code
+}
+
+/// Prints a build progress line. With `--message-format=json` it goes to stderr so stdout
+/// carries nothing but diagnostics.
+pub fn status(config: &Config, line: impl std::fmt::Display) {
+    match config.report.format {
+        MessageFormat::Human => println!("{}", line),
+        MessageFormat::Json => eprintln!("{}", line),
+    }
+}
+
+/// `neit explain <code>`: prints the long explanation of an error code.
+fn explain_logic(code: &str) {
+    match explain(code) {
+        Some(text) => println!("{}", text),
+        None => {
+            eprintln!("{}", "┌[Error] Unknown Error Code".red());
+            eprintln!(
+                "{}",
+                format!("├─ '{}' is not a Neit error code.", code).red()
+            );
+            eprintln!(
+                "{}",
+                "└─ Codes look like N0006 and are shown with every error.".red()
+            );
+            exit(1);
+        }
+    }
+}
+
+/// `neit fmt`: rewrites the file in the canonical layout, or with `--check` only reports whether
+/// it is in it.
+fn fmt_logic(config: &Config) {
+    let code = load_source(config.path);
+    let Some(formatted) = format_source(&code) else {
+        eprintln!("{}", "┌[Error] Formatting Failed".red());
+        eprintln!(
+            "{}",
+            format!("├─ Formatting '{}' would change its meaning.", config.path).red()
+        );
+        eprintln!(
+            "{}",
+            "└─ The file was left unchanged; please report this.".red()
+        );
+        exit(1);
+    };
+    if formatted == code {
+        println!(
+            "{}",
+            format!("[*] '{}' is already formatted.", config.path).green()
+        );
+        return;
+    }
+    if config.check {
+        // The first line that differs, 1-based.
+        let line = code
+            .lines()
+            .zip(formatted.lines())
+            .position(|(old, new)| old != new)
+            .unwrap_or_else(|| code.lines().count().min(formatted.lines().count()))
+            + 1;
+        eprintln!("{}", "┌[!] File Not Formatted".red());
+        eprintln!(
+            "{}",
+            format!(
+                "├─ '{}' differs from the formatted layout at line {}.",
+                config.path, line
+            )
+            .red()
+        );
+        eprintln!(
+            "{}",
+            format!("└─ Run 'neit fmt {}' to format it.", config.path).red()
+        );
+        exit(1);
+    }
+    if let Err(e) = std::fs::write(config.path, formatted) {
+        eprintln!("{}", "┌[Error] File Write Failure".red());
+        eprintln!(
+            "{}",
+            format!("└─ Unable to write '{}': {}", config.path, e).red()
+        );
+        exit(1);
+    }
+    println!("{}", format!("[*] Formatted '{}'.", config.path).green());
+}
+
+/// `neit run`: parses the file and interprets it directly, printing only the program's output.
+fn run_logic(config: &Config) {
+    let code = load_source(config.path);
+    let mut tokens: Vec<Token> = Vec::new();
+    tokens.run_lexical_analysis(&code);
+    let mut collected_vars = SymbolTable::default();
+    let mut collected_errors = Vec::new();
+    let (ast, _, _) = parse(
+        &tokens,
+        &code,
+        config.path,
+        false,
+        &mut collected_vars,
+        &mut collected_errors,
+        config.report,
+    );
+    if let Err(err) = Interpreter::default().run(&ast) {
+        eprintln!("{}", err);
+        exit(1);
+    }
+}
+
+fn main_logic() {
+    let total_start = Instant::now();
+    let config = parse_config();
+    if config.command == "help" {
+        print_help();
+        exit(0);
+    }
+    if config.command == "repl" {
+        repl();
+        exit(0);
+    }
+    if config.command == "lsp" {
+        exit(serve());
+    }
+    if config.command == "explain" {
+        explain_logic(config.path);
+        exit(0);
+    }
+    if config.command == "fmt" {
+        fmt_logic(&config);
+        exit(0);
+    }
+    if config.command == "run" {
+        run_logic(&config);
+        exit(0);
+    }
+    if config.command != "build" && config.command != "neit" {
+        eprintln!("{}", format!("┌[!!] CRITICAL ERROR").red());
+        eprintln!(
+            "{}",
+            format!("├─ Command '{}' is not recognized.", config.command).red()
+        );
+        eprintln!(
+            "{}",
+            "└─ Please refer to 'neit help' for a list of valid commands.".red()
+        );
+        exit(1);
+    }
+    status(
+        &config,
+        "┌[*] Neit Build System - Initiating Build Process".blue(),
+    );
+    status(
+        &config,
+        format!("├─ Compiling source file: '{}'", config.path).cyan(),
+    );
+    let mut code = load_source(config.path);
+    let proj = Path::new(config.path);
     //let topcode = code.clone();
-    println!(
-        "{}",
-        format!("├─ Source file '{}' loaded successfully.", config.path).cyan()
+    status(
+        &config,
+        format!("├─ Source file '{}' loaded successfully.", config.path).cyan(),
     );
     let mut hash_map = read_hashes();
     if !source_has_changed(config.path, &mut hash_map) {
@@ -260,28 +454,28 @@ fn main_logic() {
             #[cfg(not(target_os = "windows"))]
             out_file.push_str(".out");
         }
-        println!("{}", "[*] No changes detected in the source.".cyan());
-        println!("{}", "[*] Skipping re-tokenization and parsing.".cyan());
-        println!(
-            "{}",
-            format!("└─ Reusing existing output file: '{}'", out_file).cyan()
+        status(&config, "[*] No changes detected in the source.".cyan());
+        status(&config, "[*] Skipping re-tokenization and parsing.".cyan());
+        status(
+            &config,
+            format!("└─ Reusing existing output file: '{}'", out_file).cyan(),
         );
         exit(0);
     } else {
-        println!("{}", "[*] Source modifications detected.".cyan());
-        println!("{}", "[*] Tokenizing source code...".cyan());
+        status(&config, "[*] Source modifications detected.".cyan());
+        status(&config, "[*] Tokenizing source code...".cyan());
         let mut tokens: Vec<Token> = Vec::new();
         tokens.run_lexical_analysis(&code);
-        println!(
-            "{}",
+        status(
+            &config,
             format!(
                 "[*] Tokenization complete ({} tokens produced).",
                 tokens.len()
             )
-            .cyan()
+            .cyan(),
         );
         let proj_path: &'static str = Box::leak(proj.display().to_string().into_boxed_str());
-        let mut collected_vars = Vec::new();
+        let mut collected_vars = SymbolTable::default();
         let mut collected_errors = Vec::new();
         let (ast, _, _) = parse(
             &tokens,
@@ -290,39 +484,34 @@ fn main_logic() {
             false,
             &mut collected_vars,
             &mut collected_errors,
-            1,
-        );
-        println!(
-            "{}",
-            "[*] Parsing complete. AST generated successfully.".cyan()
+            config.report,
         );
-        let mut math_exprs = HashMap::new();
-        code = make_c(
-            &ast,
-            true,
-            &mut collected_vars,
-            &mut collected_errors,
-            &mut math_exprs,
+        status(
+            &config,
+            "[*] Parsing complete. AST generated successfully.".cyan(),
         );
-        println!("{}", "[*] Intermediate C code generated.".cyan());
-        println!(
-            "{}",
+        // Code generation rebuilds the scopes as it walks the tree.
+        collected_vars.clear();
+        code = make_c(&ast, true, &mut collected_vars);
+        status(&config, "[*] Intermediate C code generated.".cyan());
+        status(
+            &config,
             format!(
                 "└─ Build preparation completed in {}.",
                 format_duration(total_start.elapsed())
             )
-            .cyan()
+            .cyan(),
         );
     }
     let compiler_start = Instant::now();
     match linux_b_64(&code, &config) {
-        Ok(()) => println!(
-            "{}",
+        Ok(()) => status(
+            &config,
             format!(
                 "└─ Build SUCCESS: Output generated for '{}' , output file is named '{}'",
                 config.path, config.out
             )
-            .green()
+            .green(),
         ),
         Err(e) => {
             eprintln!("{}", "┌[Error] Build FAILURE".red());
@@ -334,13 +523,13 @@ fn main_logic() {
             exit(1);
         }
     }
-    println!(
-        "{}",
+    status(
+        &config,
         format!(
             "└─ Compiler execution time: {} ms",
             compiler_start.elapsed().as_millis()
         )
-        .magenta()
+        .magenta(),
     );
 }
 
@@ -89,6 +89,8 @@ void write_str(int fd, const char *str) {
 void write_num(int fd, int num) {
     char buffer[20];
     int i = 0;
+    // Negating INT_MIN overflows, so the digits come from the unsigned magnitude.
+    unsigned int mag = num < 0 ? 0u - (unsigned int)num : (unsigned int)num;
     if (num == 0) {
         #if defined(_WIN32) || defined(_WIN64)
         fwrite("0", 1, 1, stdout);
@@ -105,11 +107,10 @@ void write_num(int fd, int num) {
         #if defined(__unix__) || defined(__unix) || defined(__linux__) || defined(__APPLE__) || defined(__MACH__)
         write(fd, "-", 1);
         #endif
-        num = -num;
     }
-    while (num > 0) {
-        buffer[i++] = (num % 10) + '0';
-        num /= 10;
+    while (mag > 0) {
+        buffer[i++] = (mag % 10) + '0';
+        mag /= 10;
     }
     for (int j = i - 1; j >= 0; j--) {
         #if defined(_WIN32) || defined(_WIN64)
@@ -187,26 +188,15 @@ void write_ptr(int fd, void *ptr) {
 }
 
 void write_float(int fd, double num) {
-    char buffer[50];
-    int int_part = (int)num;
-    double frac_part = num - int_part;
-
-    write_num(fd, int_part);
-    // Write decimal point.
-    #if defined(_WIN32) || defined(_WIN64)
-    fwrite(".", 1, 1, stdout);
-    #endif
-    #if defined(__unix__) || defined(__unix) || defined(__linux__) || defined(__APPLE__) || defined(__MACH__)
-    write(fd, ".", 1);
-    #endif
-
-    // Multiply the fractional part to get 6 decimal places.
-    frac_part *= 1000000;
-    int frac_int = (int)(frac_part + 0.5); // rounding
-    // Ensure leading zeros if necessary.
-    char frac_buffer[10];
-    snprintf(frac_buffer, sizeof(frac_buffer), "%06d", frac_int);
-    write_str(fd, frac_buffer);
+    // Room for the largest double, which has 309 digits before the point.
+    char buffer[400];
+    // Some C libraries write a NaN with its sign bit set as `-nan`.
+    if (num != num) {
+        write_str(fd, "nan");
+        return;
+    }
+    snprintf(buffer, sizeof(buffer), "%.6f", num);
+    write_str(fd, buffer);
 }
 
 void write_long(int fd, long int num) {
@@ -256,6 +246,10 @@ void nprintf(int fd, const char *format, ...) {
             char c = (char)va_arg(args, int);
             write_char(fd, c);
             ptr += 2;
+        } else if (*ptr == '%' && *(ptr + 1) == 'b') {
+            int b = va_arg(args, int);
+            write_str(fd, b ? "true" : "false");
+            ptr += 2;
         } else if (*ptr == '%' && *(ptr + 1) == 'x') {
             unsigned int hex = va_arg(args, unsigned int);
             write_hex(fd, hex);
@@ -465,6 +459,134 @@ void ninput(nstring *ns) {
     ns->str = buffer;
 }
 
+// Reports input that does not fit the requested type and stops the program.
+static void ninput_fail(const char *type, const nstring *ns, int line) {
+    fprintf(stderr, "[!] Runtime error at line %d: expected %s input, got \"%s\"\n", line, type, ns->str ? ns->str : "");
+    exit(1);
+}
+
+// Reads a line holding an integer between min and max.
+long long ninput_int(const char *type, long long min, long long max, int line) {
+    nstring ns = {NULL, 0};
+    ninput(&ns);
+    char *end = ns.str;
+    long long val = ns.str ? strtoll(ns.str, &end, 10) : 0;
+    while (end && (*end == ' ' || *end == '\t' || *end == '\r')) end++;
+    if (!ns.str || end == ns.str || *end != '\0' || val < min || val > max) {
+        ninput_fail(type, &ns, line);
+    }
+    free(ns.str);
+    return val;
+}
+
+// Reads a line holding a floating point number.
+double ninput_float(const char *type, int line) {
+    nstring ns = {NULL, 0};
+    ninput(&ns);
+    char *end = ns.str;
+    double val = ns.str ? strtod(ns.str, &end) : 0;
+    while (end && (*end == ' ' || *end == '\t' || *end == '\r')) end++;
+    if (!ns.str || end == ns.str || *end != '\0') {
+        ninput_fail(type, &ns, line);
+    }
+    free(ns.str);
+    return val;
+}
+
+// Reads a line holding exactly one character.
+char ninput_ch(int line) {
+    nstring ns = {NULL, 0};
+    ninput(&ns);
+    if (!ns.str || ns.len != 1) {
+        ninput_fail("ch", &ns, line);
+    }
+    char c = ns.str[0];
+    free(ns.str);
+    return c;
+}
+
+// Reads a line holding `true` or `false`.
+int ninput_bool(int line) {
+    nstring ns = {NULL, 0};
+    ninput(&ns);
+    int val = ns.str && strcmp(ns.str, "true") == 0;
+    if (!ns.str || (!val && strcmp(ns.str, "false") != 0)) {
+        ninput_fail("bool", &ns, line);
+    }
+    free(ns.str);
+    return val;
+}
+
+// A dynamic array of `size`-byte elements. Fixed-length arrays use it too and never grow.
+typedef struct {
+    void *data;
+    custom_size_t len;
+    custom_size_t cap;
+    custom_size_t size;
+} narray;
+
+// Makes an array of len elements copied from init, which may be NULL when len is 0.
+narray narray_new(custom_size_t size, custom_size_t len, const void *init) {
+    narray arr = {NULL, len, len, size};
+    if (len > 0) {
+        arr.data = malloc(size * len);
+        if (!arr.data) {
+            fprintf(stderr, "Memory allocation failed.\n");
+            exit(1);
+        }
+        memcpy(arr.data, init, size * len);
+    }
+    return arr;
+}
+
+// Divides two integers, stopping the program if the divisor is zero. Dividing the smallest
+// value by -1 wraps around.
+long long ndiv(long long a, long long b, int line) {
+    if (b == 0) {
+        fprintf(stderr, "[!] Runtime error at line %d: division by zero\n", line);
+        exit(1);
+    }
+    if (b == -1) {
+        return (long long)(0ULL - (unsigned long long)a);
+    }
+    return a / b;
+}
+
+// Returns the address of an element, stopping the program if the index is out of range.
+void *narray_at(const narray *arr, long long index, int line) {
+    if (index < 0 || (custom_size_t)index >= arr->len) {
+        fprintf(stderr, "[!] Runtime error at line %d: index %lld is out of range for an array of length %lu\n", line, index, arr->len);
+        exit(1);
+    }
+    return (char *)arr->data + (custom_size_t)index * arr->size;
+}
+
+// Appends a copy of val, doubling the capacity when the array is full.
+void narray_push(narray *arr, const void *val) {
+    if (arr->len == arr->cap) {
+        custom_size_t cap = arr->cap ? arr->cap * 2 : 4;
+        void *data = realloc(arr->data, cap * arr->size);
+        if (!data) {
+            fprintf(stderr, "Memory reallocation failed.\n");
+            exit(1);
+        }
+        arr->data = data;
+        arr->cap = cap;
+    }
+    memcpy((char *)arr->data + arr->len * arr->size, val, arr->size);
+    arr->len++;
+}
+
+// Removes the last element and returns its address, which stays valid until the next push.
+void *narray_pop(narray *arr, int line) {
+    if (arr->len == 0) {
+        fprintf(stderr, "[!] Runtime error at line %d: pop from an empty array\n", line);
+        exit(1);
+    }
+    arr->len--;
+    return (char *)arr->data + arr->len * arr->size;
+}
+
 "#;
 
 pub static NULIBCH: &'static str = r#"
@@ -479,8 +601,10 @@ pub static NULIBCH: &'static str = r#"
 #include <stdlib.h>
 #include <string.h>
 #include <stdint.h>
+#include <stdbool.h>
 
 typedef int8_t  i8;
+typedef int16_t i16;
 typedef int32_t i32;
 typedef int64_t i64;
 typedef float  f32;
@@ -505,6 +629,13 @@ typedef struct {
     custom_size_t len;
 } nstring;
 
+typedef struct {
+    void *data;
+    custom_size_t len;
+    custom_size_t cap;
+    custom_size_t size;
+} narray;
+
 typedef struct MemBlock {
     char* data;
     custom_size_t size;
@@ -542,6 +673,15 @@ nstring nstrcpy(const nstring *src);
 int nstr_cmp(const nstring *s1, const nstring *s2);
 nstring nstrcat(const nstring *s1, const nstring *s2);
 void ninput(nstring *nstring);
+long long ninput_int(const char *type, long long min, long long max, int line);
+double ninput_float(const char *type, int line);
+char ninput_ch(int line);
+int ninput_bool(int line);
+narray narray_new(custom_size_t size, custom_size_t len, const void *init);
+long long ndiv(long long a, long long b, int line);
+void *narray_at(const narray *arr, long long index, int line);
+void narray_push(narray *arr, const void *val);
+void *narray_pop(narray *arr, int line);
 int file_exists(nstring filename);
 
 #endif // NULIBC_H
@@ -1,5 +1,5 @@
 use crate::{
-    helpers::{Condition, Operand},
+    helpers::{Condition, Expr},
     parse_systems::{PrintTokTypes, Variables, AST},
 };
 use std::collections::HashSet;
@@ -7,25 +7,47 @@ use std::collections::HashSet;
 pub fn pass1(ast: &mut Vec<AST>) {
     let mut used_vars = HashSet::new();
 
-    #[inline(always)]
-    fn collect_usage_from_str(s: &str, used: &mut HashSet<String>) {
-        s.split(|c: char| !c.is_alphanumeric() && c != '_')
-            .filter(|token| !token.is_empty() && token.chars().any(|c| c.is_alphabetic()))
-            .for_each(|token| {
-                used.insert(token.to_string());
-            });
+    fn collect_usage_expr(expr: &Expr, used: &mut HashSet<String>) {
+        match expr {
+            Expr::Var(name) => {
+                used.insert(name.clone());
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| collect_usage_expr(arg, used)),
+            Expr::Unary(_, inner) | Expr::Group(inner) | Expr::Cast(inner, _) => {
+                collect_usage_expr(inner, used)
+            }
+            Expr::Binary(left, _, right, _) => {
+                collect_usage_expr(left, used);
+                collect_usage_expr(right, used);
+            }
+            Expr::Cond(cond) => collect_usage_condition(cond, used),
+            Expr::Array(items) => items.iter().for_each(|item| collect_usage_expr(item, used)),
+            Expr::Index(name, index, _) => {
+                used.insert(name.clone());
+                collect_usage_expr(index, used);
+            }
+            Expr::Len(name) | Expr::Pop(name, _) | Expr::Field(name, _) => {
+                used.insert(name.clone());
+            }
+            Expr::Struct(_, fields) => fields
+                .iter()
+                .for_each(|(_, value)| collect_usage_expr(value, used)),
+            Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Char(_) | Expr::Bool(_) => {}
+        }
     }
 
-    #[inline(always)]
     fn collect_usage_condition(cond: &Condition, used: &mut HashSet<String>) {
-        // Instead of converting the condition to a debug string, iterate directly over its child conditions.
-        for child in &cond.child_conditions {
-            if let Operand::Variable(var) = &child.left {
-                used.insert(var.clone());
+        match cond {
+            Condition::Compare(child) => {
+                collect_usage_expr(&child.left, used);
+                collect_usage_expr(&child.right, used);
             }
-            if let Operand::Variable(var) = &child.right {
-                used.insert(var.clone());
+            Condition::Value(value, _) => collect_usage_expr(value, used),
+            Condition::Logical(left, _, right) => {
+                collect_usage_condition(left, used);
+                collect_usage_condition(right, used);
             }
+            Condition::Not(inner) | Condition::Group(inner) => collect_usage_condition(inner, used),
         }
     }
 
@@ -37,43 +59,100 @@ pub fn pass1(ast: &mut Vec<AST>) {
                     text,
                 } => {
                     for ptok in text {
-                        if let PrintTokTypes::Var(v) = ptok {
-                            used.insert(v.clone());
+                        match ptok {
+                            // `%p.x` reads the struct `p`.
+                            PrintTokTypes::Var(v) => {
+                                let base = v.split('.').next().unwrap_or(v);
+                                used.insert(base.to_string());
+                            }
+                            PrintTokTypes::Call(_, args, _) => {
+                                args.iter().for_each(|arg| collect_usage_expr(arg, used));
+                            }
+                            PrintTokTypes::Expr(expr, _) => collect_usage_expr(expr, used),
+                            _ => {}
                         }
                     }
                 }
-                AST::VarAssign(var) => {
-                    if let Variables::MATH(_, expr) = var {
-                        collect_usage_from_str(expr, used);
-                    }
+                AST::VarAssign(Variables::MATH(_, expr), _)
+                | AST::Var(Variables::MATH(_, expr), ..) => collect_usage_expr(expr, used),
+                AST::Call(_, args, _) => {
+                    args.iter().for_each(|arg| collect_usage_expr(arg, used));
+                }
+                AST::Return(Some(expr), _) => collect_usage_expr(expr, used),
+                // Changing an array in place needs the array, even if it is never read.
+                AST::SetIndex {
+                    array,
+                    index,
+                    value,
+                    ..
+                } => {
+                    used.insert(array.clone());
+                    collect_usage_expr(index, used);
+                    collect_usage_expr(value, used);
+                }
+                AST::Push(array, value, _) => {
+                    used.insert(array.clone());
+                    collect_usage_expr(value, used);
+                }
+                AST::Pop(array, ..) => {
+                    used.insert(array.clone());
+                }
+                AST::SetField { var, value, .. } => {
+                    used.insert(var.clone());
+                    collect_usage_expr(value, used);
                 }
-                AST::While(body, cond) | AST::IF(body, cond) => {
+                AST::Input {
+                    var,
+                    declare: false,
+                    ..
+                } => {
+                    used.insert(var.clone());
+                }
+                AST::While(body, cond) | AST::IF(body, cond) | AST::ElseIf(body, cond) => {
                     collect_usage_condition(cond, used);
                     collect_usage_ast(body, used);
                 }
+                AST::Else(body) => collect_usage_ast(body, used),
+                AST::Func(def) => collect_usage_ast(&def.body, used),
+                AST::For {
+                    start,
+                    end,
+                    step,
+                    body,
+                    ..
+                } => {
+                    for bound in [start, end, step] {
+                        collect_usage_expr(bound, used);
+                    }
+                    collect_usage_ast(body, used);
+                }
                 _ => {}
             }
         }
     }
 
     collect_usage_ast(ast, &mut used_vars);
+    prune(ast, &used_vars);
+}
 
-    ast.retain(|node| match node {
-        AST::Var(var) => {
-            let name = match var {
-                Variables::MATH(n, _) => n.to_string(),
-                Variables::Char(n, _) => n.to_string(),
-                Variables::I8(n, _) => n.to_string(),
-                Variables::I16(n, _) => n.to_string(),
-                Variables::I32(n, _) => n.to_string(),
-                Variables::I64(n, _) => n.to_string(),
-                Variables::F32(n, _) => n.to_string(),
-                Variables::F64(n, _) => n.to_string(),
-                Variables::Str(n, _) => n.to_string(),
-                Variables::REF(n, _) => n.to_string(),
-            };
-            used_vars.contains(&name)
+/// Drops declarations whose variable is never read, in every block.
+fn prune(ast: &mut Vec<AST>, used_vars: &HashSet<String>) {
+    for node in ast.iter_mut() {
+        match node {
+            AST::While(body, _)
+            | AST::IF(body, _)
+            | AST::ElseIf(body, _)
+            | AST::Else(body)
+            | AST::For { body, .. } => prune(body, used_vars),
+            AST::Func(def) => prune(&mut def.body, used_vars),
+            _ => {}
         }
+    }
+
+    ast.retain(|node| match node {
+        // A value with side effects is kept even when unused.
+        AST::Var(Variables::MATH(_, expr), ..) if expr.has_effects() => true,
+        AST::Var(var, ..) => used_vars.contains(var.name()),
         _ => true,
     });
 }
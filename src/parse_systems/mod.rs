@@ -1,10 +1,19 @@
 use std::process::exit;
 
 use crate::{
-    err_system::{err_types::ErrTypes, error_msg_gen::gen_error_msg},
-    helpers::Condition,
+    err_system::{
+        diagnostic::{Diagnostic, MessageFormat, ReportOptions, Severity},
+        err_types::ErrTypes,
+        error_msg_gen::format_error_msg,
+        json_msg_gen::gen_json_msg,
+    },
+    helpers::{
+        symbol_table::{StructDef, SymbolTable},
+        Condition, Expr,
+    },
     optimisers::pass1::pass1,
-    tok_system::tokens::Token,
+    tok_system::tokens::{Span, Token},
+    type_system::checker::check,
 };
 use colored::Colorize;
 use parse1::p1;
@@ -15,11 +24,86 @@ pub enum AST {
         descriptor: FileDescriptors,
         text: Vec<PrintTokTypes>,
     },
-    Var(Variables),
+    /// A declaration with the type tag it was declared with, e.g. `i32` or `const;str`, and
+    /// the span of its value.
+    Var(Variables, &'static str, Span),
     While(Vec<AST>, Condition),
     IF(Vec<AST>, Condition),
-    VarAssign(Variables),
-    Input(Variables),
+    /// `else if` branch; always follows an `IF` or another `ElseIf`.
+    ElseIf(Vec<AST>, Condition),
+    /// `else` branch; always follows an `IF` or an `ElseIf`.
+    Else(Vec<AST>),
+    /// An assignment, with the span of the assigned value.
+    VarAssign(Variables, Span),
+    /// Reads a line from stdin into a variable, declaring it first when `declare` is set.
+    Input {
+        var: String,
+        typ: &'static str,
+        declare: bool,
+        /// Source line, reported when the input does not fit the type.
+        line: i32,
+    },
+    /// Counting loop over a numeric range.
+    For {
+        var: String,
+        /// Integer type of the loop variable.
+        typ: &'static str,
+        start: Expr,
+        end: Expr,
+        step: Expr,
+        /// Where the start, end and step were written. A step left out points at the range.
+        bounds: [Span; 3],
+        /// `..=` includes the end value, `..` stops before it.
+        inclusive: bool,
+        body: Vec<AST>,
+    },
+    Break,
+    Continue,
+    Func(FuncDef),
+    /// Return from the enclosing function, with an optional value expression and the span of
+    /// the value, or of `return` when there is none.
+    Return(Option<Expr>, Span),
+    /// A function call used as a statement: name, arguments and the span of the call.
+    Call(String, Vec<Expr>, Span),
+    /// An assignment to an array element, `xs[i] = value`.
+    SetIndex {
+        array: String,
+        index: Expr,
+        value: Expr,
+        /// Source line, reported when the index is out of range.
+        line: i32,
+        /// Where the element and the value were written.
+        spans: [Span; 2],
+    },
+    /// `push(xs, value)`: adds a value to the end of a growable array, with the span of the
+    /// statement.
+    Push(String, Expr, Span),
+    /// `pop(xs)` used as a statement, dropping the element it removes; with the source line,
+    /// reported when the array is empty, and the span of the statement.
+    Pop(String, i32, Span),
+    /// A struct type declaration, `struct Point { x: f64, y: f64 }`.
+    Struct(StructDef),
+    /// An assignment to a struct field, `p.x = value`.
+    SetField {
+        var: String,
+        field: String,
+        value: Expr,
+        /// Where the field and the value were written.
+        spans: [Span; 2],
+    },
+}
+
+#[derive(Debug)]
+/// A user-defined function.
+pub struct FuncDef {
+    pub name: String,
+    /// Parameter names and types, in declaration order.
+    pub params: Vec<(String, &'static str)>,
+    /// Return type, `None` for functions that return nothing.
+    pub ret: Option<&'static str>,
+    pub body: Vec<AST>,
+    /// Where the name was written.
+    pub span: Span,
 }
 
 #[derive(Debug)]
@@ -28,7 +112,14 @@ pub enum PrintTokTypes {
     Var(String),
     Newline,
     Word(String),
+    /// Text printed exactly as it is, such as a string literal with its escapes decoded.
+    Text(String),
     Space,
+    /// A function call whose result is printed: name, arguments and the span of the call.
+    Call(String, Vec<Expr>, Span),
+    /// An array element or length whose value is printed, `%xs[0]` or `%len(xs)`: the
+    /// expression and its span.
+    Expr(Expr, Span),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -56,10 +147,26 @@ pub enum Variables {
     Str(&'static str, String),
     F32(&'static str, f32),
     F64(&'static str, f64),
-    // First is the variable name, second is the reference variable name.
-    REF(&'static str, String),
-    // Variable holding mathematical operations.
-    MATH(String, String),
+    Bool(&'static str, bool),
+    // Variable holding a computed expression.
+    MATH(String, Expr),
+}
+
+impl Variables {
+    pub fn name(&self) -> &str {
+        match self {
+            Variables::MATH(name, _) => name,
+            Variables::I32(name, _) => name,
+            Variables::I8(name, _) => name,
+            Variables::I16(name, _) => name,
+            Variables::I64(name, _) => name,
+            Variables::Char(name, _) => name,
+            Variables::Str(name, _) => name,
+            Variables::F32(name, _) => name,
+            Variables::F64(name, _) => name,
+            Variables::Bool(name, _) => name,
+        }
+    }
 }
 
 pub mod parse1;
@@ -68,8 +175,10 @@ pub mod parse3;
 pub mod parse4;
 pub mod parse5;
 pub mod parse6;
+pub mod parse7;
 
-/// Parses tokens into an AST while collecting variables and reporting errors.
+/// Parses tokens into an AST while collecting variables and reporting errors and warnings.
+/// Errors end the process; warnings only do with `report.deny_warnings`.
 ///
 /// # Arguments
 /// - `tokens`: The tokens to parse.
@@ -77,40 +186,135 @@ pub mod parse6;
 /// - `file`: Name of the file being parsed.
 /// - `use_args_vars_err`: If `true`, the function uses the provided vectors without clearing them;
 ///   if `false`, it clears the provided vectors before parsing.
-/// - `collected_vars`: A mutable reference to a vector of variable tuples (name and type).
+/// - `collected_vars`: The symbol table; blocks push and pop their own scopes on it.
 /// - `collected_errors`: A mutable reference to a vector of errors.
+/// - `report`: How errors and warnings are printed, and whether warnings fail.
 ///
 /// # Returns
 /// A triple containing:
 /// - The parsed AST (owned),
-/// - A reference to the collected variables,
+/// - A reference to the symbol table,
 /// - A reference to the collected errors.
 pub fn parse<'a>(
-    tokens: &'a Vec<Token>,
+    tokens: &'a [Token],
     code: &String,
     file: &'static str,
     use_args_vars_err: bool,
-    collected_vars: &'a mut Vec<(String, &'static str)>,
+    collected_vars: &'a mut SymbolTable,
     collected_errors: &'a mut Vec<ErrTypes>,
-    line: i32,
-) -> (Vec<AST>, &'a Vec<(String, &'static str)>, &'a Vec<ErrTypes>) {
+    report: ReportOptions,
+) -> (Vec<AST>, &'a SymbolTable, &'a Vec<ErrTypes>) {
     if !use_args_vars_err {
         collected_vars.clear();
         collected_errors.clear();
     }
-    let tpcode = code.clone();
-    let mut line = line;
-    let mut ast = p1(tokens, &tpcode, collected_errors, collected_vars, &mut line);
+    let mut ast = parse_program(tokens, code, collected_vars, collected_errors);
     pass1(&mut ast);
 
     if !collected_errors.is_empty() {
-        println!("{}{}", "[!] Errors in file ".bold().red(), file);
-        for err in collected_errors.iter() {
-            println!("{}\n──+++++++++++++++──", gen_error_msg(*err, code));
-        }
-        eprintln!("{}", "[!]".bold().red());
+        let diagnostics: Vec<Diagnostic> = collected_errors
+            .iter()
+            .map(|err| Diagnostic::from(*err))
+            .collect();
+        print_diagnostics(&diagnostics, code, file, report.format);
         exit(1);
     }
 
+    // Warnings are only worth showing once the file parses; a dropped statement would leave
+    // its variables looking unused.
+    let warnings = collect_warnings(collected_vars, code);
+    if !warnings.is_empty() {
+        print_diagnostics(&warnings, code, file, report.format);
+        if report.deny_warnings {
+            if report.format == MessageFormat::Human {
+                eprintln!(
+                    "{}",
+                    "[!] Warnings are denied by --deny-warnings".bold().red()
+                );
+            }
+            exit(1);
+        }
+    }
+
     (ast, collected_vars, collected_errors)
 }
+
+/// The warnings for a parsed file in source order, leaving out those silenced by
+/// `# neit: allow(...)`.
+pub fn collect_warnings(collected_vars: &SymbolTable, code: &str) -> Vec<Diagnostic> {
+    let mut warnings = collected_vars.clone().finish();
+    warnings.sort_by_key(|warning| warning.span().start);
+    warnings
+        .into_iter()
+        .filter(|warning| !warning.allowed(code))
+        .map(Diagnostic::from)
+        .collect()
+}
+
+/// Prints diagnostics of one severity for a file. Human-readable errors go to stdout and warnings
+/// to stderr, so `neit run` output stays clean; JSON always goes to stdout.
+fn print_diagnostics(diagnostics: &[Diagnostic], code: &str, file: &str, format: MessageFormat) {
+    match format {
+        MessageFormat::Human => {
+            let warnings = diagnostics
+                .iter()
+                .all(|diag| diag.severity == Severity::Warning);
+            let banner = if warnings {
+                "[!] Warnings in file ".bold().yellow()
+            } else {
+                "[!] Errors in file ".bold().red()
+            };
+            let text: Vec<String> = diagnostics
+                .iter()
+                .map(|diag| format!("{}\n──+++++++++++++++──", format_error_msg(diag, code)))
+                .collect();
+            if warnings {
+                eprintln!("{}{}\n{}", banner, file, text.join("\n"));
+            } else {
+                println!("{}{}\n{}", banner, file, text.join("\n"));
+                eprintln!("{}", "[!]".bold().red());
+            }
+        }
+        MessageFormat::Json => {
+            for diag in diagnostics {
+                println!("{}", gen_json_msg(diag, file, code));
+            }
+        }
+    }
+}
+
+/// Parses a whole program and checks its types, leaving any errors in `collected_errors`, in
+/// source order. `collected_vars` may already hold names, as it does in the REPL.
+pub fn parse_program(
+    tokens: &[Token],
+    code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Vec<AST> {
+    let declared_before = collected_vars.clone();
+    // A block comment that is never closed hides the rest of the file; point at its `##`.
+    for comment in tokens.iter().flat_map(|tok| &tok.comments) {
+        if !comment.closed {
+            let span = Span {
+                end: comment.span.start + 2,
+                ..comment.span
+            };
+            collected_errors.push(ErrTypes::UnexpectedEndOfInput(span));
+        }
+    }
+    let ast = parse_tokens(tokens, code, collected_vars, collected_errors);
+    check(&ast, &declared_before, collected_errors);
+    collected_errors.sort_by_key(|err| err.span().start);
+    ast
+}
+
+/// Parses tokens into an AST, leaving any errors in `collected_errors` instead of reporting them.
+/// Block bodies are parsed with this, so their errors are reported once by the enclosing `parse`.
+pub fn parse_tokens(
+    tokens: &[Token],
+    code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Vec<AST> {
+    p1(tokens, code, collected_errors, collected_vars)
+}
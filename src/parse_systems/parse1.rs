@@ -1,5 +1,37 @@
 use super::{parse2::parse2, FileDescriptors, PrintTokTypes, AST};
-use crate::{err_system::err_types::ErrTypes, tok_system::tokens::Token};
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        expr_parser::{parse_typed_expr, resolve_names, string_value},
+        fn_calls::{collect_group, collect_paren_group, lookup_fn},
+        symbol_table::SymbolTable,
+        Expr,
+    },
+    tok_system::tokens::{span_of, Token, TokenKind},
+};
+
+/// Skips what a failed statement left of the line its error is on, along with any block opened
+/// there, so the rest of the statement is not parsed as new statements. Parsing picks up again
+/// at the next line.
+fn recover(tokens_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>, line: i32) {
+    while let Some(tok) = tokens_iter.next_if(|tok| tok.span.line <= line) {
+        match tok.kind {
+            TokenKind::EOL => break,
+            TokenKind::LCurly => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match tokens_iter.next().map(|tok| &tok.kind) {
+                        Some(TokenKind::LCurly) => depth += 1,
+                        Some(TokenKind::RCurly) => depth -= 1,
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
 
 #[inline(always)]
 #[allow(non_snake_case)]
@@ -7,14 +39,15 @@ pub fn p1(
     tokens: &[Token],
     code: &String,
     COLLECTED_ERRORS: &mut Vec<ErrTypes>,
-    COLLECTED_VARS: &mut Vec<(String, &'static str)>,
-    LINE: &mut i32,
+    COLLECTED_VARS: &mut SymbolTable,
 ) -> Vec<AST> {
     let mut ast = Vec::new();
     let mut tokens_iter = tokens.iter().peekable();
+    // Headers reported for having no `{`, whose `}` may still follow.
+    let mut headless = 0;
     while let Some(token) = tokens_iter.next() {
-        match token {
-            Token::Iden(cmd)
+        match &token.kind {
+            TokenKind::Iden(cmd)
                 if cmd == "print" || cmd == "println" || cmd == "eprint" || cmd == "eprintln" =>
             {
                 let fd = if cmd == "eprint" || cmd == "eprintln" {
@@ -26,37 +59,127 @@ pub fn p1(
                 let mut content = Vec::with_capacity(16);
                 let mut escape_mode = false;
                 let mut has_seen_delim_space = false;
-                loop {
-                    match tokens_iter.next() {
-                        Some(Token::EOL) | Some(Token::EOF) => {
-                            *LINE += 1;
+                let mut ended = false;
+                while let Some(tok) = tokens_iter.next() {
+                    match &tok.kind {
+                        TokenKind::EOL | TokenKind::EOF => {
+                            // The space before a trailing comment is not part of the text.
+                            if !tok.comments.is_empty() {
+                                while matches!(content.last(), Some(PrintTokTypes::Space)) {
+                                    content.pop();
+                                }
+                            }
                             if add_newline {
                                 content.push(PrintTokTypes::Newline);
                             }
                             ast.push(AST::Print {
                                 descriptor: fd,
-                                text: content,
+                                text: std::mem::take(&mut content),
                             });
+                            ended = true;
                             break;
                         }
-                        Some(Token::Space) => {
+                        TokenKind::Space => {
                             if !has_seen_delim_space {
                                 has_seen_delim_space = true;
                             } else {
                                 content.push(PrintTokTypes::Space);
                             }
                         }
-                        Some(Token::BackSlash) => {
+                        TokenKind::BackSlash => {
                             escape_mode = true;
                         }
-                        Some(Token::PercentSign) => {
-                            if let Some(Token::Iden(var_text)) = tokens_iter.next() {
-                                content.push(PrintTokTypes::Var(var_text.clone()));
+                        TokenKind::PercentSign => {
+                            if let Some(
+                                name @ Token {
+                                    kind: TokenKind::Iden(var_text),
+                                    ..
+                                },
+                            ) = tokens_iter.next()
+                            {
+                                if tokens_iter
+                                    .peek()
+                                    .is_some_and(|tok| tok.kind == TokenKind::LSmallBrac)
+                                    && lookup_fn(var_text, COLLECTED_VARS).is_some()
+                                {
+                                    let Some(group) = collect_paren_group(&mut tokens_iter) else {
+                                        COLLECTED_ERRORS
+                                            .push(ErrTypes::UnbalancedParentheses(name.span));
+                                        continue;
+                                    };
+                                    let mut call = vec![name.clone()];
+                                    call.extend(group);
+                                    let span = span_of(&call).unwrap_or(name.span);
+                                    if let Some((Expr::Call(name, args), _)) = parse_typed_expr(
+                                        &call,
+                                        COLLECTED_VARS,
+                                        COLLECTED_ERRORS,
+                                        name.span,
+                                    ) {
+                                        content.push(PrintTokTypes::Call(name, args, span));
+                                    }
+                                } else if let Some(open) =
+                                    tokens_iter.peek().and_then(|tok| match tok.kind {
+                                        TokenKind::LSmallBrac
+                                            if matches!(var_text.as_str(), "len" | "pop") =>
+                                        {
+                                            Some(TokenKind::LSmallBrac)
+                                        }
+                                        TokenKind::LSquareBrac => Some(TokenKind::LSquareBrac),
+                                        _ => None,
+                                    })
+                                {
+                                    // `%len(xs)` and `%xs[0]` print the value of an expression.
+                                    let close = if open == TokenKind::LSmallBrac {
+                                        TokenKind::RSmallBracket
+                                    } else {
+                                        TokenKind::RSquareBracket
+                                    };
+                                    let Some(group) = collect_group(&mut tokens_iter, open, close)
+                                    else {
+                                        COLLECTED_ERRORS
+                                            .push(ErrTypes::UnbalancedParentheses(name.span));
+                                        continue;
+                                    };
+                                    let mut value = vec![name.clone()];
+                                    value.extend(group);
+                                    let span = span_of(&value).unwrap_or(name.span);
+                                    if let Some((expr, _)) = parse_typed_expr(
+                                        &value,
+                                        COLLECTED_VARS,
+                                        COLLECTED_ERRORS,
+                                        name.span,
+                                    ) {
+                                        content.push(PrintTokTypes::Expr(expr, span));
+                                    }
+                                } else {
+                                    // A sentence can end right after a variable, as in `%x.`.
+                                    let path = var_text.trim_end_matches('.');
+                                    let src = [name.clone()];
+                                    // `%p.x` prints one field of a struct. A variable whose
+                                    // declaration failed was reported there, and is left out.
+                                    let expr = match path.split_once('.') {
+                                        Some((var, field))
+                                            if COLLECTED_VARS.lookup(var).is_some() =>
+                                        {
+                                            Expr::Field(var.to_string(), field.to_string())
+                                        }
+                                        _ => Expr::Var(path.to_string()),
+                                    };
+                                    if resolve_names(&expr, COLLECTED_VARS, COLLECTED_ERRORS, &src)
+                                    {
+                                        content.push(PrintTokTypes::Var(path.to_string()));
+                                        let dots = &var_text[path.len()..];
+                                        if !dots.is_empty() {
+                                            content.push(PrintTokTypes::Word(dots.to_string()));
+                                        }
+                                    }
+                                }
                             } else {
                                 content.push(PrintTokTypes::Word("%".to_string()));
                             }
                         }
-                        Some(Token::Iden(text)) => {
+                        TokenKind::Iden(text) => {
                             if escape_mode {
                                 if text == "n" {
                                     content.push(PrintTokTypes::Newline);
@@ -68,37 +191,63 @@ pub fn p1(
                                 content.push(PrintTokTypes::Word(text.clone()));
                             }
                         }
-                        Some(other) => {
+                        // A string keeps its quotes; one left open is plain text.
+                        TokenKind::Str { raw, closed: false } => {
+                            content.push(PrintTokTypes::Text(format!("\"{}", raw)));
+                        }
+                        TokenKind::Str { raw, .. } => match string_value(raw, true, tok.span) {
+                            Ok(text) => content.push(PrintTokTypes::Text(format!("\"{}\"", text))),
+                            Err(err) => COLLECTED_ERRORS.push(err),
+                        },
+                        other => {
                             let repr = match other {
-                                Token::Quote => "\"",
-                                Token::EqSign => "=",
-                                Token::ADDOP => "+",
-                                Token::SUBOP => "-",
-                                Token::DIVOP => "/",
-                                Token::MULTIOP => "*",
-                                Token::DoubleEqSign => "==",
-                                Token::LCurly => "{",
-                                Token::RCurly => "}",
-                                Token::And => "&",
-                                Token::Or => "|",
-                                Token::Not => "!",
-                                Token::GreaterThan => ">",
-                                Token::LessThan => "<",
-                                Token::LSmallBrac => "(",
-                                Token::RSmallBracket => ")",
+                                TokenKind::EqSign => "=",
+                                TokenKind::ADDOP => "+",
+                                TokenKind::SUBOP => "-",
+                                TokenKind::DIVOP => "/",
+                                TokenKind::MULTIOP => "*",
+                                TokenKind::DoubleEqSign => "==",
+                                TokenKind::LCurly => "{",
+                                TokenKind::RCurly => "}",
+                                TokenKind::And => "&",
+                                TokenKind::Or => "|",
+                                TokenKind::Not => "!",
+                                TokenKind::GreaterThan => ">",
+                                TokenKind::LessThan => "<",
+                                TokenKind::LSmallBrac => "(",
+                                TokenKind::RSmallBracket => ")",
+                                TokenKind::LSquareBrac => "[",
+                                TokenKind::RSquareBracket => "]",
+                                TokenKind::Comma => ",",
+                                TokenKind::Colon => ":",
                                 _ => "",
                             };
                             if !repr.is_empty() {
                                 content.push(PrintTokTypes::Word(repr.to_string()));
                             }
                         }
-                        None => break,
                     }
                 }
+                // The last line of a block has no line end before its `}`.
+                if !ended {
+                    if add_newline {
+                        content.push(PrintTokTypes::Newline);
+                    }
+                    ast.push(AST::Print {
+                        descriptor: fd,
+                        text: content,
+                    });
+                }
+            }
+            TokenKind::EOL | TokenKind::Space => {}
+            // Blocks take their own `}`, so one here closes nothing, unless it ends the body of
+            // a header that was reported for having no `{`.
+            TokenKind::RCurly if headless > 0 => headless -= 1,
+            TokenKind::RCurly => {
+                COLLECTED_ERRORS.push(ErrTypes::UnbalancedParentheses(token.span));
             }
-            Token::EOL => *LINE += 1,
-            Token::Space => {}
             _ => {
+                let errors_before = COLLECTED_ERRORS.len();
                 parse2(
                     token,
                     &mut tokens_iter,
@@ -106,8 +255,16 @@ pub fn p1(
                     code,
                     COLLECTED_VARS,
                     COLLECTED_ERRORS,
-                    LINE,
                 );
+                headless += COLLECTED_ERRORS[errors_before..]
+                    .iter()
+                    .filter(|err| matches!(err, ErrTypes::MissingBlock(_)))
+                    .count();
+                if COLLECTED_ERRORS.len() > errors_before {
+                    if let Some(err) = COLLECTED_ERRORS.last() {
+                        recover(&mut tokens_iter, err.span().line);
+                    }
+                }
             }
         }
     }
@@ -1,7 +1,224 @@
 use super::parse3::parse3;
 use super::AST;
+use crate::helpers::expr_parser::{collect_expr_tokens, parse_typed_expr};
+use crate::helpers::fn_calls::tok_text;
+use crate::helpers::symbol_table::{SymbolTable, UNKNOWN};
+use crate::helpers::Expr;
 use crate::parse_systems::Variables;
-use crate::{err_system::err_types::ErrTypes, tok_system::tokens::Token};
+use crate::{
+    err_system::err_types::ErrTypes,
+    tok_system::tokens::{span_of, Span, Token, TokenKind},
+    type_system::{
+        array_type, elem_type, fixed_len, is_array, is_value_type, value_type, EMPTY_ARRAY, VALUE,
+    },
+};
+
+/// Builds a variable of the given type from a numeric literal, or `None` if the value does not fit.
+pub fn typed_literal(name: &'static str, literal: &Expr, typ: &str) -> Option<Variables> {
+    match (literal, typ) {
+        (Expr::Int(val), "i8") => i8::try_from(*val).ok().map(|v| Variables::I8(name, v)),
+        (Expr::Int(val), "i16") => i16::try_from(*val).ok().map(|v| Variables::I16(name, v)),
+        (Expr::Int(val), "i32") => i32::try_from(*val).ok().map(|v| Variables::I32(name, v)),
+        (Expr::Int(val), "i64") => Some(Variables::I64(name, *val)),
+        (Expr::Float(val), "f32") => Some(Variables::F32(name, *val as f32)),
+        (Expr::Float(val), "f64") => Some(Variables::F64(name, *val)),
+        _ => None,
+    }
+}
+
+/// Builds a variable declared with a type. A literal of that type is kept as it is; any other
+/// value is computed, and the type checker makes sure it fits.
+pub fn annotated_var(name: &'static str, value: Expr, typ: &str) -> Variables {
+    match (value, typ) {
+        (Expr::Str(text), "str") => Variables::Str(name, text),
+        (Expr::Char(c), "ch") => Variables::Char(name, c),
+        (Expr::Bool(val), "bool") => Variables::Bool(name, val),
+        (value, typ) => match typed_literal(name, &value, typ) {
+            Some(var) => var,
+            None => Variables::MATH(name.to_string(), value),
+        },
+    }
+}
+
+/// The value of a variable declared without one.
+pub fn zero_value(name: &'static str, typ: &str, collected_vars: &SymbolTable) -> Variables {
+    annotated_var(name, zero_expr(typ, collected_vars), typ)
+}
+
+/// The zero value of a type: zero, empty text or `false`. A growable array starts empty and a
+/// fixed one holds zero values, as a struct's fields do; the remaining type is `ch`, which
+/// starts as the character with code 0.
+pub fn zero_expr(typ: &str, collected_vars: &SymbolTable) -> Expr {
+    if let Some(def) = collected_vars.struct_def(typ) {
+        let fields = def
+            .fields
+            .iter()
+            .map(|(field, typ)| (field.clone(), zero_expr(typ, collected_vars)))
+            .collect();
+        return Expr::Struct(def.name.to_string(), fields);
+    }
+    match typ {
+        "i8" | "i16" | "i32" | "i64" => Expr::Int(0),
+        "f32" | "f64" => Expr::Float(0.0),
+        "str" => Expr::Str(String::new()),
+        "bool" => Expr::Bool(false),
+        typ => match elem_type(typ) {
+            Some(elem) => Expr::Array(vec![
+                zero_expr(elem, collected_vars);
+                fixed_len(typ).unwrap_or(0)
+            ]),
+            None => Expr::Char('\0'),
+        },
+    }
+}
+
+/// Parses the optional `: type` after a declared name, giving the type and where it was
+/// written. The type may be a value type, an array or a declared struct.
+pub fn type_annotation(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<'_, Token>>,
+    collected_vars: &SymbolTable,
+) -> Result<Option<(&'static str, Span)>, ErrTypes> {
+    skip_spaces(token_iter);
+    let Some(colon) = token_iter.next_if(|tok| tok.kind == TokenKind::Colon) else {
+        return Ok(None);
+    };
+    skip_spaces(token_iter);
+    match token_iter.next() {
+        Some(Token {
+            kind: TokenKind::Iden(name),
+            span,
+            ..
+        }) => {
+            match value_type(name).or_else(|| collected_vars.struct_def(name).map(|def| def.name)) {
+                Some(typ) => Ok(Some((typ, *span))),
+                None => Err(ErrTypes::UnsupportedVarType(*span)),
+            }
+        }
+        Some(Token {
+            kind: TokenKind::LSquareBrac,
+            span,
+            ..
+        }) => array_annotation(token_iter, *span).map(Some),
+        tok => Err(ErrTypes::MissingValue(
+            tok.map_or(colon.span, |tok| tok.span),
+            None,
+        )),
+    }
+}
+
+/// Parses the rest of an array type after its `[`: `[i32]` for a growable array, or `[i32; 3]`
+/// for one of fixed length.
+fn array_annotation(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<'_, Token>>,
+    open: Span,
+) -> Result<(&'static str, Span), ErrTypes> {
+    let mut text = String::new();
+    let mut end = open;
+    let span = loop {
+        match token_iter.peek().copied() {
+            Some(tok) if tok.kind == TokenKind::RSquareBracket => {
+                token_iter.next();
+                break open.to(tok.span);
+            }
+            Some(tok) if !matches!(tok.kind, TokenKind::EOL | TokenKind::EOF) => {
+                text.push_str(tok_text(tok));
+                end = tok.span;
+                token_iter.next();
+            }
+            _ => return Err(ErrTypes::UnbalancedParentheses(open.to(end))),
+        }
+    };
+    // `;` is part of a word, so `i32; 3` arrives as `i32;` and `3`.
+    let (elem, len) = match text.split_once(';') {
+        Some((elem, len)) => (elem, Some(len.trim().parse::<usize>())),
+        None => (text.as_str(), None),
+    };
+    match (value_type(elem.trim()), len) {
+        (Some(elem), None) => Ok((array_type(elem, None), span)),
+        (Some(elem), Some(Ok(len))) => Ok((array_type(elem, Some(len)), span)),
+        _ => Err(ErrTypes::UnsupportedVarType(span)),
+    }
+}
+
+#[inline(always)]
+fn skip_spaces(token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>) {
+    while token_iter
+        .next_if(|tok| tok.kind == TokenKind::Space)
+        .is_some()
+    {}
+}
+
+/// Parses the `= value` part of a `may` declaration into the variable, its type tag and the
+/// span of the value. Without a declared type, a value with a type error gets `UNKNOWN`, and
+/// is left for the type checker to report.
+fn may_value(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<'_, Token>>,
+    var_name: &str,
+    name_span: Span,
+    declared: Option<&'static str>,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Option<(Variables, &'static str, Span)> {
+    skip_spaces(token_iter);
+
+    let eq_tok = token_iter.next();
+    let eq_span = eq_tok.map_or(name_span, |tok| tok.span);
+    match eq_tok.map(|tok| &tok.kind) {
+        Some(TokenKind::EqSign) => {}
+        Some(TokenKind::EOL) | Some(TokenKind::EOF) | None => {
+            collected_errors.push(ErrTypes::MissingValue(eq_span, None));
+            return None;
+        }
+        Some(_) => {
+            collected_errors.push(ErrTypes::UnexpectedToken(eq_span));
+            return None;
+        }
+    }
+
+    let value = collect_expr_tokens(token_iter);
+    let value_span = span_of(&value).unwrap_or(eq_span);
+    let (expr, typ) = parse_typed_expr(&value, collected_vars, collected_errors, eq_span)?;
+    let var_name_static = Box::leak(var_name.to_string().into_boxed_str());
+    if let Some(declared) = declared {
+        return Some((
+            annotated_var(var_name_static, expr, declared),
+            declared,
+            value_span,
+        ));
+    }
+    let (var, typ) = match expr {
+        Expr::Int(_) | Expr::Float(_) => (typed_literal(var_name_static, &expr, typ), typ),
+        Expr::Str(text) => (Some(Variables::Str(var_name_static, text)), "str"),
+        Expr::Char(c) => (Some(Variables::Char(var_name_static, c)), "ch"),
+        Expr::Bool(val) => (Some(Variables::Bool(var_name_static, val)), "bool"),
+        Expr::Cast(ref literal, cast) if matches!(**literal, Expr::Int(_) | Expr::Float(_)) => {
+            (typed_literal(var_name_static, literal, cast), cast)
+        }
+        // `[]` has no element type to give the variable.
+        Expr::Array(ref items) if items.is_empty() && typ == EMPTY_ARRAY => {
+            collected_errors.push(ErrTypes::MissingValue(value_span, None));
+            return None;
+        }
+        expr => {
+            // A call that returns nothing gives no value to hold; the checker reports it.
+            let typ = if is_value_type(typ)
+                || is_array(typ)
+                || collected_vars.struct_def(typ).is_some()
+            {
+                typ
+            } else {
+                UNKNOWN
+            };
+            (Some(Variables::MATH(var_name.to_string(), expr)), typ)
+        }
+    };
+    // Only a converted literal can fail to fit its type.
+    let Some(var) = var else {
+        collected_errors.push(ErrTypes::OutOfRange(value_span, typ));
+        return None;
+    };
+    Some((var, typ, value_span))
+}
 
 #[allow(unused, non_snake_case)]
 pub fn parse2(
@@ -9,366 +226,175 @@ pub fn parse2(
     token_iter: &mut std::iter::Peekable<std::slice::Iter<'_, Token>>,
     ast: &mut Vec<AST>,
     code: &String,
-    collected_vars: &mut Vec<(String, &'static str)>,
+    collected_vars: &mut SymbolTable,
     collected_errors: &mut Vec<ErrTypes>,
-    line: &mut i32,
 ) {
-    match token {
-        Token::EOL => *line += 1,
-        Token::Iden(ref id) if id == "may" => {
-            while let Some(Token::Space) = token_iter.peek() {
-                token_iter.next();
-            }
-            let var_name = match token_iter.next() {
-                Some(Token::Iden(name)) => name.clone(),
+    match &token.kind {
+        TokenKind::EOL => {}
+        TokenKind::Iden(id) if id == "may" => {
+            skip_spaces(token_iter);
+            let name_tok = token_iter.next();
+            let (mut var_name, mut name_span) = match name_tok {
+                Some(Token {
+                    kind: TokenKind::Iden(name),
+                    span,
+                    ..
+                }) => (name.clone(), *span),
                 _ => {
-                    collected_errors.push(ErrTypes::UnknownCMD(*line));
+                    let span = name_tok.map_or(token.span, |tok| tok.span);
+                    collected_errors.push(ErrTypes::UnknownCMD(span));
                     return;
                 }
             };
 
-            if collected_vars.iter().any(|(name, _)| name == &var_name) {
-                collected_errors.push(ErrTypes::VarAlreadyExists(*line));
-                return;
-            }
-
-            while let Some(Token::Space) = token_iter.peek() {
-                token_iter.next();
+            // `may shadow x = ...` declares a new `x` that hides one from an outer block.
+            let mut shadow = false;
+            if var_name == "shadow" {
+                skip_spaces(token_iter);
+                if let Some(Token {
+                    kind: TokenKind::Iden(name),
+                    span,
+                    ..
+                }) = token_iter.peek()
+                {
+                    var_name = name.clone();
+                    name_span = *span;
+                    shadow = true;
+                    token_iter.next();
+                }
             }
 
-            let mut found_eq = false;
-            let mut found_math_op = false;
-            let mut math_operator: Option<char> = None;
-            if let Some(first) = token_iter.next() {
-                match first {
-                    Token::EqSign => found_eq = true,
-                    Token::ADDOP => {
-                        found_math_op = true;
-                        math_operator = Some('+');
-                    }
-                    Token::SUBOP => {
-                        found_math_op = true;
-                        math_operator = Some('-');
-                    }
-                    Token::MULTIOP => {
-                        found_math_op = true;
-                        math_operator = Some('*');
-                    }
-                    Token::DIVOP => {
-                        found_math_op = true;
-                        math_operator = Some('/');
-                    }
-                    _ => {
-                        collected_errors.push(ErrTypes::UnknownCMD(*line));
-                        return;
-                    }
-                }
-            } else {
-                collected_errors.push(ErrTypes::UnknownCMD(*line));
+            let prev = collected_vars.declared_span(&var_name);
+            if collected_vars.declared_here(&var_name) {
+                collected_errors.push(ErrTypes::VarAlreadyExists(name_span, prev));
                 return;
             }
-
-            while let Some(Token::Space) = token_iter.peek() {
-                token_iter.next();
+            if !shadow && collected_vars.lookup(&var_name).is_some() {
+                collected_errors.push(ErrTypes::ImplicitShadow(name_span, prev));
+                return;
             }
 
-            if found_eq && !found_math_op {
-                if let Some(next_tok) = token_iter.peek() {
-                    match next_tok {
-                        Token::ADDOP => {
-                            found_math_op = true;
-                            math_operator = Some('+');
-                            token_iter.next();
-                        }
-                        Token::SUBOP => {
-                            found_math_op = true;
-                            math_operator = Some('-');
-                            token_iter.next();
-                        }
-                        Token::MULTIOP => {
-                            found_math_op = true;
-                            math_operator = Some('*');
-                            token_iter.next();
-                        }
-                        Token::DIVOP => {
-                            found_math_op = true;
-                            math_operator = Some('/');
-                            token_iter.next();
-                        }
-                        _ => {}
-                    }
-                }
-            } else if found_math_op && !found_eq {
-                while let Some(Token::Space) = token_iter.peek() {
-                    token_iter.next();
+            // `may count: i64 = ...` gives the type instead of taking the value's.
+            let declared = match type_annotation(token_iter, collected_vars) {
+                Ok(declared) => declared,
+                Err(err) => {
+                    collected_errors.push(err);
+                    collected_vars.declare_at(var_name, UNKNOWN, name_span);
+                    return;
                 }
-                if let Some(next_tok) = token_iter.next() {
-                    if let Token::EqSign = next_tok {
-                        found_eq = true;
-                    } else {
-                        collected_errors.push(ErrTypes::UnexpectedToken(*line));
-                        return;
-                    }
-                } else {
-                    collected_errors.push(ErrTypes::MissingValue(*line));
+            };
+            // Without a value, the variable starts at its type's zero value.
+            if let Some((typ, typ_span)) = declared {
+                skip_spaces(token_iter);
+                if matches!(
+                    token_iter.peek().map(|tok| &tok.kind),
+                    Some(TokenKind::EOL | TokenKind::EOF) | None
+                ) {
+                    let name = Box::leak(var_name.clone().into_boxed_str());
+                    let var = zero_value(name, typ, collected_vars);
+                    collected_vars.declare_unset(var_name, typ, name_span);
+                    ast.push(AST::Var(var, typ, typ_span));
                     return;
                 }
             }
 
-            while let Some(Token::Space) = token_iter.peek() {
-                token_iter.next();
+            match may_value(
+                token_iter,
+                &var_name,
+                name_span,
+                declared.map(|(typ, _)| typ),
+                collected_vars,
+                collected_errors,
+            ) {
+                Some((var, typ, value_span)) => {
+                    collected_vars.declare_at(var_name, typ, name_span);
+                    ast.push(AST::Var(var, typ, value_span));
+                }
+                // Declared anyway, so later uses are not reported as missing too.
+                None => collected_vars.declare_at(var_name, UNKNOWN, name_span),
             }
+        }
+        TokenKind::Iden(id) if id == "input" => {
+            skip_spaces(token_iter);
+            let name_tok = token_iter.next();
+            let (var_name, name_span) = match name_tok {
+                Some(Token {
+                    kind: TokenKind::Iden(name),
+                    span,
+                    ..
+                }) => (name.clone(), *span),
+                _ => {
+                    let span = name_tok.map_or(token.span, |tok| tok.span);
+                    collected_errors.push(ErrTypes::MissingValue(span, None));
+                    return;
+                }
+            };
 
-            let mut raw_value = String::new();
-            while let Some(tok) = token_iter.peek() {
-                match tok {
-                    Token::EOL | Token::EOF => break,
-                    Token::Space => {
-                        token_iter.next();
-                    }
-                    Token::Iden(val) => {
-                        raw_value.push_str(val);
-                        token_iter.next();
-                    }
-                    Token::ADDOP => {
-                        raw_value.push('+');
-                        token_iter.next();
-                    }
-                    Token::SUBOP => {
-                        raw_value.push('-');
-                        token_iter.next();
-                    }
-                    Token::MULTIOP => {
-                        raw_value.push('*');
-                        token_iter.next();
-                    }
-                    Token::DIVOP => {
-                        raw_value.push('/');
-                        token_iter.next();
-                    }
-                    Token::LSmallBrac => {
-                        raw_value.push('(');
-                        token_iter.next();
-                    }
-                    Token::RSmallBracket => {
-                        raw_value.push(')');
-                        token_iter.next();
-                    }
+            // Optional target type: `input age(i32)`.
+            let mut wanted = None;
+            if let Some(open) = token_iter.next_if(|tok| tok.kind == TokenKind::LSmallBrac) {
+                let typ_tok = token_iter.next();
+                let typ_span = typ_tok.map_or(open.span, |tok| tok.span);
+                let typ = match typ_tok.map(|tok| &tok.kind) {
+                    Some(TokenKind::Iden(typ)) => value_type(typ),
                     _ => {
-                        token_iter.next();
+                        collected_errors.push(ErrTypes::MissingValue(typ_span, None));
+                        return;
                     }
+                };
+                let Some(typ) = typ else {
+                    collected_errors.push(ErrTypes::UnsupportedVarType(typ_span));
+                    return;
+                };
+                if !token_iter
+                    .next()
+                    .is_some_and(|tok| tok.kind == TokenKind::RSmallBracket)
+                {
+                    collected_errors.push(ErrTypes::UnbalancedParentheses(open.span.to(typ_span)));
+                    return;
                 }
+                wanted = Some(typ);
             }
-
-            if raw_value.trim().is_empty() {
-                collected_errors.push(ErrTypes::MissingValue(*line));
+            if let Some(extra) = span_of(&collect_expr_tokens(token_iter)) {
+                collected_errors.push(ErrTypes::UnexpectedToken(extra));
                 return;
             }
 
-            if found_eq && found_math_op {
-                match math_operator {
-                    Some('+') => raw_value = format!("1+{}", raw_value),
-                    Some('-') => raw_value = format!("1-{}", raw_value),
-                    _ => {}
+            // An existing variable keeps its type; a new one is declared as a str unless typed.
+            let prev = collected_vars.declared_span(&var_name);
+            let (typ, declare) = match collected_vars.lookup(&var_name) {
+                Some(typ) if typ.starts_with("const;") => {
+                    collected_errors.push(ErrTypes::VarISConst(name_span, prev));
+                    return;
                 }
-
-                if raw_value.contains('+')
-                    || raw_value.contains('-')
-                    || raw_value.contains('*')
-                    || raw_value.contains('/')
-                {
-                    let mut expr_tokens = Vec::new();
-                    let mut current = String::new();
-                    for c in raw_value.chars() {
-                        if "+-*/".contains(c) {
-                            if !current.trim().is_empty() {
-                                expr_tokens.push(current.trim().to_string());
-                            }
-                            expr_tokens.push(c.to_string());
-                            current.clear();
-                        } else {
-                            current.push(c);
-                        }
-                    }
-                    if !current.trim().is_empty() {
-                        expr_tokens.push(current.trim().to_string());
-                    }
-                    if !expr_tokens.is_empty() {
-                        if let Some(first) = expr_tokens.first() {
-                            if "+-*/".contains(first.as_str()) {
-                                collected_errors.push(ErrTypes::MissingValue(*line));
-                                return;
-                            }
-                        }
-                        if let Some(last) = expr_tokens.last() {
-                            if "+-*/".contains(last.as_str()) {
-                                collected_errors.push(ErrTypes::MissingValue(*line));
-                                return;
-                            }
-                        }
-                        for i in 0..expr_tokens.len() - 1 {
-                            if "+-*/".contains(expr_tokens[i].as_str())
-                                && "+-*/".contains(expr_tokens[i + 1].as_str())
-                            {
-                                collected_errors.push(ErrTypes::InvalidMathUsage(*line));
-                                return;
-                            }
-                            if expr_tokens[i] == "/" && expr_tokens[i + 1].trim() == "0" {
-                                collected_errors.push(ErrTypes::DivisionByZero(*line));
-                                return;
-                            }
-                        }
-                    }
+                Some(typ) if is_array(typ) || collected_vars.struct_def(typ).is_some() => {
+                    collected_errors.push(ErrTypes::TypeMismatch(name_span, VALUE, typ));
+                    return;
                 }
-                let math_expr = raw_value.clone();
-                let var = Variables::MATH(var_name.clone(), math_expr);
-                if collected_vars.iter().all(|(name, _)| name != &var_name) {
-                    collected_vars.push((var_name.clone(), "f32"));
+                Some(typ) if !is_value_type(typ) => {
+                    collected_errors.push(ErrTypes::VarAlreadyExists(name_span, prev));
+                    return;
                 }
-                ast.push(AST::Var(var));
-            } else {
-                let (is_quoted, mut processed_value) =
-                    if raw_value.starts_with('\'') && raw_value.ends_with('\'') {
-                        (true, raw_value[1..raw_value.len() - 1].to_string())
-                    } else if raw_value.starts_with('"') && raw_value.ends_with('"') {
-                        (true, raw_value[1..raw_value.len() - 1].to_string())
-                    } else {
-                        (false, raw_value.clone())
-                    };
-
-                if raw_value.starts_with('"') && raw_value.ends_with('"') {
-                    let var_name_static = Box::leak(var_name.clone().into_boxed_str());
-                    collected_vars.push((var_name.clone(), "str"));
-                    ast.push(AST::Var(Variables::Str(var_name_static, processed_value)));
-                } else if is_quoted {
-                    if processed_value.chars().count() != 1 {
-                        collected_errors.push(ErrTypes::CharVarLen(*line));
+                Some(typ) => {
+                    if let Some(wanted) = wanted.filter(|wanted| *wanted != typ) {
+                        collected_errors.push(ErrTypes::TypeMismatch(name_span, typ, wanted));
                         return;
                     }
-                    let var_name_static = Box::leak(var_name.clone().into_boxed_str());
-                    collected_vars.push((var_name.clone(), "ch"));
-                    ast.push(AST::Var(Variables::Char(
-                        var_name_static,
-                        processed_value.chars().next().unwrap(),
-                    )));
-                } else {
-                    let mut forced_type = None;
-                    if processed_value.ends_with(')') {
-                        if let Some(start) = processed_value.rfind('(') {
-                            let potential = processed_value[start + 1..processed_value.len() - 1]
-                                .trim()
-                                .to_string();
-                            if potential == "i8"
-                                || potential == "i16"
-                                || potential == "i32"
-                                || potential == "i64"
-                                || potential == "f32"
-                                || potential == "f64"
-                            {
-                                forced_type = Some(potential);
-                                let trimmed_value = processed_value[..start].trim().to_string();
-                                if trimmed_value.is_empty() {
-                                    collected_errors.push(ErrTypes::MissingValue(*line));
-                                    return;
-                                }
-                                processed_value = trimmed_value;
-                            }
-                        }
-                    }
-                    let var_name_static = Box::leak(var_name.clone().into_boxed_str());
-                    if let Some(ty) = forced_type {
-                        match ty.as_str() {
-                            "i8" => {
-                                if let Ok(val) = processed_value.parse::<i8>() {
-                                    collected_vars.push((var_name.clone(), "i8"));
-                                    ast.push(AST::Var(Variables::I8(var_name_static, val)));
-                                } else {
-                                    collected_errors.push(ErrTypes::VarNotFound(*line));
-                                    return;
-                                }
-                            }
-                            "i16" => {
-                                if let Ok(val) = processed_value.parse::<i16>() {
-                                    collected_vars.push((var_name.clone(), "i16"));
-                                    ast.push(AST::Var(Variables::I16(var_name_static, val)));
-                                } else {
-                                    collected_errors.push(ErrTypes::VarNotFound(*line));
-                                    return;
-                                }
-                            }
-                            "i32" => {
-                                if let Ok(val) = processed_value.parse::<i32>() {
-                                    collected_vars.push((var_name.clone(), "i32"));
-                                    ast.push(AST::Var(Variables::I32(var_name_static, val)));
-                                } else {
-                                    collected_errors.push(ErrTypes::VarNotFound(*line));
-                                    return;
-                                }
-                            }
-                            "i64" => {
-                                if let Ok(val) = processed_value.parse::<i64>() {
-                                    collected_vars.push((var_name.clone(), "i64"));
-                                    ast.push(AST::Var(Variables::I64(var_name_static, val)));
-                                } else {
-                                    collected_errors.push(ErrTypes::VarNotFound(*line));
-                                    return;
-                                }
-                            }
-                            "f32" => {
-                                if let Ok(val) = processed_value.parse::<f32>() {
-                                    collected_vars.push((var_name.clone(), "f32"));
-                                    ast.push(AST::Var(Variables::F32(var_name_static, val)));
-                                } else {
-                                    collected_errors.push(ErrTypes::VarNotFound(*line));
-                                    return;
-                                }
-                            }
-                            "f64" => {
-                                if let Ok(val) = processed_value.parse::<f64>() {
-                                    collected_vars.push((var_name.clone(), "f64"));
-                                    ast.push(AST::Var(Variables::F64(var_name_static, val)));
-                                } else {
-                                    collected_errors.push(ErrTypes::VarNotFound(*line));
-                                    return;
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else {
-                        if let Ok(val) = processed_value.parse::<i8>() {
-                            collected_vars.push((var_name.clone(), "i8"));
-                            ast.push(AST::Var(Variables::I8(var_name_static, val)));
-                        } else if let Ok(val) = processed_value.parse::<i16>() {
-                            collected_vars.push((var_name.clone(), "i16"));
-                            ast.push(AST::Var(Variables::I16(var_name_static, val)));
-                        } else if let Ok(val) = processed_value.parse::<i32>() {
-                            collected_vars.push((var_name.clone(), "i32"));
-                            ast.push(AST::Var(Variables::I32(var_name_static, val)));
-                        } else if let Ok(val) = processed_value.parse::<i64>() {
-                            collected_vars.push((var_name.clone(), "i64"));
-                            ast.push(AST::Var(Variables::I64(var_name_static, val)));
-                        } else if let Ok(val) = processed_value.parse::<f32>() {
-                            collected_vars.push((var_name.clone(), "f32"));
-                            ast.push(AST::Var(Variables::F32(var_name_static, val)));
-                        } else if let Ok(val) = processed_value.parse::<f64>() {
-                            collected_vars.push((var_name.clone(), "f64"));
-                            ast.push(AST::Var(Variables::F64(var_name_static, val)));
-                        } else if collected_vars
-                            .iter()
-                            .any(|(name, _)| name == &processed_value)
-                        {
-                            collected_vars.push((var_name.clone(), "ref"));
-                            ast.push(AST::Var(Variables::REF(var_name_static, processed_value)));
-                        } else {
-                            collected_errors.push(ErrTypes::VarNotFound(*line));
-                            return;
-                        }
-                    }
+                    (typ, false)
                 }
+                None => (wanted.unwrap_or("str"), true),
+            };
+            if declare {
+                collected_vars.declare_at(var_name.clone(), typ, name_span);
+            } else {
+                collected_vars.mark_write(&var_name, name_span);
             }
-        }
-        Token::EOL => {
-            collected_errors.push(ErrTypes::UnexpectedToken(*line));
+            ast.push(AST::Input {
+                var: var_name,
+                typ,
+                declare,
+                line: name_span.line,
+            });
         }
         _ => {
             parse3(
@@ -378,7 +404,6 @@ pub fn parse2(
                 code,
                 collected_vars,
                 collected_errors,
-                line,
             );
         }
     }
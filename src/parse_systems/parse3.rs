@@ -1,111 +1,443 @@
 use super::{parse4::parse4, AST};
 use crate::{
-    err_system::err_types::ErrTypes, helpers::condition_parser::parse_condition,
-    parse_systems::parse, tok_system::tokens::Token,
+    err_system::{err_types::ErrTypes, warn_types::WarnTypes},
+    helpers::{
+        condition_parser::parse_condition,
+        expr_parser::{parse_expr, resolve_names},
+        symbol_table::{ScopeKind, SymbolTable},
+        ChildCond, CondToks, Condition, Expr,
+    },
+    parse_systems::parse_tokens,
+    run_system::interp::Interpreter,
+    tok_system::tokens::{span_of, Span, Token, TokenKind},
+    type_system::infer,
 };
 
+/// Collects condition tokens until the opening '{', returning them with the span of the '{'.
+/// A header line must end in '{' or be followed by a line starting with one; with
+/// `allow_newline` it may also go on inside parentheses or after `&&` or `||`. Otherwise it is
+/// reported as a missing block, so the lines after it are not taken for a condition.
 #[inline(always)]
-pub fn parse3(
-    token: &Token,
+fn collect_cond(
     token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
-    ast: &mut Vec<AST>,
-    code: &String,
-    collected_vars: &mut Vec<(String, &'static str)>,
     collected_errors: &mut Vec<ErrTypes>,
-    line: &mut i32,
-) {
-    match token {
-        Token::Iden(iden) if iden == "while" => {
-            let mut collected_code = String::new();
-            let mut cond = Vec::new();
-            let mut body = Vec::new();
-
-            // Collect condition tokens until '{'
-            while let Some(tok) = token_iter.next() {
-                if tok == &Token::LCurly {
-                    break;
-                } else if tok == &Token::EOL {
-                    collected_errors.push(ErrTypes::UnexpectedToken(*line));
-                    *line += 1;
-                } else {
-                    if let Some(line_str) = code.lines().nth((*line - 1) as usize) {
-                        collected_code.push_str(line_str);
-                        collected_code.push('\n');
-                    }
-                    cond.push(tok.clone());
+    allow_newline: bool,
+    keyword: Span,
+) -> Option<(Vec<Token>, Span)> {
+    let mut cond: Vec<Token> = Vec::new();
+    let mut depth = 0;
+    while let Some(tok) = token_iter.next() {
+        match tok.kind {
+            TokenKind::LCurly => return Some((cond, tok.span)),
+            TokenKind::EOL | TokenKind::EOF => {
+                let open = cond
+                    .iter()
+                    .rev()
+                    .find(|tok| tok.kind != TokenKind::Space)
+                    .is_some_and(|last| matches!(last.kind, TokenKind::And | TokenKind::Or));
+                // A '{' on a line of its own still opens the block.
+                let brace_next = token_iter
+                    .clone()
+                    .find(|tok| !matches!(tok.kind, TokenKind::Space | TokenKind::EOL))
+                    .is_some_and(|tok| tok.kind == TokenKind::LCurly);
+                if !(brace_next || allow_newline && (depth > 0 || open)) {
+                    let end = span_of(&cond).unwrap_or(keyword);
+                    collected_errors.push(ErrTypes::MissingBlock(keyword.to(end)));
+                    return None;
+                }
+            }
+            _ => {
+                match tok.kind {
+                    TokenKind::LSmallBrac => depth += 1,
+                    TokenKind::RSmallBracket => depth -= 1,
+                    _ => {}
                 }
+                cond.push(tok.clone());
             }
-            let parsed_cond = parse_condition(&cond, collected_errors, collected_vars, *line);
+        }
+    }
+    collected_errors.push(ErrTypes::MissingBlock(keyword));
+    None
+}
 
-            // Collect body tokens until '}'
-            while let Some(tok) = token_iter.next() {
-                if tok == &Token::RCurly {
-                    break;
-                } else if tok == &Token::EOL {
-                    *line += 1;
-                    body.push(tok.clone());
-                    continue;
-                } else {
-                    body.push(tok.clone());
+/// Collects body tokens until the '}' matching the already consumed '{' at `open`.
+#[inline(always)]
+fn collect_body(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    open: Span,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Vec<Token> {
+    let mut body = Vec::new();
+    let mut depth = 1;
+    for tok in token_iter.by_ref() {
+        match tok.kind {
+            TokenKind::LCurly => depth += 1,
+            TokenKind::RCurly => {
+                depth -= 1;
+                if depth == 0 {
+                    return body;
                 }
             }
-            let body_parsed = parse(
-                &body,
-                &collected_code,
-                "",
-                true,
+            _ => {}
+        }
+        body.push(tok.clone());
+    }
+    collected_errors.push(ErrTypes::UnexpectedEndOfInput(open));
+    body
+}
+
+/// Collects and parses a block body in the current scope, returning its AST.
+#[inline(always)]
+fn parse_body(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    open: Span,
+    code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Vec<AST> {
+    let body = collect_body(token_iter, open, collected_errors);
+    parse_tokens(&body, code, collected_vars, collected_errors)
+}
+
+/// Parses a block body in a scope of its own, so its variables end with the block.
+#[inline(always)]
+fn parse_block(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    open: Span,
+    code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    kind: ScopeKind,
+) -> Vec<AST> {
+    collected_vars.push_scope(kind);
+    let body = parse_body(token_iter, open, code, collected_vars, collected_errors);
+    collected_vars.pop_scope();
+    body
+}
+
+/// Stands in for an `if` condition that failed to parse, so a following `else` still has its
+/// `if`. It is never run: the file already has an error.
+fn failed_cond() -> Condition {
+    Condition::Compare(ChildCond {
+        left: Expr::Int(0),
+        operator: CondToks::Equal,
+        right: Expr::Int(0),
+        span: Span::default(),
+    })
+}
+
+// Start, end and step tokens of a range, and whether it includes its end.
+type RangeTokens = (Vec<Token>, Vec<Token>, Vec<Token>, bool);
+
+/// Splits a `for` header (`0..10`, `0..=n step 2`) into its parts.
+fn split_range(header: &[Token]) -> Option<RangeTokens> {
+    // The lexer keeps `..` inside words (`0..10`), so the word holding it is split in two.
+    let at = header
+        .iter()
+        .position(|tok| matches!(&tok.kind, TokenKind::Iden(word) if word.contains("..")))?;
+    let TokenKind::Iden(word) = &header[at].kind else {
+        return None;
+    };
+    let (before, after) = word.split_once("..")?;
+    // Each half of the word keeps the part of the span it was written at.
+    let piece = |from: usize, text: &str| {
+        let span = header[at].span;
+        Token::new(
+            TokenKind::Iden(text.to_string()),
+            Span {
+                start: span.start + from,
+                end: span.start + from + text.len(),
+                col: span.col + word[..from].chars().count() as i32,
+                ..span
+            },
+        )
+    };
+    let mut start = header[..at].to_vec();
+    if !before.is_empty() {
+        start.push(piece(0, before));
+    }
+    let mut rest = Vec::new();
+    if !after.is_empty() {
+        rest.push(piece(before.len() + 2, after));
+    }
+    rest.extend_from_slice(&header[at + 1..]);
+
+    // `..=` reaches the lexer as a word ending in `..` followed by `=`.
+    let inclusive =
+        after.is_empty() && rest.first().map(|tok| &tok.kind) == Some(&TokenKind::EqSign);
+    if inclusive {
+        rest.remove(0);
+    }
+    let (end, step) = match rest
+        .iter()
+        .position(|tok| matches!(&tok.kind, TokenKind::Iden(kw) if kw == "step"))
+    {
+        Some(at) => (rest[..at].to_vec(), rest[at + 1..].to_vec()),
+        None => {
+            let span = span_of(header).unwrap_or_default();
+            (
+                rest,
+                vec![Token::new(TokenKind::Iden("1".to_string()), span)],
+            )
+        }
+    };
+    Some((start, end, step, inclusive))
+}
+
+/// Whether a condition is made of literals only and evaluates to false, so its block never runs.
+fn always_false(cond: &Condition) -> bool {
+    fn literal(expr: &Expr) -> bool {
+        match expr {
+            Expr::Var(_)
+            | Expr::Call(..)
+            | Expr::Array(_)
+            | Expr::Index(..)
+            | Expr::Len(_)
+            | Expr::Pop(..)
+            | Expr::Struct(..)
+            | Expr::Field(..) => false,
+            Expr::Unary(_, inner) | Expr::Group(inner) | Expr::Cast(inner, _) => literal(inner),
+            Expr::Binary(left, _, right, _) => literal(left) && literal(right),
+            Expr::Cond(cond) => constant(cond),
+            Expr::Int(_) | Expr::Float(_) | Expr::Str(_) | Expr::Char(_) | Expr::Bool(_) => true,
+        }
+    }
+    fn constant(cond: &Condition) -> bool {
+        match cond {
+            Condition::Compare(child) => literal(&child.left) && literal(&child.right),
+            Condition::Value(value, _) => literal(value),
+            Condition::Logical(left, _, right) => constant(left) && constant(right),
+            Condition::Not(inner) | Condition::Group(inner) => constant(inner),
+        }
+    }
+    constant(cond) && matches!(Interpreter::default().eval_cond(cond), Ok(false))
+}
+
+/// Warns about a block that can never run because its condition is always false.
+fn check_reachable(cond: Option<&Condition>, tokens: &[Token], collected_vars: &mut SymbolTable) {
+    if let (Some(cond), Some(span)) = (cond, span_of(tokens)) {
+        if always_false(cond) {
+            collected_vars.warn(WarnTypes::UnreachableCode(span));
+        }
+    }
+}
+
+/// Whether a `for` loop counts down. This is decided from the step as written, so a step held in
+/// a variable always counts up.
+pub fn counts_down(step: &Expr) -> bool {
+    matches!(step, Expr::Int(val) if *val < 0) || matches!(step, Expr::Unary(..))
+}
+
+/// Picks the loop variable type from the range bounds: `i64` if any bound is one, `i32`
+/// otherwise. Bounds that are not integers are left for the type checker.
+fn range_type(bounds: [&Expr; 3], collected_vars: &SymbolTable) -> &'static str {
+    if bounds
+        .into_iter()
+        .any(|bound| infer(bound, collected_vars) == "i64")
+    {
+        "i64"
+    } else {
+        "i32"
+    }
+}
+
+#[inline(always)]
+pub fn parse3(
+    token: &Token,
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    ast: &mut Vec<AST>,
+    code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) {
+    match &token.kind {
+        TokenKind::Iden(iden) if iden == "while" => {
+            let Some((cond, open)) = collect_cond(token_iter, collected_errors, false, token.span)
+            else {
+                return;
+            };
+            let parsed_cond = parse_condition(&cond, collected_errors, collected_vars, token.span);
+            check_reachable(parsed_cond.as_ref(), &cond, collected_vars);
+            let body = parse_block(
+                token_iter,
+                open,
+                code,
                 collected_vars,
                 collected_errors,
-                *line,
+                ScopeKind::Loop,
             );
-            ast.push(AST::While(body_parsed.0, parsed_cond));
+            if let Some(cond) = parsed_cond {
+                ast.push(AST::While(body, cond));
+            }
         }
-        Token::Iden(iden) if iden == "if" => {
-            let mut collected_code = String::new();
-            let mut cond = Vec::new();
-            let mut body = Vec::new();
-
-            // Collect condition tokens until '{'
-            while let Some(tok) = token_iter.next() {
-                if tok == &Token::LCurly {
-                    break;
-                } else if tok == &Token::EOL {
-                    *line += 1;
-                    continue;
-                } else {
-                    if let Some(line_str) = code.lines().nth((*line - 1) as usize) {
-                        collected_code.push_str(line_str);
-                        collected_code.push('\n');
-                    }
-                    cond.push(tok.clone());
+        TokenKind::Iden(iden) if iden == "for" => {
+            while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
+                token_iter.next();
+            }
+            let name_tok = token_iter.next();
+            let name_span = name_tok.map_or(token.span, |tok| tok.span);
+            let var_name = match name_tok.map(|tok| &tok.kind) {
+                Some(TokenKind::Iden(name)) => name.clone(),
+                _ => {
+                    collected_errors.push(ErrTypes::UnknownCMD(name_span));
+                    return;
                 }
+            };
+            while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
+                token_iter.next();
+            }
+            let in_tok = token_iter.next();
+            if !matches!(in_tok.map(|tok| &tok.kind), Some(TokenKind::Iden(kw)) if kw == "in") {
+                let span = in_tok.map_or(name_span, |tok| tok.span);
+                collected_errors.push(ErrTypes::UnexpectedToken(span));
+                return;
             }
-            let parsed_cond = parse_condition(&cond, collected_errors, collected_vars, *line);
+            let Some((header, open)) =
+                collect_cond(token_iter, collected_errors, false, token.span)
+            else {
+                return;
+            };
+            let header_span = span_of(&header).unwrap_or(token.span);
 
-            // Collect body tokens until '}'
-            while let Some(tok) = token_iter.next() {
-                if tok == &Token::RCurly {
-                    break;
-                } else if tok == &Token::EOL {
-                    *line += 1;
-                    body.push(tok.clone());
-                    continue;
-                } else {
-                    body.push(tok.clone());
+            let checked = match split_range(&header) {
+                Some((start, end, step, inclusive)) => {
+                    let bounds = [&start, &end, &step]
+                        .map(|bound| parse_expr(bound, collected_errors, header_span));
+                    let spans =
+                        [&start, &end, &step].map(|src| span_of(src).unwrap_or(header_span));
+                    match bounds {
+                        [Some(start_expr), Some(end_expr), Some(step_expr)] => {
+                            let resolved = [
+                                (&start_expr, &start),
+                                (&end_expr, &end),
+                                (&step_expr, &step),
+                            ]
+                            .into_iter()
+                            .all(|(bound, src)| {
+                                resolve_names(bound, collected_vars, collected_errors, src)
+                            });
+                            let typ =
+                                range_type([&start_expr, &end_expr, &step_expr], collected_vars);
+                            if step_expr == Expr::Int(0) {
+                                let span = span_of(&step).unwrap_or(header_span);
+                                collected_errors.push(ErrTypes::InvalidNumberFormat(span));
+                                None
+                            } else if collected_vars.lookup(&var_name).is_some() {
+                                let prev = collected_vars.declared_span(&var_name);
+                                collected_errors.push(ErrTypes::VarAlreadyExists(name_span, prev));
+                                None
+                            } else {
+                                resolved.then_some((
+                                    start_expr, end_expr, step_expr, spans, inclusive, typ,
+                                ))
+                            }
+                        }
+                        _ => None,
+                    }
                 }
+                None => {
+                    collected_errors.push(ErrTypes::SyntaxError(header_span));
+                    None
+                }
+            };
+            let Some((start, end, step, bounds, inclusive, typ)) = checked else {
+                // Skip the body so its statements are not parsed outside the loop.
+                collect_body(token_iter, open, collected_errors);
+                return;
+            };
+
+            // The loop variable only exists inside the body.
+            collected_vars.push_scope(ScopeKind::Loop);
+            collected_vars.declare_at(var_name.clone(), typ, name_span);
+            let body = parse_body(token_iter, open, code, collected_vars, collected_errors);
+            collected_vars.pop_scope();
+            ast.push(AST::For {
+                var: var_name,
+                typ,
+                start,
+                end,
+                step,
+                bounds,
+                inclusive,
+                body,
+            });
+        }
+        TokenKind::Iden(iden) if iden == "break" || iden == "continue" => {
+            if !collected_vars.in_loop() {
+                collected_errors.push(ErrTypes::BreakOutsideLoop(token.span));
+                return;
             }
-            let body_parsed = parse(
-                &body,
-                &collected_code,
-                "",
-                true,
+            collected_vars.forget_writes();
+            ast.push(if iden == "break" {
+                AST::Break
+            } else {
+                AST::Continue
+            });
+        }
+        TokenKind::Iden(iden) if iden == "if" => {
+            let Some((cond, open)) = collect_cond(token_iter, collected_errors, true, token.span)
+            else {
+                return;
+            };
+            let parsed_cond = parse_condition(&cond, collected_errors, collected_vars, token.span);
+            check_reachable(parsed_cond.as_ref(), &cond, collected_vars);
+            let body = parse_block(
+                token_iter,
+                open,
+                code,
                 collected_vars,
                 collected_errors,
-                *line,
+                ScopeKind::Block,
             );
-            ast.push(AST::IF(body_parsed.0, parsed_cond));
+            ast.push(AST::IF(body, parsed_cond.unwrap_or_else(failed_cond)));
+        }
+        TokenKind::Iden(iden) if iden == "else" => {
+            // `else` must directly follow an `if` or `else if` block.
+            if !matches!(ast.last(), Some(AST::IF(..)) | Some(AST::ElseIf(..))) {
+                collected_errors.push(ErrTypes::ElseWithoutIf(token.span));
+                return;
+            }
+            while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
+                token_iter.next();
+            }
+            let next = token_iter.next();
+            match next.map(|tok| &tok.kind) {
+                Some(TokenKind::Iden(iden)) if iden == "if" => {
+                    let Some((cond, open)) =
+                        collect_cond(token_iter, collected_errors, true, token.span)
+                    else {
+                        return;
+                    };
+                    let parsed_cond =
+                        parse_condition(&cond, collected_errors, collected_vars, token.span);
+                    check_reachable(parsed_cond.as_ref(), &cond, collected_vars);
+                    let body = parse_block(
+                        token_iter,
+                        open,
+                        code,
+                        collected_vars,
+                        collected_errors,
+                        ScopeKind::Block,
+                    );
+                    ast.push(AST::ElseIf(body, parsed_cond.unwrap_or_else(failed_cond)));
+                }
+                Some(TokenKind::LCurly) => {
+                    let body = parse_block(
+                        token_iter,
+                        next.map_or(token.span, |tok| tok.span),
+                        code,
+                        collected_vars,
+                        collected_errors,
+                        ScopeKind::Block,
+                    );
+                    ast.push(AST::Else(body));
+                }
+                _ => collected_errors.push(ErrTypes::UnexpectedToken(
+                    next.map_or(token.span, |tok| tok.span),
+                )),
+            }
         }
-        Token::EOL => *line += 1,
+        TokenKind::EOL => {}
         _ => {
             parse4(
                 token,
@@ -114,7 +446,6 @@ pub fn parse3(
                 code,
                 collected_vars,
                 collected_errors,
-                line,
             );
         }
     }
@@ -1,5 +1,24 @@
-use super::{parse5::parse5, AST};
-use crate::{err_system::err_types::ErrTypes, parse_systems::Variables, tok_system::tokens::Token};
+use super::{parse5::parse5, parse6::parse6, parse7::parse7, AST};
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        expr_parser::{
+            collect_expr_tokens, parse_expr, parse_typed_expr, resolve_names, resolve_var,
+        },
+        fn_calls::lookup_fn,
+        suggest::closest,
+        symbol_table::{SymbolTable, UNKNOWN},
+        BinOp, Expr,
+    },
+    parse_systems::Variables,
+    tok_system::tokens::{span_of, Token, TokenKind},
+};
+
+// Words that start a statement.
+const KEYWORDS: [&str; 18] = [
+    "print", "println", "eprint", "eprintln", "may", "const", "input", "while", "for", "if",
+    "else", "fn", "return", "break", "continue", "push", "pop", "struct",
+];
 
 #[inline(always)]
 pub fn parse4(
@@ -7,12 +26,11 @@ pub fn parse4(
     token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
     ast: &mut Vec<AST>,
     _code: &String,
-    collected_vars: &mut Vec<(String, &'static str)>,
+    collected_vars: &mut SymbolTable,
     collected_errors: &mut Vec<ErrTypes>,
-    line: &mut i32,
 ) {
-    let var_name: String = match token {
-        Token::Iden(name) => {
+    let var_name: String = match &token.kind {
+        TokenKind::Iden(name) => {
             if name == "const" || name == "global" {
                 parse5(
                     token,
@@ -21,9 +39,28 @@ pub fn parse4(
                     _code,
                     collected_vars,
                     collected_errors,
-                    line,
                 );
 
+                return;
+            } else if name == "fn" || name == "return" {
+                parse6(
+                    token,
+                    token_iter,
+                    ast,
+                    _code,
+                    collected_vars,
+                    collected_errors,
+                );
+                return;
+            } else if name == "struct" {
+                parse7(
+                    token,
+                    token_iter,
+                    ast,
+                    _code,
+                    collected_vars,
+                    collected_errors,
+                );
                 return;
             } else {
                 name.clone()
@@ -34,360 +71,207 @@ pub fn parse4(
         }
     };
 
-    // Check if variable exists and is not const.
-    let var_info_option = collected_vars
-        .iter()
-        .find(|(name, _)| name == &var_name)
-        .cloned();
-    if var_info_option.is_none() {
-        collected_errors.push(ErrTypes::VarNotFound(*line));
+    // `push(xs, value)` and `pop(xs)` change the length of a growable array.
+    if matches!(var_name.as_str(), "push" | "pop")
+        && token_iter
+            .peek()
+            .is_some_and(|tok| tok.kind == TokenKind::LSmallBrac)
+    {
+        let mut call = vec![token.clone()];
+        call.extend(collect_expr_tokens(token_iter));
+        let span = span_of(&call).unwrap_or(token.span);
+        match parse_expr(&call, collected_errors, token.span) {
+            Some(Expr::Call(_, args)) => match args.as_slice() {
+                [Expr::Var(array), value] => {
+                    if resolve_var(array, collected_vars, collected_errors, &call)
+                        && resolve_names(value, collected_vars, collected_errors, &call)
+                    {
+                        ast.push(AST::Push(array.clone(), value.clone(), span));
+                    }
+                }
+                [_, _] => collected_errors.push(ErrTypes::UnexpectedToken(span)),
+                _ => collected_errors.push(ErrTypes::ArgCountMismatch(token.span)),
+            },
+            Some(Expr::Pop(array, line)) => {
+                if !resolve_var(&array, collected_vars, collected_errors, &call) {
+                    return;
+                }
+                ast.push(AST::Pop(array, line, span));
+            }
+            Some(_) => collected_errors.push(ErrTypes::UnexpectedToken(span)),
+            None => {}
+        }
+        return;
+    }
+
+    // A call used as a statement, e.g. `greet("bob")`.
+    if lookup_fn(&var_name, collected_vars).is_some() {
+        let mut call = vec![token.clone()];
+        call.extend(collect_expr_tokens(token_iter));
+        let span = span_of(&call).unwrap_or(token.span);
+        match parse_typed_expr(&call, collected_vars, collected_errors, token.span) {
+            Some((Expr::Call(name, args), _)) => ast.push(AST::Call(name, args, span)),
+            Some(_) => collected_errors.push(ErrTypes::UnexpectedToken(span)),
+            None => {}
+        }
+        return;
+    }
+
+    // `p.x = value` assigns one field of a struct; the struct variable is what must exist.
+    let field = var_name.split_once('.').map(|(var, field)| {
+        let field = Expr::Field(var.to_string(), field.to_string());
+        (var.to_string(), field)
+    });
+    let base = field.as_ref().map_or(&var_name, |(var, _)| var);
+
+    // Check if variable exists and is not const. An unknown word may also be a misspelled
+    // statement, so keywords are suggested too.
+    let Some(var_type) = collected_vars.lookup(base) else {
+        let similar = collected_vars
+            .similar(base, |_| true)
+            .or_else(|| closest(base, KEYWORDS.into_iter()));
+        collected_errors.push(ErrTypes::VarNotFound(token.span, similar));
+        return;
+    };
+    if var_type == UNKNOWN {
+        // Its declaration failed and was reported; the rest of the statement is skipped.
+        collect_expr_tokens(token_iter);
         return;
     }
-    let (_, var_type) = var_info_option.unwrap();
     if var_type.contains("const;") {
-        collected_errors.push(ErrTypes::VarISConst(*line));
+        let decl = collected_vars.declared_span(base);
+        collected_errors.push(ErrTypes::VarISConst(token.span, decl));
         return;
     }
 
+    // `xs[i] = value` assigns one element of an array.
+    let mut target = vec![token.clone()];
+    if token_iter
+        .peek()
+        .is_some_and(|tok| tok.kind == TokenKind::LSquareBrac)
+    {
+        let mut depth = 0;
+        while let Some(tok) =
+            token_iter.next_if(|tok| !matches!(tok.kind, TokenKind::EOL | TokenKind::EOF))
+        {
+            target.push(tok.clone());
+            match tok.kind {
+                TokenKind::LSquareBrac => depth += 1,
+                TokenKind::RSquareBracket => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+    let target_span = span_of(&target).unwrap_or(token.span);
+    let element = if let Some((_, field)) = field {
+        if !resolve_names(&field, collected_vars, collected_errors, &target) {
+            collect_expr_tokens(token_iter);
+            return;
+        }
+        Some(field)
+    } else if target.len() > 1 {
+        match parse_expr(&target, collected_errors, token.span) {
+            Some(element @ Expr::Index(..)) => Some(element),
+            Some(_) => {
+                collected_errors.push(ErrTypes::UnexpectedToken(target_span));
+                return;
+            }
+            None => return,
+        }
+    } else {
+        None
+    };
+
     // Skip spaces.
-    while matches!(token_iter.peek(), Some(Token::Space)) {
+    while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
         token_iter.next();
     }
 
     // Check for assignment or compound operator.
-    let mut compound_operator: Option<char> = None;
+    let mut compound_operator: Option<BinOp> = None;
+    let op_span;
     if let Some(op_token) = token_iter.next() {
-        match op_token {
-            Token::EqSign => {}
-            Token::ADDOP | Token::SUBOP | Token::MULTIOP | Token::DIVOP => {
-                compound_operator = match op_token {
-                    Token::ADDOP => Some('+'),
-                    Token::SUBOP => Some('-'),
-                    Token::MULTIOP => Some('*'),
-                    Token::DIVOP => Some('/'),
+        op_span = op_token.span;
+        match op_token.kind {
+            TokenKind::EqSign => {}
+            TokenKind::ADDOP | TokenKind::SUBOP | TokenKind::MULTIOP | TokenKind::DIVOP => {
+                compound_operator = match op_token.kind {
+                    TokenKind::ADDOP => Some(BinOp::Add),
+                    TokenKind::SUBOP => Some(BinOp::Sub),
+                    TokenKind::MULTIOP => Some(BinOp::Mul),
+                    TokenKind::DIVOP => Some(BinOp::Div),
                     _ => None,
                 };
-                while matches!(token_iter.peek(), Some(Token::Space)) {
+                while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
                     token_iter.next();
                 }
-                if let Some(Token::EqSign) = token_iter.next() {
-                } else {
-                    collected_errors.push(ErrTypes::MissingOperator(*line));
+                if !token_iter
+                    .next()
+                    .is_some_and(|tok| tok.kind == TokenKind::EqSign)
+                {
+                    collected_errors.push(ErrTypes::MissingOperator(op_span));
                     return;
                 }
             }
             _ => {
-                collected_errors.push(ErrTypes::UnexpectedToken(*line));
+                collected_errors.push(ErrTypes::UnexpectedToken(op_span));
                 return;
             }
         }
     } else {
-        collected_errors.push(ErrTypes::MissingOperator(*line));
+        collected_errors.push(ErrTypes::MissingOperator(token.span));
         return;
     }
-    while matches!(token_iter.peek(), Some(Token::Space)) {
-        token_iter.next();
-    }
 
-    // Build the raw expression.
-    let mut raw_value = String::new();
-    while let Some(tok) = token_iter.peek() {
-        match tok {
-            Token::EOL | Token::EOF => {
-                *line += 1;
-                break;
-            }
-            Token::Space => {
-                token_iter.next();
-            }
-            Token::Iden(val) => {
-                raw_value.push_str(val);
-                token_iter.next();
-            }
-            Token::ADDOP => {
-                raw_value.push('+');
-                token_iter.next();
-            }
-            Token::SUBOP => {
-                raw_value.push('-');
-                token_iter.next();
-            }
-            Token::MULTIOP => {
-                raw_value.push('*');
-                token_iter.next();
-            }
-            Token::DIVOP => {
-                raw_value.push('/');
-                token_iter.next();
-            }
-            _ => {
-                token_iter.next();
-            }
-        }
-    }
-    if raw_value.trim().is_empty() {
-        collected_errors.push(ErrTypes::MissingValue(*line));
+    let value = collect_expr_tokens(token_iter);
+    let value_span = span_of(&value).unwrap_or(op_span);
+    let Some(mut expr) = parse_expr(&value, collected_errors, op_span) else {
         return;
-    }
+    };
 
-    // Process each operand and rebuild expression.
-    let mut final_expr = {
-        let mut result = String::new();
-        let mut current_operand = String::new();
-        for c in raw_value.chars() {
-            if "+-*/".contains(c) {
-                if !current_operand.is_empty() {
-                    if !validate_operand(
-                        &current_operand,
-                        &var_name,
-                        collected_vars,
-                        collected_errors,
-                        *line,
-                    ) {
-                        return;
-                    }
-                    result.push_str(&format_operand(&current_operand));
-                    current_operand.clear();
-                }
-                result.push(c);
-            } else {
-                current_operand.push(c);
-            }
-        }
-        if !current_operand.is_empty() {
-            if !validate_operand(
-                &current_operand,
-                &var_name,
-                collected_vars,
-                collected_errors,
-                *line,
-            ) {
-                return;
-            }
-            result.push_str(&format_operand(&current_operand));
+    if let Some(Expr::Index(_, index, _)) = &element {
+        if !resolve_names(index, collected_vars, collected_errors, &target) {
+            return;
         }
-        result
-    };
+    }
 
-    // Prepend compound assignment operator if any.
+    // `x op= value` assigns `x op (value)`.
     if let Some(op) = compound_operator {
-        final_expr = format!("{}{}{}", var_name, op, final_expr);
+        let current = element.clone().unwrap_or(Expr::Var(var_name.clone()));
+        expr = Expr::Binary(Box::new(current), op, Box::new(expr), op_span.line);
     }
 
-    // If the expression is arithmetic, treat it as math.
-    if final_expr.contains('+')
-        || final_expr.contains('-')
-        || final_expr.contains('*')
-        || final_expr.contains('/')
-    {
-        let new_var = Variables::MATH(var_name.clone(), final_expr.clone());
-        ast.push(AST::VarAssign(new_var));
+    if !resolve_names(&expr, collected_vars, collected_errors, &value) {
         return;
     }
 
-    // Create variable based on type.
-    let new_var: Variables = if final_expr.starts_with('\"') && final_expr.ends_with('\"') {
-        let processed = final_expr[1..final_expr.len() - 1].to_string();
-        if var_type == "str" {
-            Variables::Str(Box::leak(var_name.clone().into_boxed_str()), processed)
-        } else {
-            collected_errors.push(ErrTypes::TypeMismatch(*line));
-            return;
-        }
-    } else if final_expr.starts_with('\'') && final_expr.ends_with('\'') {
-        let processed = final_expr[1..final_expr.len() - 1].to_string();
-        if processed.chars().count() != 1 {
-            collected_errors.push(ErrTypes::CharVarLen(*line));
+    // Writing an element or a field leaves the array or struct itself in place.
+    match element {
+        Some(Expr::Index(array, index, line)) => {
+            ast.push(AST::SetIndex {
+                array,
+                index: *index,
+                value: expr,
+                line,
+                spans: [target_span, value_span],
+            });
             return;
         }
-        if var_type == "ch" {
-            Variables::Char(
-                Box::leak(var_name.clone().into_boxed_str()),
-                processed.chars().next().unwrap(),
-            )
-        } else {
-            collected_errors.push(ErrTypes::TypeMismatch(*line));
+        Some(Expr::Field(var, field)) => {
+            ast.push(AST::SetField {
+                var,
+                field,
+                value: expr,
+                spans: [target_span, value_span],
+            });
             return;
         }
-    } else {
-        let mut processed_value = final_expr.clone();
-        let mut forced_type = None;
-        if processed_value.ends_with(')') {
-            if let Some(start) = processed_value.rfind('(') {
-                let potential = processed_value[start + 1..processed_value.len() - 1]
-                    .trim()
-                    .to_string();
-                if potential == "i8"
-                    || potential == "i16"
-                    || potential == "i32"
-                    || potential == "i64"
-                    || potential == "f32"
-                    || potential == "f64"
-                {
-                    forced_type = Some(potential);
-                    let trimmed_value = processed_value[..start].trim().to_string();
-                    if trimmed_value.is_empty() {
-                        collected_errors.push(ErrTypes::MissingValue(*line));
-                        return;
-                    }
-                    processed_value = trimmed_value;
-                }
-            }
-        }
-        if let Some(ty) = forced_type {
-            match ty.as_str() {
-                "i8" => {
-                    if let Ok(val) = processed_value.parse::<i8>() {
-                        Variables::I8(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "i16" => {
-                    if let Ok(val) = processed_value.parse::<i16>() {
-                        Variables::I16(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "i32" => {
-                    if let Ok(val) = processed_value.parse::<i32>() {
-                        Variables::I32(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "i64" => {
-                    if let Ok(val) = processed_value.parse::<i64>() {
-                        Variables::I64(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "f32" => {
-                    if let Ok(val) = processed_value.parse::<f32>() {
-                        Variables::F32(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "f64" => {
-                    if let Ok(val) = processed_value.parse::<f64>() {
-                        Variables::F64(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                _ => {
-                    collected_errors.push(ErrTypes::TypeMismatch(*line));
-                    return;
-                }
-            }
-        } else {
-            match var_type {
-                "i8" => {
-                    if let Ok(val) = processed_value.parse::<i8>() {
-                        Variables::I8(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "i16" => {
-                    if let Ok(val) = processed_value.parse::<i16>() {
-                        Variables::I16(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "i32" => {
-                    if let Ok(val) = processed_value.parse::<i32>() {
-                        Variables::I32(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "i64" => {
-                    if let Ok(val) = processed_value.parse::<i64>() {
-                        Variables::I64(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "f32" => {
-                    if let Ok(val) = processed_value.parse::<f32>() {
-                        Variables::F32(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                "f64" => {
-                    if let Ok(val) = processed_value.parse::<f64>() {
-                        Variables::F64(Box::leak(var_name.clone().into_boxed_str()), val)
-                    } else {
-                        collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-                        return;
-                    }
-                }
-                _ => Variables::MATH(var_name.clone(), processed_value.clone()),
-            }
-        }
-    };
-
-    ast.push(AST::VarAssign(new_var));
-}
-
-#[inline(always)]
-fn validate_operand(
-    operand: &str,
-    _var_name: &str,
-    collected_vars: &mut Vec<(String, &'static str)>,
-    collected_errors: &mut Vec<ErrTypes>,
-    line: i32,
-) -> bool {
-    let trimmed = operand.trim();
-    if trimmed.is_empty() {
-        collected_errors.push(ErrTypes::MissingValue(line));
-        return false;
-    }
-    let cleaned = if trimmed.ends_with('f') || trimmed.ends_with('F') {
-        &trimmed[..trimmed.len() - 1]
-    } else {
-        trimmed
-    };
-    if cleaned.parse::<f32>().is_ok() || collected_vars.iter().any(|(name, _)| name == cleaned) {
-        true
-    } else {
-        collected_errors.push(ErrTypes::VarNotFound(line));
-        false
+        _ => {}
     }
-}
 
-#[inline(always)]
-fn format_operand(operand: &str) -> String {
-    let trimmed = operand.trim();
-    let cleaned = if trimmed.ends_with('f') || trimmed.ends_with('F') {
-        &trimmed[..trimmed.len() - 1]
-    } else {
-        trimmed
-    };
-    if let Ok(num) = cleaned.parse::<f32>() {
-        if num.fract().abs() < 1e-6 {
-            format!("{}.0", num)
-        } else {
-            num.to_string()
-        }
-    } else {
-        cleaned.to_string()
-    }
+    collected_vars.mark_write(&var_name, token.span);
+    ast.push(AST::VarAssign(Variables::MATH(var_name, expr), value_span));
 }
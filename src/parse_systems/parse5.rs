@@ -1,5 +1,18 @@
-use super::AST;
-use crate::{err_system::err_types::ErrTypes, parse_systems::Variables, tok_system::tokens::Token};
+use super::{
+    parse2::{annotated_var, type_annotation},
+    AST,
+};
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        expr_parser::{collect_expr_tokens, parse_typed_expr},
+        symbol_table::{SymbolTable, UNKNOWN},
+        Expr,
+    },
+    parse_systems::Variables,
+    tok_system::tokens::{span_of, Span, Token, TokenKind},
+    type_system::{is_array, is_growable, is_value_type, EMPTY_ARRAY},
+};
 
 #[inline(always)]
 pub fn parse5(
@@ -7,13 +20,12 @@ pub fn parse5(
     token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
     ast: &mut Vec<AST>,
     _code: &String,
-    collected_vars: &mut Vec<(String, &'static str)>,
+    collected_vars: &mut SymbolTable,
     collected_errors: &mut Vec<ErrTypes>,
-    line: &mut i32,
 ) {
     // Expect the first token to be "const"
-    let cmd = match token {
-        Token::Iden(cmd) => cmd,
+    let cmd = match &token.kind {
+        TokenKind::Iden(cmd) => cmd,
         _ => return,
     };
     if cmd != "const" {
@@ -21,185 +33,119 @@ pub fn parse5(
     }
 
     // Skip spaces after "const"
-    while matches!(token_iter.peek(), Some(Token::Space)) {
+    while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
         token_iter.next();
     }
 
     // Get variable name.
-    let var_name = match token_iter.next() {
-        Some(Token::Iden(name)) => name.clone(),
+    let name_tok = token_iter.next();
+    let name_span = name_tok.map_or(token.span, |tok| tok.span);
+    let var_name = match name_tok.map(|tok| &tok.kind) {
+        Some(TokenKind::Iden(name)) => name.clone(),
         _ => {
-            collected_errors.push(ErrTypes::UnknownCMD(*line));
+            collected_errors.push(ErrTypes::UnknownCMD(name_span));
             return;
         }
     };
 
-    // Avoid duplicate declarations.
-    if collected_vars.iter().any(|(name, _)| name == &var_name) {
-        collected_errors.push(ErrTypes::VarAlreadyExists(*line));
+    // Avoid duplicate declarations; constants never shadow.
+    let prev = collected_vars.declared_span(&var_name);
+    if collected_vars.declared_here(&var_name) {
+        collected_errors.push(ErrTypes::VarAlreadyExists(name_span, prev));
         return;
     }
-
-    // Skip spaces before assignment operator.
-    while matches!(token_iter.peek(), Some(Token::Space)) {
-        token_iter.next();
+    if collected_vars.lookup(&var_name).is_some() {
+        collected_errors.push(ErrTypes::ImplicitShadow(name_span, prev));
+        return;
     }
 
-    // Process assignment operator.
-    let mut compound_operator: Option<char> = None;
-    if let Some(op_token) = token_iter.next() {
-        match op_token {
-            Token::EqSign => {}
-            Token::ADDOP | Token::SUBOP | Token::MULTIOP | Token::DIVOP => {
-                compound_operator = match op_token {
-                    Token::ADDOP => Some('+'),
-                    Token::SUBOP => Some('-'),
-                    Token::MULTIOP => Some('*'),
-                    Token::DIVOP => Some('/'),
-                    _ => None,
-                };
-                while matches!(token_iter.peek(), Some(Token::Space)) {
-                    token_iter.next();
-                }
-                if let Some(Token::EqSign) = token_iter.next() {
-                    // compound assignment confirmed
-                } else {
-                    collected_errors.push(ErrTypes::MissingOperator(*line));
-                    return;
-                }
-            }
-            _ => {
-                collected_errors.push(ErrTypes::UnexpectedToken(*line));
-                return;
-            }
+    // `const limit: i64 = ...` gives the type instead of taking the value's.
+    let declared = match type_annotation(token_iter, collected_vars) {
+        // A constant's length cannot change, so its array type needs one.
+        Ok(Some((typ, typ_span))) if is_growable(typ) => {
+            collected_errors.push(ErrTypes::UnsupportedVarType(typ_span));
+            collected_vars.declare_at(var_name, UNKNOWN, name_span);
+            return;
         }
-    } else {
-        collected_errors.push(ErrTypes::MissingOperator(*line));
-        return;
-    }
-    while matches!(token_iter.peek(), Some(Token::Space)) {
-        token_iter.next();
-    }
+        Ok(declared) => declared.map(|(typ, _)| typ),
+        Err(err) => {
+            collected_errors.push(err);
+            collected_vars.declare_at(var_name, UNKNOWN, name_span);
+            return;
+        }
+    };
 
-    // Build the raw expression.
-    let mut raw_value = String::new();
-    while let Some(tok) = token_iter.peek() {
-        match tok {
-            Token::EOL | Token::EOF => {
-                *line += 1;
-                break;
-            }
-            Token::Space => {
-                token_iter.next();
-            }
-            Token::Iden(val) => {
-                raw_value.push_str(val);
-                token_iter.next();
-            }
-            Token::ADDOP => {
-                raw_value.push('+');
-                token_iter.next();
-            }
-            Token::SUBOP => {
-                raw_value.push('-');
-                token_iter.next();
-            }
-            Token::MULTIOP => {
-                raw_value.push('*');
-                token_iter.next();
-            }
-            Token::DIVOP => {
-                raw_value.push('/');
-                token_iter.next();
-            }
-            _ => {
-                token_iter.next();
-            }
+    match const_value(
+        token_iter,
+        &var_name,
+        name_span,
+        declared,
+        collected_vars,
+        collected_errors,
+    ) {
+        Some((new_var, const_type, value_span)) => {
+            collected_vars.declare_at(var_name, const_type, name_span);
+            ast.push(AST::Var(new_var, const_type, value_span));
         }
+        // Declared anyway, so later uses are not reported as missing too.
+        None => collected_vars.declare_at(var_name, UNKNOWN, name_span),
     }
-    if raw_value.trim().is_empty() {
-        collected_errors.push(ErrTypes::MissingValue(*line));
-        return;
+}
+
+/// Parses the `= value` part of a `const` declaration into the variable, its type tag and the
+/// span of the value. A value with a type error gets `UNKNOWN`, as in `may`. Constants need
+/// a value even when their type is given.
+fn const_value(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    var_name: &str,
+    name_span: Span,
+    declared: Option<&'static str>,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Option<(Variables, &'static str, Span)> {
+    // Skip spaces before assignment operator.
+    while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
+        token_iter.next();
     }
 
-    // Process operands and rebuild expression.
-    let final_expr = if (raw_value.starts_with('\"') && raw_value.ends_with('\"'))
-        || (raw_value.starts_with('\'') && raw_value.ends_with('\''))
-    {
-        raw_value
-    } else {
-        let mut result = String::new();
-        let mut current_operand = String::new();
-        for c in raw_value.chars() {
-            if "+-*/".contains(c) {
-                if !current_operand.is_empty() {
-                    if !validate_operand(
-                        &current_operand,
-                        &var_name,
-                        collected_vars,
-                        collected_errors,
-                        *line,
-                    ) {
-                        return;
-                    }
-                    result.push_str(&format_operand(&current_operand));
-                    current_operand.clear();
-                }
-                result.push(c);
-            } else {
-                current_operand.push(c);
-            }
+    // Constants are only ever assigned once, with `=`.
+    let eq_tok = token_iter.next();
+    let eq_span = eq_tok.map_or(name_span, |tok| tok.span);
+    match eq_tok.map(|tok| &tok.kind) {
+        Some(TokenKind::EqSign) => {}
+        Some(TokenKind::EOL) | Some(TokenKind::EOF) | None => {
+            collected_errors.push(ErrTypes::MissingValue(eq_span, None));
+            return None;
         }
-        if !current_operand.is_empty() {
-            if !validate_operand(
-                &current_operand,
-                &var_name,
-                collected_vars,
-                collected_errors,
-                *line,
-            ) {
-                return;
-            }
-            result.push_str(&format_operand(&current_operand));
+        Some(_) => {
+            collected_errors.push(ErrTypes::UnexpectedToken(eq_span));
+            return None;
         }
-        result
-    };
+    }
 
-    let final_expr = if let Some(op) = compound_operator {
-        format!("{}{}{}", var_name, op, final_expr)
-    } else {
-        final_expr
-    };
+    let value = collect_expr_tokens(token_iter);
+    let value_span = span_of(&value).unwrap_or(eq_span);
+    let (expr, typ) = parse_typed_expr(&value, collected_vars, collected_errors, eq_span)?;
+    // `[]` has no element type to give the constant.
+    if typ == EMPTY_ARRAY && declared.is_none() {
+        collected_errors.push(ErrTypes::MissingValue(value_span, None));
+        return None;
+    }
 
     // Create constant variable.
-    let new_var: Variables = if final_expr.starts_with('\"') && final_expr.ends_with('\"') {
-        let processed = final_expr[1..final_expr.len() - 1].to_string();
-        Variables::Str(Box::leak(var_name.clone().into_boxed_str()), processed)
-    } else if final_expr.starts_with('\'') && final_expr.ends_with('\'') {
-        let processed = final_expr[1..final_expr.len() - 1].to_string();
-        if processed.chars().count() != 1 {
-            collected_errors.push(ErrTypes::CharVarLen(*line));
-            return;
-        }
-        Variables::Char(
-            Box::leak(var_name.clone().into_boxed_str()),
-            processed.chars().next().unwrap(),
-        )
-    } else if final_expr.contains('+')
-        || final_expr.contains('-')
-        || final_expr.contains('*')
-        || final_expr.contains('/')
-    {
-        Variables::MATH(var_name.clone(), final_expr.clone())
-    } else {
-        if let Ok(val) = final_expr.parse::<i32>() {
-            Variables::I32(Box::leak(var_name.clone().into_boxed_str()), val)
-        } else if let Ok(val) = final_expr.parse::<f32>() {
-            Variables::F32(Box::leak(var_name.clone().into_boxed_str()), val)
-        } else {
-            collected_errors.push(ErrTypes::InvalidNumberFormat(*line));
-            return;
-        }
+    let name_static = Box::leak(var_name.to_string().into_boxed_str());
+    let typ = declared.unwrap_or(typ);
+    let new_var = match expr {
+        expr if declared.is_some() => annotated_var(name_static, expr, typ),
+        Expr::Str(text) => Variables::Str(name_static, text),
+        Expr::Char(c) => Variables::Char(name_static, c),
+        Expr::Bool(val) => Variables::Bool(name_static, val),
+        Expr::Int(val) => match i32::try_from(val) {
+            Ok(val) => Variables::I32(name_static, val),
+            Err(_) => Variables::I64(name_static, val),
+        },
+        Expr::Float(val) => Variables::F32(name_static, val as f32),
+        expr => Variables::MATH(var_name.to_string(), expr),
     };
 
     let const_type = match &new_var {
@@ -211,55 +157,16 @@ pub fn parse5(
         Variables::F64(_, _) => "const;f64",
         Variables::Str(_, _) => "const;str",
         Variables::Char(_, _) => "const;ch",
-        Variables::MATH(_, _) => "const;f32",
-        Variables::REF(_, _) => "const;ref",
-    };
-
-    collected_vars.push((var_name.clone(), const_type));
-    ast.push(AST::Var(new_var));
-}
-
-#[inline(always)]
-fn validate_operand(
-    operand: &str,
-    _var_name: &str,
-    collected_vars: &mut Vec<(String, &'static str)>,
-    collected_errors: &mut Vec<ErrTypes>,
-    line: i32,
-) -> bool {
-    let trimmed = operand.trim();
-    if trimmed.is_empty() {
-        collected_errors.push(ErrTypes::MissingValue(line));
-        return false;
-    }
-    let cleaned = if trimmed.ends_with('f') || trimmed.ends_with('F') {
-        &trimmed[..trimmed.len() - 1]
-    } else {
-        trimmed
+        Variables::Bool(_, _) => "const;bool",
+        Variables::MATH(_, _)
+            if !is_value_type(typ)
+                && !is_array(typ)
+                && collected_vars.struct_def(typ).is_none() =>
+        {
+            UNKNOWN
+        }
+        Variables::MATH(_, _) => Box::leak(format!("const;{}", typ).into_boxed_str()),
     };
-    if cleaned.parse::<f32>().is_ok() || collected_vars.iter().any(|(name, _)| name == cleaned) {
-        true
-    } else {
-        collected_errors.push(ErrTypes::VarNotFound(line));
-        false
-    }
-}
 
-#[inline(always)]
-fn format_operand(operand: &str) -> String {
-    let trimmed = operand.trim();
-    let cleaned = if trimmed.ends_with('f') || trimmed.ends_with('F') {
-        &trimmed[..trimmed.len() - 1]
-    } else {
-        trimmed
-    };
-    if let Ok(num) = cleaned.parse::<f32>() {
-        if num.fract().abs() < 1e-6 {
-            format!("{}.0", num)
-        } else {
-            num.to_string()
-        }
-    } else {
-        cleaned.to_string()
-    }
+    Some((new_var, const_type, value_span))
 }
@@ -0,0 +1,239 @@
+use super::{FuncDef, AST};
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        expr_parser::{collect_expr_tokens, parse_typed_expr, ARRAY_BUILTINS},
+        fn_calls::fn_tag,
+        symbol_table::{ScopeKind, SymbolTable, UNKNOWN},
+    },
+    parse_systems::parse_tokens,
+    tok_system::tokens::{span_of, Span, Token, TokenKind},
+    type_system::value_type,
+};
+
+#[inline(always)]
+fn skip_spaces(token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>) {
+    while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
+        token_iter.next();
+    }
+}
+
+/// Takes the next token, returning its kind and span. Past the last token there is no kind and
+/// `after` is used as the span.
+#[inline(always)]
+fn next_tok<'a>(
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<'a, Token>>,
+    after: Span,
+) -> (Option<&'a TokenKind>, Span) {
+    match token_iter.next() {
+        Some(tok) => (Some(&tok.kind), tok.span),
+        None => (None, after),
+    }
+}
+
+#[inline(always)]
+pub fn parse6(
+    token: &Token,
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    ast: &mut Vec<AST>,
+    code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) {
+    match &token.kind {
+        TokenKind::Iden(cmd) if cmd == "fn" => {
+            // Functions cannot be declared inside other functions.
+            if collected_vars.return_type().is_some() {
+                collected_errors.push(ErrTypes::SyntaxError(token.span));
+                return;
+            }
+            skip_spaces(token_iter);
+
+            let (name_tok, name_span) = next_tok(token_iter, token.span);
+            let fn_name = match name_tok {
+                Some(TokenKind::Iden(name)) => name.clone(),
+                _ => {
+                    collected_errors.push(ErrTypes::UnknownCMD(name_span));
+                    return;
+                }
+            };
+            // `len`, `push` and `pop` always mean the array builtins.
+            if ARRAY_BUILTINS.contains(&fn_name.as_str()) {
+                collected_errors.push(ErrTypes::ReservedKeyword(name_span));
+                return;
+            }
+            if collected_vars.lookup(&fn_name).is_some() {
+                let prev = collected_vars.declared_span(&fn_name);
+                collected_errors.push(ErrTypes::VarAlreadyExists(name_span, prev));
+                return;
+            }
+            // Declared up front so that, if the rest of the declaration fails, calls to it are
+            // not reported as unknown too. It gets its real tag once the body is parsed.
+            collected_vars.declare_at(fn_name.clone(), UNKNOWN, name_span);
+
+            // Parameter list: `(a: i32, b: str)`.
+            skip_spaces(token_iter);
+            let (open, last) = next_tok(token_iter, name_span);
+            if open != Some(&TokenKind::LSmallBrac) {
+                collected_errors.push(ErrTypes::UnexpectedToken(last));
+                return;
+            }
+            let open_span = last;
+            let mut params: Vec<(String, &'static str)> = Vec::new();
+            // Where each parameter name was written.
+            let mut param_spans = Vec::new();
+            loop {
+                skip_spaces(token_iter);
+                let (tok, last) = next_tok(token_iter, last);
+                let param_name = match tok {
+                    Some(TokenKind::RSmallBracket) if params.is_empty() => break,
+                    Some(TokenKind::Iden(name)) => name.clone(),
+                    _ => {
+                        collected_errors.push(ErrTypes::UnexpectedToken(last));
+                        return;
+                    }
+                };
+                if let Some(at) = params.iter().position(|(name, _)| name == &param_name) {
+                    collected_errors.push(ErrTypes::VarAlreadyExists(last, Some(param_spans[at])));
+                    return;
+                }
+                param_spans.push(last);
+                skip_spaces(token_iter);
+                let (tok, last) = next_tok(token_iter, last);
+                if tok != Some(&TokenKind::Colon) {
+                    collected_errors.push(ErrTypes::MissingOperator(last));
+                    return;
+                }
+                skip_spaces(token_iter);
+                let (tok, last) = next_tok(token_iter, last);
+                let param_type = match tok {
+                    Some(TokenKind::Iden(typ)) => match value_type(typ) {
+                        Some(typ) => typ,
+                        None => {
+                            collected_errors.push(ErrTypes::UnsupportedVarType(last));
+                            return;
+                        }
+                    },
+                    _ => {
+                        collected_errors.push(ErrTypes::MissingValue(last, None));
+                        return;
+                    }
+                };
+                params.push((param_name, param_type));
+                skip_spaces(token_iter);
+                let (tok, last) = next_tok(token_iter, last);
+                match tok {
+                    Some(TokenKind::Comma) => continue,
+                    Some(TokenKind::RSmallBracket) => break,
+                    _ => {
+                        collected_errors.push(ErrTypes::UnbalancedParentheses(open_span.to(last)));
+                        return;
+                    }
+                }
+            }
+
+            // Optional return type: `-> i32`.
+            skip_spaces(token_iter);
+            let mut ret = None;
+            if let Some(arrow) = token_iter.next_if(|tok| tok.kind == TokenKind::SUBOP) {
+                let (tok, last) = next_tok(token_iter, arrow.span);
+                if tok != Some(&TokenKind::GreaterThan) {
+                    collected_errors.push(ErrTypes::UnexpectedToken(last));
+                    return;
+                }
+                skip_spaces(token_iter);
+                let (tok, last) = next_tok(token_iter, last);
+                ret = match tok {
+                    Some(TokenKind::Iden(typ)) => match value_type(typ) {
+                        Some(typ) => Some(typ),
+                        None => {
+                            collected_errors.push(ErrTypes::UnsupportedVarType(last));
+                            return;
+                        }
+                    },
+                    _ => {
+                        collected_errors.push(ErrTypes::MissingValue(last, None));
+                        return;
+                    }
+                };
+                skip_spaces(token_iter);
+            }
+            let (tok, last) = next_tok(token_iter, last);
+            if tok != Some(&TokenKind::LCurly) {
+                collected_errors.push(ErrTypes::UnexpectedToken(last));
+                return;
+            }
+            let body_open = last;
+
+            // Collect body tokens until the matching '}'
+            let mut body = Vec::new();
+            let mut depth = 1;
+            for tok in token_iter.by_ref() {
+                match tok.kind {
+                    TokenKind::LCurly => depth += 1,
+                    TokenKind::RCurly => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                body.push(tok.clone());
+            }
+            if depth != 0 {
+                collected_errors.push(ErrTypes::UnexpectedEndOfInput(body_open));
+                return;
+            }
+
+            // The body only sees other functions, its own parameters and its own locals.
+            let tag = fn_tag(ret.unwrap_or("void"), &params);
+            let mut fn_vars = collected_vars.functions();
+            fn_vars.declare_at(fn_name.clone(), tag, name_span);
+            fn_vars.push_scope(ScopeKind::Function(ret.unwrap_or("void")));
+            for ((name, typ), span) in params.iter().zip(&param_spans) {
+                fn_vars.declare_at(name.clone(), typ, *span);
+            }
+            let outer = std::mem::replace(collected_vars, fn_vars);
+
+            let body_parsed = parse_tokens(&body, code, collected_vars, collected_errors);
+
+            let body_vars = std::mem::replace(collected_vars, outer);
+            collected_vars.absorb(body_vars);
+            collected_vars.retype(&fn_name, tag);
+
+            ast.push(AST::Func(FuncDef {
+                name: fn_name,
+                params,
+                ret,
+                body: body_parsed,
+                span: name_span,
+            }));
+        }
+        TokenKind::Iden(cmd) if cmd == "return" => {
+            let value = collect_expr_tokens(token_iter);
+            let ret = match collected_vars.return_type() {
+                Some(ret) => ret,
+                None => {
+                    collected_errors.push(ErrTypes::ReturnOutsideFn(token.span));
+                    return;
+                }
+            };
+            collected_vars.forget_writes();
+            let Some(value_span) = span_of(&value) else {
+                if ret != "void" {
+                    collected_errors.push(ErrTypes::MissingValue(token.span, None));
+                    return;
+                }
+                ast.push(AST::Return(None, token.span));
+                return;
+            };
+            if let Some((expr, _)) =
+                parse_typed_expr(&value, collected_vars, collected_errors, token.span)
+            {
+                ast.push(AST::Return(Some(expr), value_span));
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,177 @@
+use super::AST;
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        expr_parser::ARRAY_BUILTINS,
+        symbol_table::{StructDef, SymbolTable},
+    },
+    tok_system::tokens::{Span, Token, TokenKind},
+    type_system::value_type,
+};
+
+#[inline(always)]
+fn skip_spaces(token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>) {
+    while matches!(token_iter.peek(), Some(tok) if tok.kind == TokenKind::Space) {
+        token_iter.next();
+    }
+}
+
+/// Parses a struct declaration, `struct Point { x: f64, y: f64 }`. Fields are separated by
+/// commas or line breaks.
+#[inline(always)]
+pub fn parse7(
+    token: &Token,
+    token_iter: &mut std::iter::Peekable<std::slice::Iter<Token>>,
+    ast: &mut Vec<AST>,
+    _code: &String,
+    collected_vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) {
+    // Structs are only declared at the top level, so every function can use them.
+    if !collected_vars.at_top_level() {
+        collected_errors.push(ErrTypes::SyntaxError(token.span));
+        return;
+    }
+    skip_spaces(token_iter);
+
+    let name_tok = token_iter.next();
+    let name_span = name_tok.map_or(token.span, |tok| tok.span);
+    let name = match name_tok.map(|tok| &tok.kind) {
+        Some(TokenKind::Iden(name)) => name.clone(),
+        _ => {
+            collected_errors.push(ErrTypes::UnknownCMD(name_span));
+            return;
+        }
+    };
+    // A struct's name is used as a type, so it cannot be a builtin one.
+    if value_type(&name).is_some() || ARRAY_BUILTINS.contains(&name.as_str()) {
+        collected_errors.push(ErrTypes::ReservedKeyword(name_span));
+        return;
+    }
+    if let Some(def) = collected_vars.struct_def(&name) {
+        collected_errors.push(ErrTypes::VarAlreadyExists(name_span, Some(def.span)));
+        return;
+    }
+    if collected_vars.lookup(&name).is_some() {
+        let prev = collected_vars.declared_span(&name);
+        collected_errors.push(ErrTypes::VarAlreadyExists(name_span, prev));
+        return;
+    }
+
+    skip_spaces(token_iter);
+    let open = match token_iter.next() {
+        Some(tok) if tok.kind == TokenKind::LCurly => tok.span,
+        tok => {
+            collected_errors.push(ErrTypes::UnexpectedToken(
+                tok.map_or(name_span, |tok| tok.span),
+            ));
+            return;
+        }
+    };
+
+    // The whole body is taken first, so a bad field does not leave the rest to be parsed as
+    // statements.
+    let mut body = Vec::new();
+    let mut close = None;
+    for tok in token_iter.by_ref() {
+        if tok.kind == TokenKind::RCurly {
+            close = Some(tok.span);
+            break;
+        }
+        body.push(tok);
+    }
+    let Some(close) = close else {
+        collected_errors.push(ErrTypes::UnexpectedEndOfInput(open));
+        return;
+    };
+
+    let Some(fields) = struct_fields(&body, collected_errors) else {
+        return;
+    };
+    if fields.is_empty() {
+        collected_errors.push(ErrTypes::MissingValue(open.to(close), None));
+        return;
+    }
+
+    let def = StructDef {
+        name: Box::leak(name.into_boxed_str()),
+        fields,
+        span: name_span,
+    };
+    collected_vars.declare_struct(def.clone());
+    ast.push(AST::Struct(def));
+}
+
+/// Parses the `name: type` fields between a struct's braces.
+fn struct_fields(
+    body: &[&Token],
+    collected_errors: &mut Vec<ErrTypes>,
+) -> Option<Vec<(String, &'static str)>> {
+    let mut fields: Vec<(String, &'static str)> = Vec::new();
+    // Where each field name was written.
+    let mut field_spans: Vec<Span> = Vec::new();
+    let mut toks = body
+        .iter()
+        .copied()
+        .filter(|tok| tok.kind != TokenKind::Space)
+        .peekable();
+    while let Some(tok) = toks.next() {
+        let field = match &tok.kind {
+            TokenKind::EOL | TokenKind::Comma => continue,
+            TokenKind::Iden(field) => field.clone(),
+            _ => {
+                collected_errors.push(ErrTypes::UnexpectedToken(tok.span));
+                return None;
+            }
+        };
+        if let Some(at) = fields.iter().position(|(name, _)| *name == field) {
+            collected_errors.push(ErrTypes::VarAlreadyExists(tok.span, Some(field_spans[at])));
+            return None;
+        }
+        let Some(colon) = toks.next_if(|tok| tok.kind == TokenKind::Colon) else {
+            let span = toks.peek().map_or(tok.span, |tok| tok.span);
+            collected_errors.push(ErrTypes::MissingOperator(span));
+            return None;
+        };
+        // Fields hold single values; arrays and other structs are not allowed in them.
+        let typ = match toks.next() {
+            Some(Token {
+                kind: TokenKind::Iden(typ),
+                span,
+                ..
+            }) => match value_type(typ) {
+                Some(typ) => typ,
+                None => {
+                    collected_errors.push(ErrTypes::UnsupportedVarType(*span));
+                    return None;
+                }
+            },
+            Some(Token {
+                kind: TokenKind::EOL | TokenKind::Comma,
+                span,
+                ..
+            }) => {
+                collected_errors.push(ErrTypes::MissingValue(*span, None));
+                return None;
+            }
+            Some(other) => {
+                collected_errors.push(ErrTypes::UnsupportedVarType(other.span));
+                return None;
+            }
+            None => {
+                collected_errors.push(ErrTypes::MissingValue(colon.span, None));
+                return None;
+            }
+        };
+        // Each field ends at a comma, a line break or the closing brace.
+        if let Some(extra) =
+            toks.next_if(|tok| !matches!(tok.kind, TokenKind::EOL | TokenKind::Comma))
+        {
+            collected_errors.push(ErrTypes::UnexpectedToken(extra.span));
+            return None;
+        }
+        fields.push((field, typ));
+        field_spans.push(tok.span);
+    }
+    Some(fields)
+}
@@ -0,0 +1,719 @@
+use super::io::{format_float, Io};
+use crate::{
+    helpers::{
+        symbol_table::StructDef, BinOp, ChildCond, CondToks, Condition, Expr, LogicalJoin, UnaryOp,
+    },
+    parse_systems::{parse3::counts_down, FuncDef, PrintTokTypes, Variables, AST},
+    type_system::elem_type,
+};
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+/// A runtime value. Integers of every width are held as `i64` and floats as `f64`; storing a
+/// value in a variable narrows it to the variable's type, like the conversions in the generated C.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Char(char),
+    Bool(bool),
+    Array(Vec<Value>),
+    /// A struct's name and its fields, in declaration order.
+    Struct(&'static str, Vec<(String, Value)>),
+}
+
+impl fmt::Display for Value {
+    /// Prints the value the way `nprintf` does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(val) => write!(f, "{}", val),
+            Value::Float(val) => write!(f, "{}", format_float(*val)),
+            Value::Str(text) => write!(f, "{}", text),
+            Value::Char(c) => write!(f, "{}", c),
+            Value::Bool(val) => write!(f, "{}", val),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Struct(name, fields) => {
+                write!(f, "{} {{ ", name)?;
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field, value)?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+/// Errors that stop a running program. They read like the ones a built binary reports.
+#[derive(Debug)]
+pub enum RuntimeError {
+    /// Input that does not fit the requested type.
+    BadInput {
+        typ: &'static str,
+        got: String,
+        line: i32,
+    },
+    /// Integer division by zero.
+    DivisionByZero { line: i32 },
+    /// An array index outside the array.
+    IndexOutOfRange { index: i64, len: usize, line: i32 },
+    /// `pop` on an empty array.
+    PopEmpty { line: i32 },
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::BadInput { typ, got, line } => write!(
+                f,
+                "[!] Runtime error at line {}: expected {} input, got \"{}\"",
+                line, typ, got
+            ),
+            RuntimeError::DivisionByZero { line } => {
+                write!(f, "[!] Runtime error at line {}: division by zero", line)
+            }
+            RuntimeError::IndexOutOfRange { index, len, line } => write!(
+                f,
+                "[!] Runtime error at line {}: index {} is out of range for an array of length {}",
+                line, index, len
+            ),
+            RuntimeError::PopEmpty { line } => write!(
+                f,
+                "[!] Runtime error at line {}: pop from an empty array",
+                line
+            ),
+        }
+    }
+}
+
+/// How a statement list finished.
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+}
+
+/// Converts a value to a Neit type, truncating floats and wrapping integers like a C cast.
+fn convert(value: Value, typ: &str) -> Value {
+    let typ = typ.trim_start_matches("const;");
+    let value = match (value, elem_type(typ)) {
+        (Value::Array(items), Some(elem)) => {
+            return Value::Array(items.into_iter().map(|item| convert(item, elem)).collect())
+        }
+        (value, _) => value,
+    };
+    let value = match (value, typ) {
+        (Value::Float(val), "i8" | "i16" | "i32" | "i64") => Value::Int(val as i64),
+        (Value::Int(val), "f32" | "f64") => Value::Float(val as f64),
+        (value, _) => value,
+    };
+    match (value, typ) {
+        (Value::Int(val), "i8") => Value::Int(val as i8 as i64),
+        (Value::Int(val), "i16") => Value::Int(val as i16 as i64),
+        (Value::Int(val), "i32") => Value::Int(val as i32 as i64),
+        (Value::Float(val), "f32") => Value::Float(val as f32 as f64),
+        (value, _) => value,
+    }
+}
+
+/// Where `index` is in an array of `len` elements, or the error for an index outside it.
+fn position(index: i64, len: usize, line: i32) -> Result<usize, RuntimeError> {
+    usize::try_from(index)
+        .ok()
+        .filter(|at| *at < len)
+        .ok_or(RuntimeError::IndexOutOfRange { index, len, line })
+}
+
+fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(val) => *val as f64,
+        Value::Float(val) => *val,
+        _ => 0.0,
+    }
+}
+
+fn as_int(value: &Value) -> i64 {
+    match value {
+        Value::Int(val) => *val,
+        Value::Float(val) => *val as i64,
+        _ => 0,
+    }
+}
+
+/// Orders two values like the generated C: `strcmp` for strings, numbers after promotion.
+/// `None` when a NaN is involved, which makes every comparison but `!=` false.
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Str(left), Value::Str(right)) => Some(left.as_bytes().cmp(right.as_bytes())),
+        (Value::Char(left), Value::Char(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        (Value::Int(left), Value::Int(right)) => Some(left.cmp(right)),
+        (left, right) => as_float(left).partial_cmp(&as_float(right)),
+    }
+}
+
+/// Resolves the C escape that a `\x` print word becomes in the generated code.
+fn unescape(word: &str) -> String {
+    let Some(rest) = word.strip_prefix('\\') else {
+        return word.to_string();
+    };
+    let mut chars = rest.chars();
+    let escaped = match chars.next() {
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('0') => '\0',
+        Some('a') => '\x07',
+        Some('b') => '\x08',
+        Some('f') => '\x0c',
+        Some('v') => '\x0b',
+        Some(other) => other,
+        None => return String::new(),
+    };
+    let mut text = escaped.to_string();
+    text.push_str(chars.as_str());
+    text
+}
+
+/// Collects every function definition, including ones declared inside blocks.
+fn collect_funcs<'a>(ast: &'a [AST], funcs: &mut HashMap<&'a str, &'a FuncDef>) {
+    for node in ast {
+        match node {
+            AST::Func(def) => {
+                funcs.insert(def.name.as_str(), def);
+            }
+            AST::While(body, _)
+            | AST::IF(body, _)
+            | AST::ElseIf(body, _)
+            | AST::Else(body)
+            | AST::For { body, .. } => collect_funcs(body, funcs),
+            _ => {}
+        }
+    }
+}
+
+/// Runs an AST directly, without going through C.
+#[derive(Default)]
+pub struct Interpreter<'a> {
+    funcs: HashMap<&'a str, &'a FuncDef>,
+    structs: HashMap<&'a str, &'a StructDef>,
+    /// Variables of the running function, or of the top level; one scope per block.
+    scopes: Vec<Vec<(String, &'static str, Value)>>,
+    io: Io,
+}
+
+impl<'a> Interpreter<'a> {
+    /// An interpreter that reads and writes through `io` instead of the process's stdin and
+    /// stdout.
+    pub fn with_io(io: Io) -> Self {
+        Interpreter {
+            io,
+            ..Default::default()
+        }
+    }
+
+    /// Where the program reads and writes, with what it has written so far.
+    pub fn io(&self) -> &Io {
+        &self.io
+    }
+
+    /// Runs the statements. Variables and functions declared at the top level stay available to
+    /// later calls on the same interpreter.
+    pub fn run(&mut self, ast: &'a [AST]) -> Result<(), RuntimeError> {
+        collect_funcs(ast, &mut self.funcs);
+        // Structs are only declared at the top level.
+        for node in ast {
+            if let AST::Struct(def) = node {
+                self.structs.insert(def.name, def);
+            }
+        }
+        if self.scopes.is_empty() {
+            self.scopes.push(Vec::new());
+        }
+        // A runtime error can leave block scopes behind.
+        self.scopes.truncate(1);
+        self.exec_list(ast).map(|_| ())
+    }
+
+    fn declare(&mut self, name: &str, typ: &'static str, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), typ, convert(value, typ)));
+        }
+    }
+
+    fn slot(&mut self, name: &str) -> Option<&mut (String, &'static str, Value)> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|(var, _, _)| var == name)
+    }
+
+    /// The type and the value of a variable, or of a struct variable's field written
+    /// `var.field`.
+    fn path(&mut self, path: &str) -> Option<(&'static str, &mut Value)> {
+        let Some((var, field)) = path.split_once('.') else {
+            return self.slot(path).map(|(_, typ, value)| (*typ, value));
+        };
+        self.field(var, field)
+    }
+
+    /// The type and the value of a field of a struct variable.
+    fn field(&mut self, var: &str, field: &str) -> Option<(&'static str, &mut Value)> {
+        let structs = &self.structs;
+        let (_, typ, value) = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|(name, _, _)| name == var)?;
+        let typ = structs
+            .get(typ.trim_start_matches("const;"))?
+            .field(field)?;
+        match value {
+            Value::Struct(_, fields) => fields
+                .iter_mut()
+                .find(|(name, _)| name == field)
+                .map(|(_, value)| (typ, value)),
+            _ => None,
+        }
+    }
+
+    /// The element type and the elements of an array variable.
+    fn array(&mut self, name: &str) -> Option<(&'static str, &mut Vec<Value>)> {
+        match self.slot(name)? {
+            (_, typ, Value::Array(items)) => {
+                Some((elem_type(typ.trim_start_matches("const;"))?, items))
+            }
+            _ => None,
+        }
+    }
+
+    /// Removes the last element of an array variable.
+    fn pop(&mut self, name: &str, line: i32) -> Result<Value, RuntimeError> {
+        let item = self.array(name).and_then(|(_, items)| items.pop());
+        item.ok_or(RuntimeError::PopEmpty { line })
+    }
+
+    fn assign(&mut self, name: &str, value: Value) {
+        if let Some((_, typ, slot)) = self.slot(name) {
+            *slot = convert(value, typ);
+        }
+    }
+
+    fn exec_block(&mut self, body: &'a [AST]) -> Result<Flow, RuntimeError> {
+        self.scopes.push(Vec::new());
+        let flow = self.exec_list(body);
+        self.scopes.pop();
+        flow
+    }
+
+    fn exec_list(&mut self, ast: &'a [AST]) -> Result<Flow, RuntimeError> {
+        // Whether a branch of the current `if` / `else if` / `else` chain has run.
+        let mut branch_taken = false;
+        for node in ast {
+            let flow = match node {
+                AST::IF(body, cond) => {
+                    branch_taken = self.eval_cond(cond)?;
+                    if branch_taken {
+                        self.exec_block(body)?
+                    } else {
+                        Flow::Next
+                    }
+                }
+                AST::ElseIf(body, cond) if !branch_taken => {
+                    branch_taken = self.eval_cond(cond)?;
+                    if branch_taken {
+                        self.exec_block(body)?
+                    } else {
+                        Flow::Next
+                    }
+                }
+                AST::Else(body) if !branch_taken => self.exec_block(body)?,
+                AST::ElseIf(..) | AST::Else(_) => Flow::Next,
+                node => self.exec(node)?,
+            };
+            if !matches!(flow, Flow::Next) {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec(&mut self, node: &'a AST) -> Result<Flow, RuntimeError> {
+        match node {
+            AST::Print { descriptor, text } => {
+                let fd = descriptor.display();
+                let mut out = String::new();
+                for ptok in text {
+                    match ptok {
+                        PrintTokTypes::Newline => out.push('\n'),
+                        PrintTokTypes::Space => out.push(' '),
+                        PrintTokTypes::Word(word) => out.push_str(&unescape(word)),
+                        PrintTokTypes::Text(text) => out.push_str(text),
+                        PrintTokTypes::Var(name) => {
+                            if let Some((_, value)) = self.path(name) {
+                                out.push_str(&value.to_string());
+                            }
+                        }
+                        // A call is written on its own, after the pieces before it, as the
+                        // generated C does.
+                        PrintTokTypes::Call(name, args, _) => {
+                            self.io.write(fd, &std::mem::take(&mut out));
+                            if let Some(value) = self.call(name, args)? {
+                                self.io.write(fd, &value.to_string());
+                            }
+                        }
+                        PrintTokTypes::Expr(expr, _) if expr.has_effects() => {
+                            self.io.write(fd, &std::mem::take(&mut out));
+                            let value = self.eval(expr)?;
+                            self.io.write(fd, &value.to_string());
+                        }
+                        PrintTokTypes::Expr(expr, _) => out.push_str(&self.eval(expr)?.to_string()),
+                    }
+                }
+                self.io.write(fd, &out);
+            }
+            AST::Var(var, typ, _) => {
+                let (name, value) = self.var_value(var)?;
+                self.declare(name, typ, value);
+            }
+            AST::VarAssign(var, _) => {
+                let (name, value) = self.var_value(var)?;
+                self.assign(name, value);
+            }
+            AST::Input {
+                var,
+                typ,
+                declare,
+                line,
+            } => {
+                let value = match *typ {
+                    "str" => Ok(Value::Str(self.io.read_line())),
+                    "ch" => self.io.input_ch().map(Value::Char),
+                    "bool" => self.io.input_bool().map(Value::Bool),
+                    "f32" | "f64" => self.io.input_float().map(Value::Float),
+                    "i8" => self
+                        .io
+                        .input_int(i8::MIN.into(), i8::MAX.into())
+                        .map(Value::Int),
+                    "i16" => self
+                        .io
+                        .input_int(i16::MIN.into(), i16::MAX.into())
+                        .map(Value::Int),
+                    "i32" => self
+                        .io
+                        .input_int(i32::MIN.into(), i32::MAX.into())
+                        .map(Value::Int),
+                    _ => self.io.input_int(i64::MIN, i64::MAX).map(Value::Int),
+                };
+                let value = value.map_err(|got| RuntimeError::BadInput {
+                    typ,
+                    got,
+                    line: *line,
+                })?;
+                if *declare {
+                    self.declare(var, typ, value);
+                } else {
+                    self.assign(var, value);
+                }
+            }
+            AST::While(body, cond) => {
+                while self.eval_cond(cond)? {
+                    match self.exec_block(body)? {
+                        Flow::Break => break,
+                        Flow::Next | Flow::Continue => {}
+                        ret => return Ok(ret),
+                    }
+                }
+            }
+            AST::For {
+                var,
+                typ,
+                start,
+                end,
+                step,
+                inclusive,
+                body,
+                ..
+            } => {
+                // Mirrors the generated `for`: the end and the step are evaluated once and converted to the
+                // loop variable's type, and the comparison depends on the step as written.
+                let mut current = as_int(&convert(self.eval(start)?, typ));
+                let end_val = as_int(&convert(self.eval(end)?, typ));
+                let step_val = as_int(&convert(self.eval(step)?, typ));
+                let down = counts_down(step);
+                loop {
+                    let keep_going = match (down, inclusive) {
+                        (false, false) => current < end_val,
+                        (false, true) => current <= end_val,
+                        (true, false) => current > end_val,
+                        (true, true) => current >= end_val,
+                    };
+                    if !keep_going {
+                        break;
+                    }
+                    // The loop variable and the body share one scope, as in the parser.
+                    self.scopes
+                        .push(vec![(var.clone(), *typ, Value::Int(current))]);
+                    let flow = self.exec_list(body);
+                    let scope = self.scopes.pop();
+                    // The body may assign the loop variable.
+                    if let Some((_, _, value)) = scope.and_then(|scope| scope.into_iter().next()) {
+                        current = as_int(&value);
+                    }
+                    match flow? {
+                        Flow::Break => break,
+                        Flow::Next | Flow::Continue => {}
+                        ret => return Ok(ret),
+                    }
+                    // Stops before a step that would pass the end, as the generated `for` does,
+                    // so the variable never overflows.
+                    let (gap, stride) = if down {
+                        (current as i128 - end_val as i128, -(step_val as i128))
+                    } else {
+                        (end_val as i128 - current as i128, step_val as i128)
+                    };
+                    if gap < stride || (!inclusive && gap == stride) {
+                        break;
+                    }
+                    current += step_val;
+                }
+            }
+            AST::Break => return Ok(Flow::Break),
+            AST::Continue => return Ok(Flow::Continue),
+            AST::Return(value, _) => {
+                let value = match value {
+                    Some(expr) => Some(self.eval(expr)?),
+                    None => None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            AST::Call(name, args, _) => {
+                self.call(name, args)?;
+            }
+            AST::SetIndex {
+                array,
+                index,
+                value,
+                line,
+                ..
+            } => {
+                let index = as_int(&self.eval(index)?);
+                let value = self.eval(value)?;
+                if let Some((elem, items)) = self.array(array) {
+                    let at = position(index, items.len(), *line)?;
+                    items[at] = convert(value, elem);
+                }
+            }
+            AST::Push(array, value, _) => {
+                let value = self.eval(value)?;
+                if let Some((elem, items)) = self.array(array) {
+                    items.push(convert(value, elem));
+                }
+            }
+            AST::Pop(array, line, _) => {
+                self.pop(array, *line)?;
+            }
+            AST::SetField {
+                var, field, value, ..
+            } => {
+                let value = self.eval(value)?;
+                if let Some((typ, slot)) = self.field(var, field) {
+                    *slot = convert(value, typ);
+                }
+            }
+            // Functions and structs are collected before running; branches are handled by
+            // `exec_list`.
+            AST::Func(_) | AST::Struct(_) | AST::IF(..) | AST::ElseIf(..) | AST::Else(_) => {}
+        }
+        Ok(Flow::Next)
+    }
+
+    fn var_value<'v>(&mut self, var: &'v Variables) -> Result<(&'v str, Value), RuntimeError> {
+        Ok(match var {
+            Variables::MATH(name, expr) => (name.as_str(), self.eval(expr)?),
+            Variables::I8(name, val) => (*name, Value::Int((*val).into())),
+            Variables::I16(name, val) => (*name, Value::Int((*val).into())),
+            Variables::I32(name, val) => (*name, Value::Int((*val).into())),
+            Variables::I64(name, val) => (*name, Value::Int(*val)),
+            Variables::F32(name, val) => (*name, Value::Float((*val).into())),
+            Variables::F64(name, val) => (*name, Value::Float(*val)),
+            Variables::Char(name, c) => (*name, Value::Char(*c)),
+            Variables::Str(name, text) => (*name, Value::Str(text.clone())),
+            Variables::Bool(name, val) => (*name, Value::Bool(*val)),
+        })
+    }
+
+    /// Calls a function; `None` for functions that return nothing. The checker has made sure
+    /// the function exists.
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<Option<Value>, RuntimeError> {
+        let Some(def) = self.funcs.get(name).copied() else {
+            unreachable!("call to undeclared function `{}`", name);
+        };
+        let mut params = Vec::with_capacity(args.len());
+        for ((param, typ), arg) in def.params.iter().zip(args) {
+            let value = convert(self.eval(arg)?, typ);
+            params.push((param.clone(), *typ, value));
+        }
+        // The body only sees its parameters and its own locals.
+        let caller = std::mem::replace(&mut self.scopes, vec![params]);
+        let flow = self.exec_list(&def.body);
+        self.scopes = caller;
+        Ok(match (flow?, def.ret) {
+            (Flow::Return(Some(value)), Some(ret)) => Some(convert(value, ret)),
+            (_, None) => None,
+            (_, Some(_)) => unreachable!("function `{}` ended without returning a value", name),
+        })
+    }
+
+    pub fn eval(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        Ok(match expr {
+            Expr::Int(val) => Value::Int(*val),
+            Expr::Float(val) => Value::Float(*val),
+            Expr::Str(text) => Value::Str(text.clone()),
+            Expr::Char(c) => Value::Char(*c),
+            Expr::Bool(val) => Value::Bool(*val),
+            // Names were resolved by the parser, so a missing one is a bug.
+            Expr::Var(name) => match self.slot(name) {
+                Some((_, _, value)) => value.clone(),
+                None => unreachable!("read of undeclared variable `{}`", name),
+            },
+            Expr::Call(name, args) => match self.call(name, args)? {
+                Some(value) => value,
+                None => unreachable!("value of `{}`, which returns nothing", name),
+            },
+            Expr::Unary(UnaryOp::Neg, operand) => match self.eval(operand)? {
+                Value::Int(val) => Value::Int(val.wrapping_neg()),
+                Value::Float(val) => Value::Float(-val),
+                other => other,
+            },
+            Expr::Binary(left, op, right, line) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                self.arith(left, *op, right, *line)?
+            }
+            Expr::Group(inner) => self.eval(inner)?,
+            Expr::Cast(inner, typ) => convert(self.eval(inner)?, typ),
+            Expr::Cond(cond) => Value::Bool(self.eval_cond(cond)?),
+            Expr::Array(items) => {
+                let mut values = Vec::with_capacity(items.len());
+                for item in items {
+                    values.push(self.eval(item)?);
+                }
+                // Integers in a literal with floats widen, as the C initialiser does.
+                if values.iter().any(|value| matches!(value, Value::Float(_))) {
+                    values = values
+                        .into_iter()
+                        .map(|value| convert(value, "f64"))
+                        .collect();
+                }
+                Value::Array(values)
+            }
+            Expr::Index(name, index, line) => {
+                let index = as_int(&self.eval(index)?);
+                match self.array(name) {
+                    Some((_, items)) => items[position(index, items.len(), *line)?].clone(),
+                    None => unreachable!("index into `{}`, which is not an array", name),
+                }
+            }
+            Expr::Len(name) => {
+                let len = self.array(name).map_or(0, |(_, items)| items.len());
+                Value::Int(len as i64)
+            }
+            Expr::Pop(name, line) => self.pop(name, *line)?,
+            Expr::Struct(name, fields) => {
+                let Some(def) = self.structs.get(name.as_str()).copied() else {
+                    unreachable!("literal of undeclared struct `{}`", name);
+                };
+                let mut values = Vec::with_capacity(fields.len());
+                for (field, expr) in fields {
+                    values.push((field.as_str(), self.eval(expr)?));
+                }
+                // Fields are kept in declaration order, whatever order the literal uses.
+                let fields = def.fields.iter().map(|(field, typ)| {
+                    let value = values.iter().find(|(name, _)| name == field);
+                    let value =
+                        value.map_or(Value::Int(0), |(_, value)| convert(value.clone(), typ));
+                    (field.clone(), value)
+                });
+                Value::Struct(def.name, fields.collect())
+            }
+            Expr::Field(var, field) => match self.field(var, field) {
+                Some((_, value)) => value.clone(),
+                None => unreachable!("read of unknown field `{}.{}`", var, field),
+            },
+        })
+    }
+
+    /// Integer operations stay integers and wrap; anything involving a float is done in floats.
+    fn arith(
+        &self,
+        left: Value,
+        op: BinOp,
+        right: Value,
+        line: i32,
+    ) -> Result<Value, RuntimeError> {
+        if let (Value::Int(left), Value::Int(right)) = (&left, &right) {
+            return Ok(Value::Int(match op {
+                BinOp::Add => left.wrapping_add(*right),
+                BinOp::Sub => left.wrapping_sub(*right),
+                BinOp::Mul => left.wrapping_mul(*right),
+                BinOp::Div if *right == 0 => return Err(RuntimeError::DivisionByZero { line }),
+                BinOp::Div => left.wrapping_div(*right),
+            }));
+        }
+        let (left, right) = (as_float(&left), as_float(&right));
+        Ok(Value::Float(match op {
+            BinOp::Add => left + right,
+            BinOp::Sub => left - right,
+            BinOp::Mul => left * right,
+            BinOp::Div => left / right,
+        }))
+    }
+
+    pub fn eval_cond(&mut self, cond: &Condition) -> Result<bool, RuntimeError> {
+        Ok(match cond {
+            Condition::Compare(ChildCond {
+                left,
+                operator,
+                right,
+                ..
+            }) => {
+                let order = compare(&self.eval(left)?, &self.eval(right)?);
+                match operator {
+                    CondToks::Equal => order == Some(Ordering::Equal),
+                    CondToks::NotEqual => order != Some(Ordering::Equal),
+                    CondToks::GreaterThan => order == Some(Ordering::Greater),
+                    CondToks::LessThan => order == Some(Ordering::Less),
+                    CondToks::GreaterThanOrEqual => {
+                        matches!(order, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                    CondToks::LessThanOrEqual => {
+                        matches!(order, Some(Ordering::Less | Ordering::Equal))
+                    }
+                }
+            }
+            Condition::Value(value, _) => self.eval(value)? == Value::Bool(true),
+            Condition::Logical(left, LogicalJoin::And, right) => {
+                self.eval_cond(left)? && self.eval_cond(right)?
+            }
+            Condition::Logical(left, LogicalJoin::Or, right) => {
+                self.eval_cond(left)? || self.eval_cond(right)?
+            }
+            Condition::Not(inner) => !self.eval_cond(inner)?,
+            Condition::Group(inner) => self.eval_cond(inner)?,
+        })
+    }
+}
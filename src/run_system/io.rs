@@ -0,0 +1,164 @@
+// Counterparts of the nulibc print and input routines, so `neit run` behaves like a built binary.
+use std::io::{self, BufRead, Cursor, Write};
+
+/// Formats a float like nulibc's `write_float`: rounded to six decimals, or `inf`, `-inf` or
+/// `nan`.
+pub fn format_float(num: f64) -> String {
+    if num.is_nan() {
+        "nan".to_string()
+    } else {
+        format!("{:.6}", num)
+    }
+}
+
+/// Reads one line without its newline, or `None` once the input has ended.
+fn read_line_from(input: &mut impl BufRead) -> Option<String> {
+    let mut buf = Vec::new();
+    match input.read_until(b'\n', &mut buf) {
+        Ok(0) | Err(_) => return None,
+        Ok(_) => {}
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    }
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Reads one line from stdin without its newline, or `None` once the input has ended.
+pub fn try_read_line() -> Option<String> {
+    read_line_from(&mut io::stdin().lock())
+}
+
+/// Where a running program reads its input and writes its output.
+#[derive(Default)]
+pub enum Io {
+    /// The process's stdin, stdout and stderr.
+    #[default]
+    Std,
+    /// Reads from a string and keeps what is written, so a program can run without a terminal.
+    Buffered {
+        input: Cursor<Vec<u8>>,
+        stdout: String,
+        stderr: String,
+    },
+}
+
+impl Io {
+    /// Buffered I/O that reads `input`.
+    pub fn buffered(input: &str) -> Io {
+        Io::Buffered {
+            input: Cursor::new(input.as_bytes().to_vec()),
+            stdout: String::new(),
+            stderr: String::new(),
+        }
+    }
+
+    /// What was written to stdout; empty for [`Io::Std`].
+    pub fn stdout(&self) -> &str {
+        match self {
+            Io::Std => "",
+            Io::Buffered { stdout, .. } => stdout,
+        }
+    }
+
+    /// What was written to stderr; empty for [`Io::Std`].
+    pub fn stderr(&self) -> &str {
+        match self {
+            Io::Std => "",
+            Io::Buffered { stderr, .. } => stderr,
+        }
+    }
+
+    /// Writes text to stdout (`1`) or stderr (`2`).
+    pub fn write(&mut self, fd: i32, text: &str) {
+        match self {
+            Io::Std if fd == 2 => {
+                let mut err = io::stderr().lock();
+                let _ = err.write_all(text.as_bytes());
+                let _ = err.flush();
+            }
+            Io::Std => {
+                let mut out = io::stdout().lock();
+                let _ = out.write_all(text.as_bytes());
+                let _ = out.flush();
+            }
+            Io::Buffered { stderr, .. } if fd == 2 => stderr.push_str(text),
+            Io::Buffered { stdout, .. } => stdout.push_str(text),
+        }
+    }
+
+    /// Reads one line without its newline, like `ninput`. An empty string at end of input.
+    pub fn read_line(&mut self) -> String {
+        match self {
+            Io::Std => try_read_line(),
+            Io::Buffered { input, .. } => read_line_from(input),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Reads a line holding an integer between `min` and `max`, like `ninput_int`.
+    /// Returns the line itself when it does not fit.
+    pub fn input_int(&mut self, min: i64, max: i64) -> Result<i64, String> {
+        let line = self.read_line();
+        match strtoll(trim_number_end(&line)) {
+            Some(val) if (min..=max).contains(&val) => Ok(val),
+            _ => Err(line),
+        }
+    }
+
+    /// Reads a line holding a floating point number, like `ninput_float`.
+    pub fn input_float(&mut self) -> Result<f64, String> {
+        let line = self.read_line();
+        let text =
+            trim_number_end(&line).trim_start_matches([' ', '\t', '\n', '\x0b', '\x0c', '\r']);
+        text.parse().map_err(|_| line)
+    }
+
+    /// Reads a line holding `true` or `false`, like `ninput_bool`.
+    pub fn input_bool(&mut self) -> Result<bool, String> {
+        let line = self.read_line();
+        match line.as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(line),
+        }
+    }
+
+    /// Reads a line holding exactly one byte, like `ninput_ch`.
+    pub fn input_ch(&mut self) -> Result<char, String> {
+        let line = self.read_line();
+        match line.as_bytes() {
+            [byte] => Ok(*byte as char),
+            _ => Err(line),
+        }
+    }
+}
+
+/// Drops the spaces, tabs and carriage returns nulibc accepts after a number.
+fn trim_number_end(text: &str) -> &str {
+    text.trim_end_matches([' ', '\t', '\r'])
+}
+
+/// Parses an integer the way `strtoll` does: leading whitespace, an optional sign, then digits.
+/// Values past the `i64` range saturate, as `strtoll` clamps them.
+fn strtoll(text: &str) -> Option<i64> {
+    let text = text.trim_start_matches([' ', '\t', '\n', '\x0b', '\x0c', '\r']);
+    let (negative, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut val: i64 = 0;
+    for digit in digits.bytes() {
+        let digit = (digit - b'0') as i64;
+        val = if negative {
+            val.saturating_mul(10).saturating_sub(digit)
+        } else {
+            val.saturating_mul(10).saturating_add(digit)
+        };
+    }
+    Some(val)
+}
@@ -0,0 +1,3 @@
+pub mod interp;
+pub mod io;
+pub mod repl;
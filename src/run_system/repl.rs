@@ -0,0 +1,185 @@
+use super::{interp::Interpreter, io::try_read_line};
+use crate::{
+    err_system::error_msg_gen::gen_error_msg,
+    helpers::{expr_parser::parse_typed_expr, symbol_table::SymbolTable, Expr},
+    parse_systems::{parse_program, AST},
+    tok_system::{
+        lexer::LexicalAnalysis,
+        tokens::{span_of, Token, TokenKind},
+    },
+    type_system::{is_array, is_value_type},
+};
+use colored::Colorize;
+use std::io::{self, Write};
+
+/// How many `{` are still waiting for their `}`.
+fn open_blocks(tokens: &[Token]) -> i32 {
+    tokens.iter().fold(0, |depth, tok| match tok.kind {
+        TokenKind::LCurly => depth + 1,
+        TokenKind::RCurly => depth - 1,
+        _ => depth,
+    })
+}
+
+/// Whether the input assigns to a variable, an element or a field (`x = 1`, `xs[0] += 2`,
+/// `p.x = 3`), which would otherwise read as an `=` comparison.
+fn is_assignment(tokens: &[Token]) -> bool {
+    let mut rest = tokens
+        .iter()
+        .filter(|tok| tok.kind != TokenKind::Space)
+        .skip_while(|tok| tok.kind == TokenKind::EOL)
+        .peekable();
+    if !rest
+        .next()
+        .is_some_and(|tok| matches!(tok.kind, TokenKind::Iden(_)))
+    {
+        return false;
+    }
+    if rest
+        .peek()
+        .is_some_and(|tok| tok.kind == TokenKind::LSquareBrac)
+    {
+        let mut depth = 0;
+        for tok in rest.by_ref() {
+            match tok.kind {
+                TokenKind::LSquareBrac => depth += 1,
+                TokenKind::RSquareBracket => depth -= 1,
+                TokenKind::EOL | TokenKind::EOF => return false,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+    match rest.next().map(|tok| &tok.kind) {
+        Some(TokenKind::EqSign) => true,
+        Some(TokenKind::ADDOP | TokenKind::SUBOP | TokenKind::MULTIOP | TokenKind::DIVOP) => {
+            rest.next().is_some_and(|tok| tok.kind == TokenKind::EqSign)
+        }
+        _ => false,
+    }
+}
+
+/// Keeps the variables, functions and values of every statement entered so far.
+struct Session {
+    collected_vars: SymbolTable,
+    interp: Interpreter<'static>,
+    /// The source of every statement that ran, which the spans of earlier declarations point
+    /// into.
+    history: String,
+}
+
+impl Session {
+    /// Runs one complete input: a bare expression has its value printed, anything else is
+    /// parsed as statements and executed.
+    fn eval_input(&mut self, input: &String, tokens: &[Token]) {
+        let expr_tokens: Vec<Token> = tokens
+            .iter()
+            .filter(|tok| !matches!(tok.kind, TokenKind::EOL | TokenKind::EOF))
+            .cloned()
+            .collect();
+        let mut collected_errors = Vec::new();
+        let at = span_of(tokens).unwrap_or_default();
+        let parsed = if is_assignment(tokens) {
+            None
+        } else {
+            parse_typed_expr(
+                &expr_tokens,
+                &self.collected_vars,
+                &mut collected_errors,
+                at,
+            )
+        };
+        if let Some((expr, typ)) = parsed {
+            // An array or struct variable has no value type of its own, but is still shown
+            // whole.
+            let whole = |typ: &str| is_array(typ) || self.collected_vars.struct_def(typ).is_some();
+            let whole_var = match &expr {
+                Expr::Var(name) => self
+                    .collected_vars
+                    .lookup(name)
+                    .is_some_and(|typ| whole(typ.trim_start_matches("const;"))),
+                _ => false,
+            };
+            if collected_errors.is_empty() && (is_value_type(typ) || whole(typ) || whole_var) {
+                match self.interp.eval(&expr) {
+                    Ok(value) => println!("{}", value),
+                    Err(err) => eprintln!("{}", err),
+                }
+                return;
+            }
+        }
+
+        // A failed input leaves no trace in the session.
+        collected_errors.clear();
+        let snapshot = self.collected_vars.clone();
+        // The input is lexed after the ones before it, so an error can also quote the earlier
+        // declaration it notes.
+        let source = format!("{}{}", self.history, input);
+        let mut tokens: Vec<Token> = Vec::new();
+        tokens.run_lexical_analysis(&source);
+        let start = tokens.partition_point(|tok| tok.span.start < self.history.len());
+        let ast = parse_program(
+            &tokens[start..],
+            &source,
+            &mut self.collected_vars,
+            &mut collected_errors,
+        );
+        if !collected_errors.is_empty() {
+            for err in collected_errors {
+                println!("{}", gen_error_msg(err, &source));
+            }
+            self.collected_vars = snapshot;
+            return;
+        }
+        // Functions declared here stay callable from later inputs.
+        let ast: &'static [AST] = Box::leak(ast.into_boxed_slice());
+        match self.interp.run(ast) {
+            Ok(()) => self.history = source,
+            Err(err) => {
+                eprintln!("{}", err);
+                self.collected_vars = snapshot;
+            }
+        }
+    }
+}
+
+/// `neit repl`: reads statements from stdin and runs each one as soon as it is complete.
+pub fn repl() {
+    println!("{}", "┌[*] Neit REPL".blue());
+    println!(
+        "{}",
+        "└─ Blocks continue until their `}`; type `exit` or press Ctrl-D to leave.".blue()
+    );
+    let mut session = Session {
+        collected_vars: SymbolTable::default(),
+        interp: Interpreter::default(),
+        history: String::new(),
+    };
+    let mut input = String::new();
+    loop {
+        print!("{}", if input.is_empty() { "neit> " } else { "....> " });
+        let _ = io::stdout().flush();
+        let Some(line) = try_read_line() else {
+            println!();
+            break;
+        };
+        if input.is_empty() && line.trim() == "exit" {
+            break;
+        }
+        input.push_str(&line);
+        input.push('\n');
+
+        let mut tokens: Vec<Token> = Vec::new();
+        tokens.run_lexical_analysis(&input);
+        if open_blocks(&tokens) > 0 {
+            continue;
+        }
+        if span_of(&tokens).is_none() {
+            input.clear();
+            continue;
+        }
+        session.eval_input(&std::mem::take(&mut input), &tokens);
+    }
+}
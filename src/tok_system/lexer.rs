@@ -1,4 +1,39 @@
-use super::tokens::Token;
+use super::tokens::{Comment, Span, Token, TokenKind};
+
+/// Whether the line being lexed is a print statement, so what follows its command is printed
+/// text. The command is the first word on the line, or the first after a brace.
+fn in_print_text(tokens: &[Token]) -> bool {
+    let line_start = tokens
+        .iter()
+        .rposition(|tok| tok.kind == TokenKind::EOL)
+        .map_or(0, |at| at + 1);
+    let mut statement_start = true;
+    for tok in &tokens[line_start..] {
+        match &tok.kind {
+            TokenKind::Space => {}
+            TokenKind::LCurly | TokenKind::RCurly => statement_start = true,
+            TokenKind::Iden(word)
+                if statement_start
+                    && matches!(word.as_str(), "print" | "println" | "eprint" | "eprintln") =>
+            {
+                return true;
+            }
+            _ => statement_start = false,
+        }
+    }
+    false
+}
+
+/// Whether the `#` at `at` starts a word of its own: it begins one, and it or its `##` is
+/// followed by a space or the end of the file.
+fn comment_word(code: &str, at: usize, word: &str) -> bool {
+    let marker = if code[at..].starts_with("##") { 2 } else { 1 };
+    word.is_empty()
+        && code[at + marker..]
+            .chars()
+            .next()
+            .is_none_or(char::is_whitespace)
+}
 
 pub trait LexicalAnalysis {
     fn run_lexical_analysis(&mut self, code: &str);
@@ -8,101 +43,180 @@ impl LexicalAnalysis for Vec<Token> {
     #[inline(always)]
     fn run_lexical_analysis(&mut self, code: &str) {
         let mut word = String::with_capacity(16);
-        let mut chars = code.chars().peekable();
+        // Where the word being built starts.
+        let mut word_span = Span::default();
+        let mut chars = code.char_indices().peekable();
+        let (mut line, mut col) = (1, 1);
+        // Comments waiting for the token after them.
+        let mut comments = Vec::new();
+        let mut prev = None;
+
+        #[inline(always)]
+        fn push(tokens: &mut Vec<Token>, kind: TokenKind, span: Span, comments: &mut Vec<Comment>) {
+            let mut token = Token::new(kind, span);
+            token.comments = std::mem::take(comments);
+            tokens.push(token);
+        }
 
         #[inline(always)]
-        fn flush_word(word: &mut String, tokens: &mut Vec<Token>) {
+        fn flush_word(
+            word: &mut String,
+            word_span: Span,
+            tokens: &mut Vec<Token>,
+            comments: &mut Vec<Comment>,
+        ) {
             if !word.is_empty() {
-                tokens.push(Token::Iden(std::mem::take(word)));
+                let span = Span {
+                    end: word_span.start + word.len(),
+                    ..word_span
+                };
+                push(
+                    tokens,
+                    TokenKind::Iden(std::mem::take(word)),
+                    span,
+                    comments,
+                );
             }
         }
 
-        while let Some(c) = chars.next() {
-            match c {
-                '(' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::LSmallBrac);
-                }
-                ')' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::RSmallBracket);
-                }
-                '!' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::Not);
-                }
-                '|' if chars.peek() == Some(&'|') => {
-                    flush_word(&mut word, self);
-                    chars.next();
-                    self.push(Token::Or);
-                }
-                '>' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::GreaterThan);
-                }
-                '<' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::LessThan);
-                }
-                '&' if chars.peek() == Some(&'&') => {
-                    flush_word(&mut word, self);
-                    chars.next();
-                    self.push(Token::And);
-                }
-                '{' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::LCurly);
-                }
-                '}' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::RCurly);
-                }
-                '%' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::PercentSign);
-                }
-                '=' => {
-                    flush_word(&mut word, self);
-                    if chars.peek() == Some(&'=') {
-                        chars.next();
-                        self.push(Token::DoubleEqSign);
-                    } else {
-                        self.push(Token::EqSign);
+        while let Some((at, c)) = chars.next() {
+            let mut span = Span {
+                start: at,
+                end: at + c.len_utf8(),
+                line,
+                col,
+            };
+            col += 1;
+            let char_literal = prev == Some('\'') && chars.peek().map(|(_, c)| *c) == Some('\'');
+            prev = Some(c);
+            // A string runs to its closing quote, or to the end of the line if it has none. An
+            // escaped quote does not close it.
+            if c == '"' && !char_literal {
+                flush_word(&mut word, word_span, self, &mut comments);
+                let mut raw = String::new();
+                let mut closed = false;
+                while let Some((at, c)) = chars.next_if(|(_, c)| *c != '\n') {
+                    col += 1;
+                    span.end = at + c.len_utf8();
+                    if c == '"' {
+                        closed = true;
+                        break;
+                    }
+                    raw.push(c);
+                    if c == '\\' {
+                        if let Some((at, c)) = chars.next_if(|(_, c)| *c != '\n') {
+                            col += 1;
+                            span.end = at + c.len_utf8();
+                            raw.push(c);
+                        }
                     }
                 }
-                ' ' | '\t' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::Space);
+                push(self, TokenKind::Str { raw, closed }, span, &mut comments);
+                continue;
+            }
+            // `#` runs to the end of the line; `##` runs to the next `##`, or the end of the file.
+            // In printed text a `#` is kept, as in `C#` or `#42`, unless it starts a word of its
+            // own: `println done # log`.
+            if c == '#' && !char_literal && (comment_word(code, at, &word) || !in_print_text(self))
+            {
+                flush_word(&mut word, word_span, self, &mut comments);
+                let block = chars.next_if(|(_, c)| *c == '#').is_some();
+                let mut end = at + if block { 2 } else { 1 };
+                let mut closed = !block;
+                if block {
+                    col += 1;
+                    while let Some((at, c)) = chars.next() {
+                        end = at + c.len_utf8();
+                        if c == '\n' {
+                            line += 1;
+                            col = 1;
+                            continue;
+                        }
+                        col += 1;
+                        if c == '#' && chars.next_if(|(_, c)| *c == '#').is_some() {
+                            col += 1;
+                            end += 1;
+                            closed = true;
+                            break;
+                        }
+                    }
+                } else {
+                    while let Some((at, c)) = chars.next_if(|(_, c)| *c != '\n') {
+                        col += 1;
+                        end = at + c.len_utf8();
+                    }
                 }
+                comments.push(Comment {
+                    text: code[at..end].trim_end().to_string(),
+                    span: Span { end, ..span },
+                    block,
+                    closed,
+                });
+                prev = None;
+                continue;
+            }
+            // Two-character operators take the second character along.
+            let mut pair = |second: char, span: &mut Span, col: &mut i32| {
+                if chars.peek().map(|(_, c)| *c) == Some(second) {
+                    chars.next();
+                    span.end += 1;
+                    *col += 1;
+                    true
+                } else {
+                    false
+                }
+            };
+            let kind = match c {
+                '(' => TokenKind::LSmallBrac,
+                ')' => TokenKind::RSmallBracket,
+                '[' => TokenKind::LSquareBrac,
+                ']' => TokenKind::RSquareBracket,
+                '!' => TokenKind::Not,
+                '|' if pair('|', &mut span, &mut col) => TokenKind::Or,
+                '>' => TokenKind::GreaterThan,
+                '<' => TokenKind::LessThan,
+                '&' if pair('&', &mut span, &mut col) => TokenKind::And,
+                '{' => TokenKind::LCurly,
+                '}' => TokenKind::RCurly,
+                '%' => TokenKind::PercentSign,
+                '=' if pair('=', &mut span, &mut col) => TokenKind::DoubleEqSign,
+                '=' => TokenKind::EqSign,
+                ' ' | '\t' => TokenKind::Space,
                 '\n' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::EOL);
-                }
-                '\r' => {}
-                '\\' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::BackSlash);
-                }
-                '+' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::ADDOP);
-                }
-                '-' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::SUBOP);
-                }
-                '/' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::DIVOP);
-                }
-                '*' => {
-                    flush_word(&mut word, self);
-                    self.push(Token::MULTIOP);
+                    line += 1;
+                    col = 1;
+                    TokenKind::EOL
+                }
+                '\r' => continue,
+                '\\' => TokenKind::BackSlash,
+                '+' => TokenKind::ADDOP,
+                '-' => TokenKind::SUBOP,
+                '/' => TokenKind::DIVOP,
+                '*' => TokenKind::MULTIOP,
+                ',' => TokenKind::Comma,
+                ':' => TokenKind::Colon,
+                _ => {
+                    if word.is_empty() {
+                        word_span = span;
+                    }
+                    word.push(c);
+                    continue;
                 }
-                _ => word.push(c),
-            }
+            };
+            flush_word(&mut word, word_span, self, &mut comments);
+            push(self, kind, span, &mut comments);
         }
-        flush_word(&mut word, self);
-        self.push(Token::EOF);
+        flush_word(&mut word, word_span, self, &mut comments);
+        push(
+            self,
+            TokenKind::EOF,
+            Span {
+                start: code.len(),
+                end: code.len(),
+                line,
+                col,
+            },
+            &mut comments,
+        );
     }
 }
@@ -1,8 +1,118 @@
+use std::ops::Range;
+
+/// A range of source code: byte offsets, plus the 1-based line and column it starts at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: i32,
+    pub col: i32,
+}
+
+impl Span {
+    /// The span from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: other.end.max(self.end),
+            ..self
+        }
+    }
+
+    /// Line and column just past the end of the span, found by walking `code` from its start.
+    pub fn end_pos(&self, code: &str) -> (i32, i32) {
+        let before = &code[..self.end.min(code.len())];
+        let line = before.matches('\n').count() as i32 + 1;
+        let line_start = before.rfind('\n').map_or(0, |at| at + 1);
+        (line, before[line_start..].chars().count() as i32 + 1)
+    }
+}
+
+/// A token and where it was found.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+    /// Comments between the previous token and this one. The parser ignores them; they are kept
+    /// for tools such as the formatter.
+    pub comments: Vec<Comment>,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, span: Span) -> Self {
+        Token {
+            kind,
+            span,
+            comments: Vec::new(),
+        }
+    }
+}
+
+/// A `# ...` comment running to the end of its line, or a `## ... ##` block comment.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    /// The whole comment, markers included.
+    pub text: String,
+    pub span: Span,
+    pub block: bool,
+    /// Whether a block comment ends with its closing `##`; a line comment always does.
+    pub closed: bool,
+}
+
+/// The span from the first to the last token that is not blank, or `None` if all of them are.
+pub fn span_of(tokens: &[Token]) -> Option<Span> {
+    let mut text = tokens
+        .iter()
+        .filter(|tok| !matches!(tok.kind, TokenKind::Space | TokenKind::EOL | TokenKind::EOF));
+    let first = text.next()?.span;
+    Some(text.next_back().map_or(first, |last| first.to(last.span)))
+}
+
+/// Decodes the escapes in a string literal's source text: `\n`, `\t`, `\"`, `\\` and
+/// `\u{...}` with 1 to 6 hex digits. On a bad escape, returns its byte range in `raw`.
+pub fn unescape(raw: &str) -> Result<String, Range<usize>> {
+    let mut text = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, '"')) => '"',
+            Some((_, '\\')) => '\\',
+            Some((_, 'u')) if chars.next_if(|(_, c)| *c == '{').is_some() => {
+                let mut digits = String::new();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                    digits.push(c);
+                }
+                let close = chars.next_if(|(_, c)| *c == '}');
+                let end = chars.peek().map_or(raw.len(), |(at, _)| *at);
+                let code = u32::from_str_radix(&digits, 16).ok();
+                match code.and_then(char::from_u32) {
+                    Some(c) if close.is_some() && digits.len() <= 6 => c,
+                    _ => return Err(at..end),
+                }
+            }
+            Some((next, c)) => return Err(at..next + c.len_utf8()),
+            None => return Err(at..raw.len()),
+        };
+        text.push(escaped);
+    }
+    Ok(text)
+}
+
 #[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+pub enum TokenKind {
     Iden(String),
+    /// A `"..."` string literal: the source text between the quotes, escapes not yet decoded.
+    /// `closed` is false when the line ends before the closing quote.
+    Str {
+        raw: String,
+        closed: bool,
+    },
     Space,
-    Quote,
     BackSlash,
     EqSign,
     ADDOP,
@@ -22,4 +132,8 @@ pub enum Token {
     LessThan,
     LSmallBrac,
     RSmallBracket,
+    LSquareBrac,
+    RSquareBracket,
+    Comma,
+    Colon,
 }
@@ -0,0 +1,231 @@
+// The type check run on a parsed program before it is compiled or run. It walks the AST with
+// its own symbol table and reports every value that does not fit where it is used.
+use super::{
+    array_elem, check_cond, check_store, expr_type, is_array, is_int, is_value_type, store_error,
+    INTEGER, VALUE,
+};
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        fn_calls::fn_tag,
+        symbol_table::{ScopeKind, SymbolTable, UNKNOWN},
+        Condition, Expr,
+    },
+    parse_systems::{FuncDef, PrintTokTypes, Variables, AST},
+};
+
+/// Checks the types of a parsed program, pushing what is wrong to `collected_errors`.
+/// `collected_vars` holds what was declared before the program, such as the earlier inputs of
+/// a REPL session.
+pub fn check(ast: &[AST], collected_vars: &SymbolTable, collected_errors: &mut Vec<ErrTypes>) {
+    let mut vars = collected_vars.clone();
+    check_block(ast, &mut vars, collected_errors);
+}
+
+/// Checks a block body inside a scope of its own.
+fn check_scope(
+    body: &[AST],
+    vars: &mut SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    kind: ScopeKind,
+) {
+    vars.push_scope(kind);
+    check_block(body, vars, collected_errors);
+    vars.pop_scope();
+}
+
+fn check_fn(def: &FuncDef, vars: &mut SymbolTable, collected_errors: &mut Vec<ErrTypes>) {
+    let ret = def.ret.unwrap_or("void");
+    // Declared before the body, which may call itself.
+    vars.declare(def.name.clone(), fn_tag(ret, &def.params));
+    // As in the parser, the body only sees functions and its own names.
+    let mut fn_vars = vars.functions();
+    fn_vars.push_scope(ScopeKind::Function(ret));
+    for (name, typ) in &def.params {
+        fn_vars.declare(name.clone(), typ);
+    }
+    check_block(&def.body, &mut fn_vars, collected_errors);
+    if def.ret.is_some() && !always_returns(&def.body) {
+        collected_errors.push(ErrTypes::MissingReturn(def.span));
+    }
+}
+
+/// Whether a block cannot reach its end: it returns, every branch of an `if` chain with an
+/// `else` does, or it loops `while true` without a `break`.
+fn always_returns(body: &[AST]) -> bool {
+    // While in an `if` chain, whether every branch so far returns.
+    let mut chain = None;
+    for node in body {
+        match node {
+            AST::Return(..) => return true,
+            AST::IF(body, _) => chain = Some(always_returns(body)),
+            AST::ElseIf(body, _) => chain = chain.map(|all| all && always_returns(body)),
+            AST::Else(body) => {
+                if chain == Some(true) && always_returns(body) {
+                    return true;
+                }
+                chain = None;
+            }
+            AST::While(body, Condition::Value(Expr::Bool(true), _)) if !breaks(body) => {
+                return true
+            }
+            _ => chain = None,
+        }
+    }
+    false
+}
+
+/// Whether a loop body has a `break` that leaves it, outside of any inner loop.
+fn breaks(body: &[AST]) -> bool {
+    body.iter().any(|node| match node {
+        AST::Break => true,
+        AST::IF(body, _) | AST::ElseIf(body, _) | AST::Else(body) => breaks(body),
+        _ => false,
+    })
+}
+
+fn check_block(ast: &[AST], vars: &mut SymbolTable, collected_errors: &mut Vec<ErrTypes>) {
+    for node in ast {
+        match node {
+            AST::Var(Variables::MATH(name, expr), typ, span) => {
+                let declared = typ.trim_start_matches("const;");
+                let typ = if is_array(declared) || vars.struct_def(declared).is_some() {
+                    check_store(declared, expr, vars, collected_errors, *span);
+                    typ
+                } else {
+                    match expr_type(expr, vars, collected_errors, *span) {
+                        Some(found) if !is_value_type(found) => {
+                            collected_errors.push(ErrTypes::TypeMismatch(*span, VALUE, found));
+                            UNKNOWN
+                        }
+                        Some(found) if is_value_type(declared) => {
+                            if let Some(err) = store_error(declared, expr, found, *span) {
+                                collected_errors.push(err);
+                            }
+                            typ
+                        }
+                        _ => UNKNOWN,
+                    }
+                };
+                vars.declare(name.clone(), typ);
+            }
+            // Literals were given their types by the parser.
+            AST::Var(var, typ, _) => vars.declare(var.name().to_string(), typ),
+            AST::VarAssign(Variables::MATH(name, expr), span) => {
+                let Some(target) = vars.lookup(name).filter(|typ| {
+                    is_value_type(typ) || is_array(typ) || vars.struct_def(typ).is_some()
+                }) else {
+                    continue;
+                };
+                check_store(target, expr, vars, collected_errors, *span);
+            }
+            AST::SetIndex {
+                array,
+                index,
+                value,
+                spans: [target, span],
+                ..
+            } => {
+                let element = Expr::Index(array.clone(), Box::new(index.clone()), 0);
+                if let Some(elem) = expr_type(&element, vars, collected_errors, *target) {
+                    check_store(elem, value, vars, collected_errors, *span);
+                }
+            }
+            AST::Push(array, value, span) => {
+                if let Some(elem) = array_elem(array, true, vars, collected_errors, *span) {
+                    check_store(elem, value, vars, collected_errors, *span);
+                }
+            }
+            AST::Pop(array, _, span) => {
+                array_elem(array, true, vars, collected_errors, *span);
+            }
+            AST::Struct(def) => vars.declare_struct(def.clone()),
+            AST::SetField {
+                var,
+                field,
+                value,
+                spans: [target, span],
+            } => {
+                let field = Expr::Field(var.clone(), field.clone());
+                if let Some(typ) = expr_type(&field, vars, collected_errors, *target) {
+                    check_store(typ, value, vars, collected_errors, *span);
+                }
+            }
+            AST::VarAssign(..) => {}
+            AST::Input {
+                var, typ, declare, ..
+            } => {
+                if *declare {
+                    vars.declare(var.clone(), typ);
+                }
+            }
+            AST::For {
+                var,
+                typ,
+                start,
+                end,
+                step,
+                bounds,
+                body,
+                ..
+            } => {
+                for (bound, span) in [start, end, step].into_iter().zip(bounds) {
+                    match expr_type(bound, vars, collected_errors, *span) {
+                        Some(found) if !is_int(found) => {
+                            collected_errors.push(ErrTypes::TypeMismatch(*span, INTEGER, found))
+                        }
+                        _ => {}
+                    }
+                }
+                vars.push_scope(ScopeKind::Loop);
+                vars.declare(var.clone(), typ);
+                check_block(body, vars, collected_errors);
+                vars.pop_scope();
+            }
+            AST::While(body, cond) => {
+                check_cond(cond, vars, collected_errors);
+                check_scope(body, vars, collected_errors, ScopeKind::Loop);
+            }
+            AST::IF(body, cond) | AST::ElseIf(body, cond) => {
+                check_cond(cond, vars, collected_errors);
+                check_scope(body, vars, collected_errors, ScopeKind::Block);
+            }
+            AST::Else(body) => check_scope(body, vars, collected_errors, ScopeKind::Block),
+            AST::Func(def) => check_fn(def, vars, collected_errors),
+            AST::Return(Some(expr), span) => {
+                let ret = vars.return_type().unwrap_or("void");
+                let Some(found) = expr_type(expr, vars, collected_errors, *span) else {
+                    continue;
+                };
+                if ret == "void" {
+                    collected_errors.push(ErrTypes::TypeMismatch(*span, ret, found));
+                } else if let Some(err) = store_error(ret, expr, found, *span) {
+                    collected_errors.push(err);
+                }
+            }
+            AST::Call(name, args, span) => {
+                let call = Expr::Call(name.clone(), args.clone());
+                expr_type(&call, vars, collected_errors, *span);
+            }
+            AST::Print { text, .. } => {
+                for ptok in text {
+                    match ptok {
+                        PrintTokTypes::Call(name, args, span) => {
+                            let call = Expr::Call(name.clone(), args.clone());
+                            if let Some(found @ "void") =
+                                expr_type(&call, vars, collected_errors, *span)
+                            {
+                                collected_errors.push(ErrTypes::TypeMismatch(*span, VALUE, found));
+                            }
+                        }
+                        PrintTokTypes::Expr(expr, span) => {
+                            expr_type(expr, vars, collected_errors, *span);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            AST::Return(None, _) | AST::Break | AST::Continue => {}
+        }
+    }
+}
@@ -0,0 +1,428 @@
+// Neit's typing rules: what type each expression has, how arithmetic widens, and which values
+// can be stored where. The parser uses them to give declarations their types; `checker` walks
+// the finished AST and reports every value that breaks them.
+use crate::{
+    err_system::err_types::ErrTypes,
+    helpers::{
+        fn_calls::{fn_signature, lookup_fn},
+        symbol_table::{SymbolTable, UNKNOWN},
+        BinOp, CondToks, Condition, Expr, UnaryOp,
+    },
+    tok_system::tokens::Span,
+};
+
+pub mod checker;
+
+/// Types a variable, parameter or return value can be declared with.
+pub const VALUE_TYPES: [&str; 9] = ["i8", "i16", "i32", "i64", "f32", "f64", "str", "ch", "bool"];
+
+/// Numeric types from narrowest to widest. A number widens implicitly to any type after its
+/// own, so every integer fits a float, but a float never fits an integer.
+pub const NUMERIC_RANK: [&str; 6] = ["i8", "i16", "i32", "i64", "f32", "f64"];
+
+// What a type mismatch expects when more than one type would do.
+pub const NUMBER: &str = "number";
+pub const INTEGER: &str = "integer";
+pub const VALUE: &str = "value";
+pub const ARRAY: &str = "array";
+pub const GROWABLE: &str = "growable array";
+
+/// Type of the empty array literal `[]`, which only fits where an array type is declared.
+pub const EMPTY_ARRAY: &str = "[]";
+
+#[inline(always)]
+fn rank(typ: &str) -> Option<usize> {
+    NUMERIC_RANK.iter().position(|t| *t == typ)
+}
+
+#[inline(always)]
+pub fn is_numeric(typ: &str) -> bool {
+    rank(typ).is_some()
+}
+
+#[inline(always)]
+pub fn is_int(typ: &str) -> bool {
+    matches!(typ, "i8" | "i16" | "i32" | "i64")
+}
+
+/// Whether a type can be held by a variable: a number, `str`, `ch` or `bool`, but not `void`.
+#[inline(always)]
+pub fn is_value_type(typ: &str) -> bool {
+    VALUE_TYPES.contains(&typ)
+}
+
+/// The type a name such as `i64` in `may x: i64` stands for, if it is one.
+#[inline(always)]
+pub fn value_type(name: &str) -> Option<&'static str> {
+    VALUE_TYPES.iter().find(|typ| **typ == name).copied()
+}
+
+/// The type of an array holding `elem` values: `[i32]` for a growable one, or `[i32; 3]` for
+/// one of fixed length.
+pub fn array_type(elem: &str, len: Option<usize>) -> &'static str {
+    let typ = match len {
+        Some(len) => format!("[{}; {}]", elem, len),
+        None => format!("[{}]", elem),
+    };
+    Box::leak(typ.into_boxed_str())
+}
+
+/// The type of the elements of an array type, or `None` if `typ` is not one.
+pub fn elem_type(typ: &str) -> Option<&'static str> {
+    let inner = typ.strip_prefix('[')?.strip_suffix(']')?;
+    value_type(inner.split(';').next()?.trim())
+}
+
+/// The length of a fixed-length array type.
+pub fn fixed_len(typ: &str) -> Option<usize> {
+    let inner = typ.strip_prefix('[')?.strip_suffix(']')?;
+    inner.split_once(';')?.1.trim().parse().ok()
+}
+
+#[inline(always)]
+pub fn is_array(typ: &str) -> bool {
+    elem_type(typ).is_some()
+}
+
+/// Whether a type is an array that `push` and `pop` can change the length of.
+#[inline(always)]
+pub fn is_growable(typ: &str) -> bool {
+    is_array(typ) && fixed_len(typ).is_none()
+}
+
+/// The wider of two numeric types, which mixed arithmetic results in.
+#[inline(always)]
+pub fn wider(a: &'static str, b: &'static str) -> &'static str {
+    if rank(a) >= rank(b) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Type of an integer literal: `i32`, or `i64` for values that need it.
+pub fn int_literal_type(val: i64) -> &'static str {
+    if i32::try_from(val).is_ok() {
+        "i32"
+    } else {
+        "i64"
+    }
+}
+
+/// Whether an integer value can be stored in an integer type without losing it.
+pub fn int_fits(val: i64, typ: &str) -> bool {
+    int_bounds(typ).is_some_and(|(min, max)| (min..=max).contains(&val))
+}
+
+/// The smallest and largest values of an integer type.
+pub fn int_bounds(typ: &str) -> Option<(i64, i64)> {
+    match typ {
+        "i8" => Some((i8::MIN.into(), i8::MAX.into())),
+        "i16" => Some((i16::MIN.into(), i16::MAX.into())),
+        "i32" => Some((i32::MIN.into(), i32::MAX.into())),
+        "i64" => Some((i64::MIN, i64::MAX)),
+        _ => None,
+    }
+}
+
+/// The value of an integer constant such as `-(2 * 3)`, made only of integer literals. `None`
+/// for anything else, or if working it out overflows.
+pub fn const_int(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Int(val) => Some(*val),
+        Expr::Group(inner) => const_int(inner),
+        Expr::Unary(UnaryOp::Neg, inner) => const_int(inner)?.checked_neg(),
+        Expr::Binary(left, op, right, _) => {
+            let (left, right) = (const_int(left)?, const_int(right)?);
+            match op {
+                BinOp::Add => left.checked_add(right),
+                BinOp::Sub => left.checked_sub(right),
+                BinOp::Mul => left.checked_mul(right),
+                BinOp::Div => left.checked_div(right),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether a value of type `found` can be used where `expected` is wanted: the same type, or
+/// a number widening to a wider one.
+#[inline(always)]
+pub fn assignable(expected: &str, found: &str) -> bool {
+    expected == found || matches!((rank(expected), rank(found)), (Some(e), Some(f)) if f <= e)
+}
+
+/// The error for using `expr`, of type `found`, where `expected` is wanted, if it cannot be.
+/// Besides what [`assignable`] allows, an integer constant goes into any integer type it fits.
+pub fn store_error(
+    expected: &'static str,
+    expr: &Expr,
+    found: &'static str,
+    span: Span,
+) -> Option<ErrTypes> {
+    if assignable(expected, found) {
+        return None;
+    }
+    match const_int(expr) {
+        Some(val) if is_int(expected) && !int_fits(val, expected) => {
+            Some(ErrTypes::OutOfRange(span, expected))
+        }
+        Some(_) if is_numeric(expected) => None,
+        _ => Some(ErrTypes::TypeMismatch(span, expected, found)),
+    }
+}
+
+/// The type of a declaration's value, for the parser: what [`expr_type`] finds, or `UNKNOWN`
+/// when the value has a type error, which the checker reports.
+pub fn infer(expr: &Expr, collected_vars: &SymbolTable) -> &'static str {
+    expr_type(expr, collected_vars, &mut Vec::new(), Span::default()).unwrap_or(UNKNOWN)
+}
+
+/// Passes a numeric type through, or reports that a number was wanted.
+fn numeric(
+    typ: &'static str,
+    collected_errors: &mut Vec<ErrTypes>,
+    span: Span,
+) -> Option<&'static str> {
+    if !is_numeric(typ) {
+        collected_errors.push(ErrTypes::TypeMismatch(span, NUMBER, typ));
+        return None;
+    }
+    Some(typ)
+}
+
+/// Resolves the type of an expression. Type errors are pushed to `collected_errors`, pointing
+/// at `span`, and give `None`. So do names that are not declared or whose declaration failed,
+/// but quietly, since the parser has reported them.
+pub fn expr_type(
+    expr: &Expr,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    span: Span,
+) -> Option<&'static str> {
+    match expr {
+        Expr::Int(val) => Some(int_literal_type(*val)),
+        Expr::Float(_) => Some("f32"),
+        Expr::Str(_) => Some("str"),
+        Expr::Char(_) => Some("ch"),
+        Expr::Bool(_) => Some("bool"),
+        Expr::Var(name) => {
+            let typ = collected_vars.lookup(name)?.trim_start_matches("const;");
+            // Arrays are only used through their elements, `len`, `push` and `pop`, and
+            // structs through their fields.
+            if is_array(typ) || collected_vars.struct_def(typ).is_some() {
+                collected_errors.push(ErrTypes::TypeMismatch(span, VALUE, typ));
+                return None;
+            }
+            Some(typ).filter(|typ| is_value_type(typ))
+        }
+        Expr::Call(name, args) => {
+            let (ret, params) = lookup_fn(name, collected_vars).and_then(fn_signature)?;
+            if args.len() != params.len() {
+                return None;
+            }
+            for (arg, param) in args.iter().zip(params) {
+                let typ = expr_type(arg, collected_vars, collected_errors, span)?;
+                if let Some(err) = store_error(param, arg, typ, span) {
+                    collected_errors.push(err);
+                    return None;
+                }
+            }
+            Some(ret)
+        }
+        Expr::Unary(UnaryOp::Neg, operand) => {
+            let typ = expr_type(operand, collected_vars, collected_errors, span)?;
+            numeric(typ, collected_errors, span)
+        }
+        Expr::Binary(left_expr, _, right_expr, _) => {
+            let left = expr_type(left_expr, collected_vars, collected_errors, span)?;
+            let right = expr_type(right_expr, collected_vars, collected_errors, span)?;
+            let left = numeric(left, collected_errors, span)?;
+            let right = numeric(right, collected_errors, span)?;
+            // An integer constant takes the other side's type when it fits, so `small + 1`
+            // stays an `i8`.
+            match (const_int(left_expr), const_int(right_expr)) {
+                (Some(val), None) if is_int(right) && int_fits(val, right) => Some(right),
+                (None, Some(val)) if is_int(left) && int_fits(val, left) => Some(left),
+                _ => Some(wider(left, right)),
+            }
+        }
+        Expr::Group(inner) => expr_type(inner, collected_vars, collected_errors, span),
+        Expr::Cast(inner, typ) => {
+            let found = expr_type(inner, collected_vars, collected_errors, span)?;
+            numeric(found, collected_errors, span)?;
+            // Conversions may narrow, but a literal has to fit what it is converted to.
+            match const_int(inner) {
+                Some(val) if is_int(typ) && !int_fits(val, typ) => {
+                    collected_errors.push(ErrTypes::OutOfRange(span, typ));
+                    None
+                }
+                _ => Some(typ),
+            }
+        }
+        Expr::Cond(cond) => check_cond(cond, collected_vars, collected_errors).then_some("bool"),
+        Expr::Array(items) => {
+            // Items are numbers of any width, or values of one type.
+            let mut elem: Option<&'static str> = None;
+            for item in items {
+                let typ = expr_type(item, collected_vars, collected_errors, span)?;
+                let err = match elem {
+                    _ if !is_value_type(typ) => ErrTypes::TypeMismatch(span, VALUE, typ),
+                    Some(prev) if prev != typ && !(is_numeric(prev) && is_numeric(typ)) => {
+                        ErrTypes::TypeMismatch(span, prev, typ)
+                    }
+                    _ => {
+                        elem = Some(elem.map_or(typ, |prev| wider(prev, typ)));
+                        continue;
+                    }
+                };
+                collected_errors.push(err);
+                return None;
+            }
+            Some(elem.map_or(EMPTY_ARRAY, |elem| array_type(elem, Some(items.len()))))
+        }
+        Expr::Index(name, index, _) => {
+            let elem = array_elem(name, false, collected_vars, collected_errors, span)?;
+            let found = expr_type(index, collected_vars, collected_errors, span)?;
+            if !is_int(found) {
+                collected_errors.push(ErrTypes::TypeMismatch(span, INTEGER, found));
+                return None;
+            }
+            Some(elem)
+        }
+        Expr::Len(name) => {
+            array_elem(name, false, collected_vars, collected_errors, span)?;
+            Some("i32")
+        }
+        Expr::Pop(name, _) => array_elem(name, true, collected_vars, collected_errors, span),
+        Expr::Field(var, field) => {
+            let typ = collected_vars.lookup(var)?.trim_start_matches("const;");
+            collected_vars.struct_def(typ)?.field(field)
+        }
+        Expr::Struct(name, fields) => {
+            let def = collected_vars.struct_def(name)?;
+            let mut fits = true;
+            for (field, value) in fields {
+                let Some(expected) = def.field(field) else {
+                    continue;
+                };
+                let before = collected_errors.len();
+                check_store(expected, value, collected_vars, collected_errors, span);
+                fits &= collected_errors.len() == before;
+            }
+            fits.then_some(def.name)
+        }
+    }
+}
+
+/// The element type of the array variable `name`, which must be growable when `growable` is
+/// set. Anything else is reported as a type mismatch.
+pub fn array_elem(
+    name: &str,
+    growable: bool,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    span: Span,
+) -> Option<&'static str> {
+    let typ = collected_vars.lookup(name)?;
+    if typ == UNKNOWN {
+        return None;
+    }
+    let typ = typ.trim_start_matches("const;");
+    match elem_type(typ) {
+        Some(elem) if !growable || is_growable(typ) => Some(elem),
+        _ => {
+            let expected = if growable { GROWABLE } else { ARRAY };
+            collected_errors.push(ErrTypes::TypeMismatch(span, expected, typ));
+            None
+        }
+    }
+}
+
+/// Checks a value stored where `expected` is wanted, as in a declaration or an assignment,
+/// pushing what is wrong to `collected_errors`. An array literal is checked item by item, so
+/// integer constants go into narrower elements as they do into narrower variables.
+pub fn check_store(
+    expected: &'static str,
+    expr: &Expr,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+    span: Span,
+) {
+    if let (Some(elem), Expr::Array(items)) = (elem_type(expected), expr) {
+        if fixed_len(expected).is_some_and(|len| len != items.len()) {
+            let found = match infer(expr, collected_vars) {
+                UNKNOWN => array_type(elem, Some(items.len())),
+                found => found,
+            };
+            collected_errors.push(ErrTypes::TypeMismatch(span, expected, found));
+            return;
+        }
+        for item in items {
+            if let Some(found) = expr_type(item, collected_vars, collected_errors, span) {
+                if let Some(err) = store_error(elem, item, found, span) {
+                    collected_errors.push(err);
+                }
+            }
+        }
+        return;
+    }
+    if let Some(found) = expr_type(expr, collected_vars, collected_errors, span) {
+        if let Some(err) = store_error(expected, expr, found, span) {
+            collected_errors.push(err);
+        }
+    }
+}
+
+/// Checks the types in a condition, pushing what is wrong to `collected_errors`. Both sides of
+/// a comparison must be numbers, or values of the same type, and only numbers, strings and
+/// characters are ordered. A value on its own must be a `bool`. Returns whether it is sound.
+pub fn check_cond(
+    cond: &Condition,
+    collected_vars: &SymbolTable,
+    collected_errors: &mut Vec<ErrTypes>,
+) -> bool {
+    match cond {
+        Condition::Compare(child) => {
+            // Both sides share the comparison's span, so a bad left side is reported alone.
+            let Some(left) = expr_type(&child.left, collected_vars, collected_errors, child.span)
+            else {
+                return false;
+            };
+            let Some(right) = expr_type(&child.right, collected_vars, collected_errors, child.span)
+            else {
+                return false;
+            };
+            let ordered = !matches!(child.operator, CondToks::Equal | CondToks::NotEqual);
+            let err = if !is_value_type(left) {
+                ErrTypes::TypeMismatch(child.span, VALUE, left)
+            } else if left != right && !(is_numeric(left) && is_numeric(right)) {
+                ErrTypes::TypeMismatch(child.span, left, right)
+            } else if ordered && left == "bool" {
+                ErrTypes::TypeMismatch(child.span, NUMBER, left)
+            } else {
+                return true;
+            };
+            collected_errors.push(err);
+            false
+        }
+        Condition::Value(expr, span) => {
+            match expr_type(expr, collected_vars, collected_errors, *span) {
+                Some("bool") => true,
+                Some(found) => {
+                    collected_errors.push(ErrTypes::TypeMismatch(*span, "bool", found));
+                    false
+                }
+                None => false,
+            }
+        }
+        Condition::Logical(left, _, right) => {
+            // Both sides are checked, so each reports its own errors.
+            let left = check_cond(left, collected_vars, collected_errors);
+            check_cond(right, collected_vars, collected_errors) && left
+        }
+        Condition::Not(inner) | Condition::Group(inner) => {
+            check_cond(inner, collected_vars, collected_errors)
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// Helpers shared by the integration tests.
+
+use neit::err_system::explain::explain;
+
+/// Every error code, retired ones included.
+pub fn error_codes() -> impl Iterator<Item = String> {
+    (1..=33).map(|number| format!("N{:04}", number))
+}
+
+/// The indented snippet under `heading` in a code's explanation, such as `Wrong:`.
+pub fn snippet(code: &str, heading: &str) -> Option<String> {
+    let text = explain(code).unwrap_or_else(|| panic!("{} has no explanation", code));
+    let body = text.split(heading).nth(1)?;
+    let mut lines = Vec::new();
+    for line in body.lines().skip(1) {
+        if !line.is_empty() && !line.starts_with("    ") {
+            break;
+        }
+        lines.push(line.strip_prefix("    ").unwrap_or(line));
+    }
+    Some(lines.join("\n").trim_end().to_string() + "\n")
+}
@@ -0,0 +1,89 @@
+// Tests for the library API: `parse_source` and `compile_to_c`.
+
+use common::snippet;
+use neit::{compile_to_c, parse_source, CompileOptions};
+
+mod common;
+
+/// The codes of the diagnostics `source` fails with, or nothing when it compiles.
+fn codes_of(source: &str) -> Vec<&'static str> {
+    match parse_source(source, &CompileOptions::default()) {
+        Ok(_) => Vec::new(),
+        Err(diagnostics) => diagnostics.iter().map(|d| d.code).collect(),
+    }
+}
+
+#[test]
+fn compiles_functions_under_a_prefixed_name() {
+    let source = "fn add(a: i32, b: i32) -> i32 {\n    return a + b\n}\nmay total = add(2, 3)\nprintln total is %total\n";
+    let c = compile_to_c(source, &CompileOptions::default()).expect("program compiles");
+    assert!(c.contains("nulibc.h"));
+    assert!(c.contains("neit_fn_add("));
+}
+
+#[test]
+fn returns_errors_as_diagnostics() {
+    let diagnostics = compile_to_c("println %missing\n", &CompileOptions::default())
+        .expect_err("an unknown name is an error");
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.code, "N0006");
+    assert_eq!(diagnostic.span.line, 1);
+    assert_eq!((diagnostic.span.start, diagnostic.span.end), (9, 16));
+}
+
+#[test]
+fn optimise_drops_unread_declarations() {
+    let source = "may unused = 1\nprintln done\n";
+    let kept = parse_source(source, &CompileOptions { optimise: false }).unwrap();
+    let pruned = parse_source(source, &CompileOptions::default()).unwrap();
+    assert_eq!(kept.len(), pruned.len() + 1);
+}
+
+#[test]
+fn explain_snippets_reproduce_their_codes() {
+    let mut checked = 0;
+    for code in common::error_codes() {
+        // Retired codes keep an entry but have nothing to reproduce.
+        let Some(wrong) = snippet(&code, "Wrong:") else {
+            continue;
+        };
+        assert!(
+            codes_of(&wrong).contains(&code.as_str()),
+            "{}: the wrong snippet gave {:?}\n{}",
+            code,
+            codes_of(&wrong),
+            wrong
+        );
+        checked += 1;
+    }
+    // Every code but the two retired ones has a snippet.
+    assert_eq!(checked, 31);
+}
+
+#[test]
+fn reports_a_failed_struct_literal_once() {
+    let source =
+        "struct P { x: f64, y: f64 }\nmay a = P { x: 1.0 }\nprintln %a.x and %a.y\nmay q = a.x\n";
+    let diagnostics = parse_source(source, &CompileOptions::default()).unwrap_err();
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].code, "N0010");
+    assert_eq!(diagnostics[0].label, "no value for field `y`");
+}
+
+#[test]
+fn reports_a_stray_closing_brace_and_goes_on() {
+    let source = "may x = 1\n}\nprintln %x %y\n";
+    assert_eq!(codes_of(source), ["N0011", "N0006"]);
+    // The `}` of a header reported for having no `{` is not reported again.
+    assert_eq!(
+        codes_of("may n = 0\nwhile n < 3\n    n += 1\n}\n"),
+        ["N0029"]
+    );
+}
+
+#[test]
+fn rejects_a_char_that_is_not_one_byte() {
+    assert_eq!(codes_of("may c: ch = 'é'\n"), ["N0004"]);
+    assert_eq!(codes_of("may c: ch = 'e'\nprintln %c\n"), [] as [&str; 0]);
+}
@@ -0,0 +1,79 @@
+// Tests for `neit fmt`: the canonical layout, and that formatting is stable and keeps meaning.
+
+use common::snippet;
+use neit::{compile_to_c, fmt_system::format_source, CompileOptions};
+
+mod common;
+
+const MESSY: &str = "# messy layout
+fn   add( a:i32,b : i32 )->i32{
+return a+b
+}
+const LIMIT:i32=10
+may xs=[1,2,3]
+for i in 0..LIMIT step 2{
+if i>4{
+println big  %i   ( a+b )  # trailing
+}else{
+    println small %i
+}
+}
+println %xs[0] and %len(xs)
+";
+
+const CANONICAL: &str = "# messy layout
+fn add(a: i32, b: i32) -> i32 {
+    return a + b
+}
+const LIMIT: i32 = 10
+may xs = [1, 2, 3]
+for i in 0..LIMIT step 2 {
+    if i > 4 {
+        println big  %i   ( a+b ) # trailing
+    } else {
+        println small %i
+    }
+}
+println %xs[0] and %len(xs)
+";
+
+fn format(code: &str) -> String {
+    format_source(code).unwrap_or_else(|| panic!("could not format:\n{}", code))
+}
+
+/// Valid programs to format: the messy one and every corrected snippet in the explanations.
+fn programs() -> Vec<String> {
+    let mut programs = vec![MESSY.to_string()];
+    programs.extend(common::error_codes().filter_map(|code| snippet(&code, "Corrected:")));
+    programs
+}
+
+#[test]
+fn formats_to_the_canonical_layout() {
+    assert_eq!(format(MESSY), CANONICAL);
+}
+
+#[test]
+fn keeps_print_text_as_written() {
+    let code = "println a  +  b # not a comment\nprintln 50% of %LIMIT\n";
+    assert_eq!(format(code), code);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for program in programs() {
+        let once = format(&program);
+        assert_eq!(format(&once), once, "formatting again changed:\n{}", once);
+    }
+}
+
+#[test]
+fn formatting_keeps_the_compiled_program() {
+    let options = CompileOptions::default();
+    for program in programs() {
+        let before = compile_to_c(&program, &options).ok();
+        assert!(before.is_some(), "does not compile:\n{}", program);
+        let after = compile_to_c(&format(&program), &options).ok();
+        assert_eq!(after, before, "formatting changed:\n{}", program);
+    }
+}
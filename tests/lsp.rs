@@ -0,0 +1,156 @@
+// Tests for `neit lsp`: framed requests go in, framed replies come out.
+
+use neit::lsp_system::{json::Json, serve_on};
+
+const URI: &str = "file:///test.nsc";
+
+/// A request (with an `id`) or a notification (without one) in `Content-Length` framing.
+fn frame(id: Option<usize>, method: &str, params: Json) -> String {
+    let mut message = vec![
+        ("jsonrpc".to_string(), Json::from("2.0")),
+        ("method".to_string(), Json::from(method)),
+        ("params".to_string(), params),
+    ];
+    if let Some(id) = id {
+        message.push(("id".to_string(), Json::from(id)));
+    }
+    let body = Json::Obj(message).to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+fn document(text: &str) -> Json {
+    Json::obj([(
+        "textDocument",
+        Json::obj([("uri", URI.into()), ("text", text.into())]),
+    )])
+}
+
+fn position(line: usize, character: usize) -> Json {
+    Json::obj([
+        ("textDocument", Json::obj([("uri", URI.into())])),
+        (
+            "position",
+            Json::obj([("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+/// Runs the server over `requests` and returns its exit code and the replies it wrote.
+fn run(requests: &[String]) -> (i32, Vec<Json>) {
+    let input = requests.concat();
+    let mut output = Vec::new();
+    let code = serve_on(&mut input.as_bytes(), &mut output);
+    let output = String::from_utf8(output).unwrap();
+    let replies = output
+        .split("Content-Length: ")
+        .skip(1)
+        .map(|message| {
+            let (_, body) = message.split_once("\r\n\r\n").unwrap();
+            Json::parse(body).expect("the server writes valid JSON")
+        })
+        .collect();
+    (code, replies)
+}
+
+/// The reply to the request with `id`.
+fn reply(replies: &[Json], id: usize) -> &Json {
+    replies
+        .iter()
+        .find(|reply| reply.get("id").and_then(Json::as_usize) == Some(id))
+        .unwrap_or_else(|| panic!("no reply to request {}", id))
+}
+
+/// The diagnostics of each `publishDiagnostics` notification, in order.
+fn published(replies: &[Json]) -> Vec<&[Json]> {
+    replies
+        .iter()
+        .filter(|reply| {
+            reply.get("method").and_then(Json::as_str) == Some("textDocument/publishDiagnostics")
+        })
+        .map(|reply| {
+            reply
+                .at(&["params", "diagnostics"])
+                .and_then(Json::as_array)
+                .unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn answers_requests_on_an_open_file() {
+    let code = "may count = 1\nprintln %count\n";
+    let (exit, replies) = run(&[
+        frame(Some(1), "initialize", Json::obj([])),
+        frame(None, "textDocument/didOpen", document(code)),
+        frame(Some(2), "textDocument/hover", position(1, 10)),
+        frame(Some(3), "textDocument/definition", position(1, 10)),
+        frame(Some(4), "textDocument/completion", position(1, 0)),
+        frame(Some(5), "shutdown", Json::Null),
+        frame(None, "exit", Json::Null),
+    ]);
+    assert_eq!(exit, 0);
+
+    let capabilities = reply(&replies, 1).at(&["result", "capabilities"]).unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(published(&replies), vec![&[] as &[Json]]);
+
+    let hover = reply(&replies, 2).at(&["result", "contents", "value"]);
+    let hover = hover.and_then(Json::as_str).unwrap();
+    assert_eq!(hover, "```neit\ncount: i32\n```");
+
+    let start = reply(&replies, 3)
+        .at(&["result", "range", "start"])
+        .unwrap();
+    assert_eq!(start.get("line").and_then(Json::as_usize), Some(0));
+    assert_eq!(start.get("character").and_then(Json::as_usize), Some(4));
+
+    let items = reply(&replies, 4).get("result").and_then(Json::as_array);
+    let labels: Vec<&str> = items
+        .unwrap()
+        .iter()
+        .filter_map(|item| item.get("label").and_then(Json::as_str))
+        .collect();
+    assert!(labels.contains(&"may"));
+    assert!(labels.contains(&"count"));
+
+    assert_eq!(reply(&replies, 5).get("result"), Some(&Json::Null));
+}
+
+#[test]
+fn publishes_diagnostics_as_the_file_changes() {
+    let change = Json::obj([
+        ("textDocument", Json::obj([("uri", URI.into())])),
+        (
+            "contentChanges",
+            Json::Arr(vec![Json::obj([("text", "println done\n".into())])]),
+        ),
+    ]);
+    let (_, replies) = run(&[
+        frame(None, "textDocument/didOpen", document("println %missing\n")),
+        frame(None, "textDocument/didChange", change),
+    ]);
+    let published = published(&replies);
+    assert_eq!(published.len(), 2);
+
+    let [diagnostic] = published[0] else {
+        panic!("expected one diagnostic: {:?}", published[0]);
+    };
+    assert_eq!(diagnostic.get("code").and_then(Json::as_str), Some("N0006"));
+    let start = diagnostic.at(&["range", "start"]).unwrap();
+    assert_eq!(start.get("character").and_then(Json::as_usize), Some(9));
+    assert!(published[1].is_empty());
+}
+
+#[test]
+fn rejects_unknown_requests_and_bad_json() {
+    let body = "{not json";
+    let (exit, replies) = run(&[
+        frame(Some(1), "textDocument/rename", Json::Null),
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body),
+    ]);
+    // The stream ended without a shutdown request.
+    assert_eq!(exit, 1);
+    let code = |reply: &Json| reply.at(&["error", "code"]).cloned();
+    assert_eq!(code(reply(&replies, 1)), Some(Json::Num(-32601.0)));
+    assert_eq!(code(&replies[1]), Some(Json::Num(-32700.0)));
+}
@@ -0,0 +1,65 @@
+// Tests for `neit repl`: lines go in on stdin, results come out on stdout.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs the REPL over `lines` and returns what it printed for each input, without the prompts
+/// and banner.
+fn repl(lines: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_neit"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("neit runs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(lines.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .replace("....> ", "neit> ")
+        .split("neit> ")
+        .skip(1)
+        .map(|answer| answer.trim_end().to_string())
+        .filter(|answer| !answer.is_empty())
+        .collect()
+}
+
+#[test]
+fn prints_the_value_of_an_expression() {
+    assert_eq!(repl("may x = 5\nx * 2\nx == 5\n"), ["10", "true"]);
+}
+
+#[test]
+fn assigns_instead_of_comparing() {
+    let lines = "may x = 5
+x = 7
+x
+x += 2
+x
+may b = true
+b = false
+b
+may xs = [1, 2]
+xs[0] = 9
+xs
+struct P { x: i32 }
+may p = P { x: 1 }
+p.x = 5
+p.x
+";
+    assert_eq!(repl(lines), ["7", "9", "false", "[9, 2]", "5"]);
+}
+
+#[test]
+fn keeps_blocks_open_until_they_close() {
+    let lines = "may total = 0\nfor i in 1..3 {\n    total += i\n}\ntotal\n";
+    assert_eq!(repl(lines), ["3"]);
+}
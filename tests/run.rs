@@ -0,0 +1,192 @@
+// Tests for `neit run`: programs run through the interpreter, with input given as a string and
+// output kept in memory.
+
+use neit::{
+    compile_to_c,
+    nulibc::{NULIBC, NULIBCH},
+    parse_source,
+    run_system::{interp::Interpreter, io::Io},
+    CompileOptions,
+};
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// What a program wrote to stdout and stderr, and the runtime error that stopped it, if any.
+struct Run {
+    stdout: String,
+    stderr: String,
+    error: Option<String>,
+}
+
+/// Runs `source` the way `neit run` does, reading `input` as stdin.
+fn run(source: &str, input: &str) -> Run {
+    let ast = parse_source(source, &CompileOptions { optimise: false })
+        .unwrap_or_else(|diagnostics| panic!("does not compile: {:?}", diagnostics));
+    let mut interp = Interpreter::with_io(Io::buffered(input));
+    let error = interp.run(&ast).err().map(|err| err.to_string());
+    Run {
+        stdout: interp.io().stdout().to_string(),
+        stderr: interp.io().stderr().to_string(),
+        error,
+    }
+}
+
+const PROGRAM: &str = "struct Point { x: f64, y: f64 }
+fn area(w: i32, h: i32) -> i32 {
+    return w * h
+}
+may p = Point { x: 1.5, y: -2.0 }
+may xs: [i32] = [3, 1, 2]
+push(xs, 7)
+may total = 0
+for i in 0..len(xs) {
+    total += xs[i]
+}
+may small: i8 = 127
+small += 1
+may big: f64 = 10000000000000000000.0
+may ok = area(3, 4) == 12
+println %p and %xs sum %total
+println %small %big %ok %area(2, 5)
+eprintln oops
+";
+
+#[test]
+fn prints_values_like_nprintf() {
+    let out = run(PROGRAM, "");
+    assert_eq!(
+        out.stdout,
+        "Point { x: 1.500000, y: -2.000000 } and [3, 1, 2, 7] sum 13\n\
+         -128 10000000000000000000.000000 true 10\n"
+    );
+    assert_eq!(out.stderr, "oops\n");
+    assert_eq!(out.error, None);
+}
+
+#[test]
+fn prints_a_line_left_to_right() {
+    let source = "fn loud(n: i32) -> i32 {
+    println called
+    return n
+}
+may q: [i32] = [1, 2, 3]
+println %len(q) %pop(q) %len(q)
+println a %loud(1) b
+";
+    assert_eq!(run(source, "").stdout, "3 3 2\na called\n1 b\n");
+}
+
+#[test]
+fn reads_input() {
+    let source = "input name\ninput age(i32)\ninput ready(bool)\nprintln %name is %age, %ready\n";
+    let out = run(source, "Ada\n36\ntrue\n");
+    assert_eq!(out.stdout, "Ada is 36, true\n");
+}
+
+#[test]
+fn stops_on_bad_input() {
+    let out = run("input n(i8)\nprintln %n\n", "300\n");
+    assert_eq!(out.stdout, "");
+    assert_eq!(
+        out.error.as_deref(),
+        Some("[!] Runtime error at line 1: expected i8 input, got \"300\"")
+    );
+}
+
+#[test]
+fn stops_on_division_by_zero() {
+    let out = run("may a = 1\nmay b = 0\nprintln before\nmay c = a / b\n", "");
+    assert_eq!(out.stdout, "before\n");
+    assert_eq!(
+        out.error.as_deref(),
+        Some("[!] Runtime error at line 4: division by zero")
+    );
+}
+
+#[test]
+fn stops_on_an_index_out_of_range() {
+    let out = run("may xs = [1, 2]\nmay i = 2\nprintln first %xs[i]\n", "");
+    assert_eq!(out.stdout, "first ");
+    assert_eq!(
+        out.error.as_deref(),
+        Some("[!] Runtime error at line 3: index 2 is out of range for an array of length 2")
+    );
+}
+
+#[test]
+fn stops_on_pop_from_an_empty_array() {
+    let out = run("may xs: [i32] = []\nmay x = pop(xs)\n", "");
+    assert_eq!(
+        out.error.as_deref(),
+        Some("[!] Runtime error at line 2: pop from an empty array")
+    );
+}
+
+/// Builds `source` with the system C compiler and runs it, or returns `None` when there is no
+/// compiler.
+fn run_built(name: &str, source: &str, input: &str) -> Option<String> {
+    Command::new("cc").arg("--version").output().ok()?;
+    let dir = std::env::temp_dir().join(format!("neit-run-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let c = compile_to_c(source, &CompileOptions::default()).unwrap();
+    fs::write(dir.join("main.c"), c).unwrap();
+    fs::write(dir.join("nulibc.c"), NULIBC).unwrap();
+    fs::write(dir.join("nulibc.h"), NULIBCH).unwrap();
+    let built = Command::new("cc")
+        .current_dir(&dir)
+        .args(["main.c", "nulibc.c", "-o", "main"])
+        .output()
+        .unwrap();
+    assert!(
+        built.status.success(),
+        "{}",
+        String::from_utf8_lossy(&built.stderr)
+    );
+    let mut child = Command::new(dir.join("main"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn matches_the_built_binary() {
+    let programs = [
+        ("values", PROGRAM, ""),
+        (
+            "order",
+            "may q: [i32] = [1, 2, 3]\nprintln %len(q) %pop(q) %len(q) %q\n",
+            "",
+        ),
+        (
+            "input",
+            "input n(i32)\ninput x(f64)\nmay y = x * 2.0\nprintln %n %x %y\n",
+            "-17\n0.1\n",
+        ),
+        (
+            "floats",
+            "may big: f64 = 123456789012345678.0\nmay z = 0.0\nmay inf = 1.0 / z\nprintln %big %inf\n",
+            "",
+        ),
+    ];
+    for (name, source, input) in programs {
+        let Some(built) = run_built(name, source, input) else {
+            eprintln!("no C compiler; skipped");
+            return;
+        };
+        assert_eq!(run(source, input).stdout, built, "{}", name);
+    }
+}